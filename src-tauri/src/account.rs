@@ -0,0 +1,196 @@
+// 资金账户：CThostFtdcTradingAccountField 到前端 AccountInfo 的转换，
+// 以及每个交易会话最近一次查询成功的资金快照
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use tauri_app_vue_lib::{char_flag_to_string, gb18030_cstr_to_str_i8, CThostFtdcTradingAccountField};
+
+/// 未指定币种时优先返回的币种
+pub const DEFAULT_CURRENCY_ID: &str = "CNY";
+
+/// 资金账户，字段与 types/ctp.ts 中的 AccountInfo 对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountInfo {
+    pub broker_id: String,
+    pub account_id: String,
+    pub pre_mortgage: f64,
+    pub pre_credit: f64,
+    pub pre_deposit: f64,
+    pub pre_balance: f64,
+    pub pre_margin: f64,
+    pub interest_base: f64,
+    pub interest: f64,
+    pub deposit: f64,
+    pub withdraw: f64,
+    pub frozen_margin: f64,
+    pub frozen_cash: f64,
+    pub frozen_commission: f64,
+    pub curr_margin: f64,
+    pub cash_in: f64,
+    pub commission: f64,
+    pub close_profit: f64,
+    pub position_profit: f64,
+    pub balance: f64,
+    pub available: f64,
+    pub withdraw_quota: f64,
+    pub reserve: f64,
+    pub trading_day: String,
+    pub settlement_id: i32,
+    pub credit: f64,
+    pub mortgage: f64,
+    pub exchange_margin: f64,
+    pub delivery_margin: f64,
+    pub exchange_delivery_margin: f64,
+    pub reserve_balance: f64,
+    pub currency_id: String,
+    pub pre_fund_mortgage_in: f64,
+    pub pre_fund_mortgage_out: f64,
+    pub fund_mortgage_in: f64,
+    pub fund_mortgage_out: f64,
+    pub fund_mortgage_available: f64,
+    pub mortgage_able_fund: f64,
+    pub spec_product_margin: f64,
+    pub spec_product_frozen_margin: f64,
+    pub spec_product_commission: f64,
+    pub spec_product_frozen_commission: f64,
+    pub spec_product_position_profit: f64,
+    pub spec_product_close_profit: f64,
+    pub spec_product_position_profit_by_alg: f64,
+    pub spec_product_exchange_margin: f64,
+    pub biz_type: String,
+    pub frozen_swap: f64,
+    pub remain_swap: f64,
+}
+
+impl From<&CThostFtdcTradingAccountField> for AccountInfo {
+    fn from(a: &CThostFtdcTradingAccountField) -> Self {
+        AccountInfo {
+            broker_id: gb18030_cstr_to_str_i8(&a.BrokerID).to_string(),
+            account_id: gb18030_cstr_to_str_i8(&a.AccountID).to_string(),
+            pre_mortgage: a.PreMortgage,
+            pre_credit: a.PreCredit,
+            pre_deposit: a.PreDeposit,
+            pre_balance: a.PreBalance,
+            pre_margin: a.PreMargin,
+            interest_base: a.InterestBase,
+            interest: a.Interest,
+            deposit: a.Deposit,
+            withdraw: a.Withdraw,
+            frozen_margin: a.FrozenMargin,
+            frozen_cash: a.FrozenCash,
+            frozen_commission: a.FrozenCommission,
+            curr_margin: a.CurrMargin,
+            cash_in: a.CashIn,
+            commission: a.Commission,
+            close_profit: a.CloseProfit,
+            position_profit: a.PositionProfit,
+            balance: a.Balance,
+            available: a.Available,
+            withdraw_quota: a.WithdrawQuota,
+            reserve: a.Reserve,
+            trading_day: gb18030_cstr_to_str_i8(&a.TradingDay).to_string(),
+            settlement_id: a.SettlementID,
+            credit: a.Credit,
+            mortgage: a.Mortgage,
+            exchange_margin: a.ExchangeMargin,
+            delivery_margin: a.DeliveryMargin,
+            exchange_delivery_margin: a.ExchangeDeliveryMargin,
+            reserve_balance: a.ReserveBalance,
+            currency_id: gb18030_cstr_to_str_i8(&a.CurrencyID).to_string(),
+            pre_fund_mortgage_in: a.PreFundMortgageIn,
+            pre_fund_mortgage_out: a.PreFundMortgageOut,
+            fund_mortgage_in: a.FundMortgageIn,
+            fund_mortgage_out: a.FundMortgageOut,
+            fund_mortgage_available: a.FundMortgageAvailable,
+            mortgage_able_fund: a.MortgageableFund,
+            spec_product_margin: a.SpecProductMargin,
+            spec_product_frozen_margin: a.SpecProductFrozenMargin,
+            spec_product_commission: a.SpecProductCommission,
+            spec_product_frozen_commission: a.SpecProductFrozenCommission,
+            spec_product_position_profit: a.SpecProductPositionProfit,
+            spec_product_close_profit: a.SpecProductCloseProfit,
+            spec_product_position_profit_by_alg: a.SpecProductPositionProfitByAlg,
+            spec_product_exchange_margin: a.SpecProductExchangeMargin,
+            biz_type: char_flag_to_string(a.BizType),
+            frozen_swap: a.FrozenSwap,
+            remain_swap: a.RemainSwap,
+        }
+    }
+}
+
+/// 一次资金查询的全部币种，以及查询成功的时间（毫秒时间戳）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    pub accounts: Vec<AccountInfo>,
+    pub updated_at: i64,
+}
+
+impl AccountSnapshot {
+    pub fn new(accounts: Vec<AccountInfo>) -> Self {
+        AccountSnapshot {
+            accounts,
+            updated_at: chrono::Local::now().timestamp_millis(),
+        }
+    }
+
+    /// 按币种取账户；未指定时优先人民币，没有人民币账户时取第一条
+    pub fn select(&self, currency_id: Option<&str>) -> Option<&AccountInfo> {
+        match currency_id.filter(|id| !id.is_empty()) {
+            Some(id) => self.accounts.iter().find(|a| a.currency_id == id),
+            None => self
+                .accounts
+                .iter()
+                .find(|a| a.currency_id == DEFAULT_CURRENCY_ID)
+                .or_else(|| self.accounts.first()),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    // 每个交易会话最近一次成功的资金快照
+    static ref ACCOUNT_SNAPSHOTS: Arc<Mutex<HashMap<String, AccountSnapshot>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+pub fn store_snapshot(session_id: &str, snapshot: AccountSnapshot) {
+    ACCOUNT_SNAPSHOTS
+        .lock()
+        .unwrap()
+        .insert(session_id.to_string(), snapshot);
+}
+
+pub fn cached_snapshot(session_id: &str) -> Option<AccountSnapshot> {
+    ACCOUNT_SNAPSHOTS.lock().unwrap().get(session_id).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(currency_id: &str, available: f64) -> AccountInfo {
+        AccountInfo {
+            currency_id: currency_id.to_string(),
+            available,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn selects_requested_currency() {
+        let snapshot = AccountSnapshot::new(vec![account("USD", 10.0), account("CNY", 20.0)]);
+        assert_eq!(snapshot.select(Some("USD")).unwrap().available, 10.0);
+        assert!(snapshot.select(Some("HKD")).is_none());
+    }
+
+    #[test]
+    fn defaults_to_cny_then_first_row() {
+        let snapshot = AccountSnapshot::new(vec![account("USD", 10.0), account("CNY", 20.0)]);
+        assert_eq!(snapshot.select(None).unwrap().currency_id, "CNY");
+        assert_eq!(snapshot.select(Some("")).unwrap().currency_id, "CNY");
+
+        let snapshot = AccountSnapshot::new(vec![account("USD", 10.0)]);
+        assert_eq!(snapshot.select(None).unwrap().currency_id, "USD");
+    }
+}
@@ -0,0 +1,597 @@
+// 算法单：TWAP 在时间窗口内按切片均匀报出，冰山单每次只挂出一笔显示数量，成交后再补下一笔。
+// 子单都是限价单，暂停和撤销时撤掉挂单；
+// 成交和报单回报到达时由交易事件泵调用 on_spi_output 推进
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use tauri_app_vue_lib::trader_api::CThostFtdcTraderSpiOutput;
+use tauri_app_vue_lib::{
+    char_flag_to_string, gb18030_cstr_to_str_i8, THOST_FTDC_OSS_InsertRejected, THOST_FTDC_OST_Canceled,
+};
+
+use crate::ctp_commands::{await_cancel, get_next_request_id, send_cancel, send_order, OrderRequest};
+use crate::ctp_flags::{Direction, HedgeFlag, OffsetFlag};
+use crate::order_store::{self, OrderKey};
+use crate::trader_events::{self, OrderInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlgoKind {
+    /// 在 duration_secs 内分 slices 次均匀报出
+    Twap,
+    /// 每次只挂出 clip_size，全部成交后再报下一笔
+    Iceberg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlgoStatus {
+    Running,
+    /// 已撤掉挂单，不再报出新子单，可恢复
+    Paused,
+    Completed,
+    Canceled,
+    /// 子单被拒、未能发出或在算法单之外结束
+    Failed,
+}
+
+/// 前端提交的算法单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlgoRequest {
+    pub instrument_id: String,
+    pub direction: Direction,
+    /// 子单的限价
+    pub price: f64,
+    pub volume: i32,
+    pub offset_flag: Option<OffsetFlag>,
+    pub hedge_flag: Option<HedgeFlag>,
+    pub kind: AlgoKind,
+    /// 冰山单每次显示的数量
+    pub clip_size: Option<i32>,
+    /// TWAP 的执行时长和切片数
+    pub duration_secs: Option<u64>,
+    pub slices: Option<u32>,
+}
+
+/// 算法单下的一笔子单及其最新状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlgoChild {
+    pub key: OrderKey,
+    pub volume: i32,
+    pub order_status: String,
+    pub filled_volume: i32,
+    /// 报单被拒时的原因
+    pub error: Option<String>,
+    /// 由暂停或撤销算法单撤掉，恢复时可以补报
+    pub canceled_by_algo: bool,
+}
+
+/// 一笔算法单（父单）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlgoOrder {
+    pub algo_id: String,
+    pub instrument_id: String,
+    pub direction: Direction,
+    pub price: f64,
+    pub volume: i32,
+    pub offset_flag: Option<OffsetFlag>,
+    pub hedge_flag: Option<HedgeFlag>,
+    pub kind: AlgoKind,
+    pub clip_size: Option<i32>,
+    pub duration_secs: Option<u64>,
+    pub slices: Option<u32>,
+    pub filled_volume: i32,
+    /// 已报出、尚未成交且仍在挂单的数量
+    pub working_volume: i32,
+    pub status: AlgoStatus,
+    pub created_at: i64,
+    pub children: Vec<AlgoChild>,
+    pub error: Option<String>,
+}
+
+pub fn validate(request: &AlgoRequest) -> Result<(), String> {
+    if request.volume <= 0 {
+        return Err(format!("算法单数量必须大于 0: {}", request.volume));
+    }
+    if request.price <= 0.0 {
+        return Err(format!("无效的报单价格: {}", request.price));
+    }
+    match request.kind {
+        AlgoKind::Iceberg if request.clip_size.is_none_or(|clip| clip <= 0) => {
+            Err("冰山单需要大于 0 的显示数量".to_string())
+        }
+        AlgoKind::Twap if request.duration_secs.is_none_or(|secs| secs == 0) => {
+            Err("TWAP 需要大于 0 的执行时长".to_string())
+        }
+        AlgoKind::Twap if request.slices.is_none_or(|slices| slices == 0) => {
+            Err("TWAP 需要至少 1 个切片".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+impl AlgoOrder {
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, AlgoStatus::Running | AlgoStatus::Paused)
+    }
+
+    // TWAP 相邻切片的间隔（毫秒）
+    fn slice_interval_ms(&self) -> i64 {
+        let slices = self.slices.unwrap_or(1).max(1) as i64;
+        (self.duration_secs.unwrap_or(0) as i64 * 1000 / slices).max(1)
+    }
+
+    /// 到 now 为止按计划应报出的累计数量；冰山单不限时间
+    pub fn due_volume(&self, now: i64) -> i32 {
+        match self.kind {
+            AlgoKind::Iceberg => self.volume,
+            AlgoKind::Twap => {
+                let slices = self.slices.unwrap_or(1).max(1) as i64;
+                let due = ((now - self.created_at).max(0) / self.slice_interval_ms() + 1).min(slices);
+                (self.volume as i64 * due / slices) as i32
+            }
+        }
+    }
+
+    /// 现在应报出的子单数量，0 表示不报
+    pub fn next_child_volume(&self, now: i64) -> i32 {
+        if self.status != AlgoStatus::Running {
+            return 0;
+        }
+        let unsent = self.volume - self.filled_volume - self.working_volume;
+        if unsent <= 0 {
+            return 0;
+        }
+        match self.kind {
+            AlgoKind::Iceberg if self.working_volume > 0 => 0,
+            AlgoKind::Iceberg => self.clip_size.unwrap_or(unsent).min(unsent),
+            AlgoKind::Twap => (self.due_volume(now) - self.filled_volume - self.working_volume).max(0),
+        }
+    }
+
+    /// 按子单状态汇总成交和挂单数量，判断是否全部成交、有子单被拒或子单在算法单之外结束
+    pub fn settle(&mut self) {
+        self.filled_volume = self.children.iter().map(|child| child.filled_volume).sum();
+        self.working_volume = self
+            .children
+            .iter()
+            .filter(|child| !order_store::is_final_status(&child.order_status))
+            .map(|child| (child.volume - child.filled_volume).max(0))
+            .sum();
+        if !self.is_active() {
+            return;
+        }
+        if let Some(error) = self.children.iter().find_map(|child| child.error.clone()) {
+            self.status = AlgoStatus::Failed;
+            self.error = Some(error);
+        } else if self.filled_volume >= self.volume {
+            self.status = AlgoStatus::Completed;
+        } else if let Some(child) = self.children.iter().find(|child| {
+            !child.canceled_by_algo
+                && order_store::is_final_status(&child.order_status)
+                && child.filled_volume < child.volume
+        }) {
+            // 手工撤单或一键撤单撤掉的子单不能再补报，整笔算法单随之结束
+            let canceled = child.order_status == char_flag_to_string(THOST_FTDC_OST_Canceled as i8);
+            self.status = if canceled { AlgoStatus::Canceled } else { AlgoStatus::Failed };
+            self.error = Some(format!("子单 {} 在算法单之外结束，算法单停止", child.key.order_ref));
+        }
+    }
+
+    /// 从报单簿刷新子单状态
+    fn refresh(&mut self, session_id: &str) {
+        order_store::with_book(session_id, |book| {
+            for child in &mut self.children {
+                if let Some(managed) = book.get_by_key(&child.key) {
+                    child.order_status = managed.order.order_status.clone();
+                    child.filled_volume = managed.filled_volume;
+                    if managed.order.order_submit_status
+                        == char_flag_to_string(THOST_FTDC_OSS_InsertRejected as i8)
+                    {
+                        child.error = Some(format!("子单 {} 被拒: {}", child.key.order_ref, managed.order.status_msg));
+                    }
+                }
+            }
+        });
+        self.settle();
+    }
+
+    // 仍在挂单的子单
+    fn working_orders(&self, session_id: &str) -> Vec<OrderInfo> {
+        order_store::with_book(session_id, |book| {
+            self.children
+                .iter()
+                .filter_map(|child| book.get_by_key(&child.key))
+                .map(|managed| managed.order.clone())
+                .filter(|order| !order_store::is_final_status(&order.order_status))
+                .collect()
+        })
+    }
+
+    // 记下由算法单自己撤掉的子单
+    fn mark_canceled(&mut self, targets: &[OrderInfo]) {
+        for child in &mut self.children {
+            if targets.iter().any(|target| OrderKey::of(target) == child.key) {
+                child.canceled_by_algo = true;
+            }
+        }
+    }
+
+    // 算法单失败或被外部撤单结束后，撤掉其余仍在挂单的子单
+    fn cancel_remaining(&mut self, session_id: &str) {
+        let targets = self.working_orders(session_id);
+        if targets.is_empty() {
+            return;
+        }
+        self.mark_canceled(&targets);
+        println!("📤 [DEBUG] Algo order {} stopped, canceling {} remaining children", self.algo_id, targets.len());
+        let session_id = session_id.to_string();
+        let algo_id = self.algo_id.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(error) = cancel_working(&session_id, targets).await {
+                println!("❌ [ERROR] Algo order {} remaining children not canceled: {}", algo_id, error);
+            }
+        });
+    }
+
+    // 刷新后按计划报出下一笔子单
+    fn step(&mut self, session_id: &str, now: i64) {
+        let active = self.is_active();
+        self.refresh(session_id);
+        if active && !self.is_active() {
+            self.cancel_remaining(session_id);
+        }
+        let volume = self.next_child_volume(now);
+        if volume <= 0 {
+            return;
+        }
+        let order = OrderRequest::limit(&self.instrument_id, self.direction, self.price, volume)
+            .with_flags(self.offset_flag, self.hedge_flag);
+        println!("📤 [DEBUG] Algo order {} sending child of {} lots", self.algo_id, volume);
+        match send_order(session_id, &order, false) {
+            Ok(pending) => {
+                self.children.push(AlgoChild {
+                    key: pending.key,
+                    volume,
+                    order_status: String::new(),
+                    filled_volume: 0,
+                    error: None,
+                    canceled_by_algo: false,
+                });
+                self.settle();
+            }
+            Err(error) => {
+                println!("❌ [ERROR] Algo order {} child not sent: {}", self.algo_id, error);
+                self.status = AlgoStatus::Failed;
+                self.error = Some(error);
+                self.cancel_remaining(session_id);
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    // 每个交易会话当日的算法单
+    static ref ALGO_ORDERS: Arc<Mutex<HashMap<String, Vec<AlgoOrder>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+// 在锁内找到算法单并处理；子单在锁内发出，避免两次回报同时补单
+fn with_algo<R>(
+    session_id: &str,
+    algo_id: &str,
+    f: impl FnOnce(&mut AlgoOrder) -> Result<R, String>,
+) -> Result<R, String> {
+    let mut algos = ALGO_ORDERS.lock().unwrap();
+    let algo = algos
+        .get_mut(session_id)
+        .and_then(|session| session.iter_mut().find(|a| a.algo_id == algo_id))
+        .ok_or_else(|| format!("未找到算法单 {}", algo_id))?;
+    f(algo)
+}
+
+// TWAP 按切片间隔定时推进，算法单结束后停止
+fn spawn_schedule(app: AppHandle, session_id: String, algo: &AlgoOrder) {
+    let algo_id = algo.algo_id.clone();
+    let slices = algo.slices.unwrap_or(1);
+    let interval = Duration::from_millis(algo.slice_interval_ms() as u64);
+    tauri::async_runtime::spawn(async move {
+        let started = tokio::time::Instant::now();
+        for slice in 1..slices {
+            tokio::time::sleep_until(started + interval * slice).await;
+            let now = chrono::Local::now().timestamp_millis();
+            let Ok(algo) = with_algo(&session_id, &algo_id, |algo| {
+                algo.step(&session_id, now);
+                Ok(algo.clone())
+            }) else {
+                break;
+            };
+            let active = algo.is_active();
+            trader_events::emit_algo_order(&app, algo);
+            if !active {
+                break;
+            }
+        }
+    });
+}
+
+/// 报出首笔子单并开始执行；TWAP 同时启动切片定时器
+pub fn start(app: &AppHandle, session_id: &str, request: AlgoRequest) -> Result<AlgoOrder, String> {
+    validate(&request)?;
+    let created_at = chrono::Local::now().timestamp_millis();
+    let mut algo = AlgoOrder {
+        algo_id: format!("algo_{}_{}", created_at, get_next_request_id()),
+        instrument_id: request.instrument_id,
+        direction: request.direction,
+        price: request.price,
+        volume: request.volume,
+        offset_flag: request.offset_flag,
+        hedge_flag: request.hedge_flag,
+        kind: request.kind,
+        clip_size: request.clip_size,
+        duration_secs: request.duration_secs,
+        slices: request.slices,
+        filled_volume: 0,
+        working_volume: 0,
+        status: AlgoStatus::Running,
+        created_at,
+        children: Vec::new(),
+        error: None,
+    };
+
+    let mut algos = ALGO_ORDERS.lock().unwrap();
+    algo.step(session_id, created_at);
+    if algo.status == AlgoStatus::Failed && algo.children.is_empty() {
+        return Err(algo.error.unwrap_or_default());
+    }
+    algos.entry(session_id.to_string()).or_default().push(algo.clone());
+    drop(algos);
+
+    if algo.kind == AlgoKind::Twap {
+        spawn_schedule(app.clone(), session_id.to_string(), &algo);
+    }
+    Ok(algo)
+}
+
+/// 交易事件泵收到的每条消息都交给这里：成交或报单状态变化时推进同合约的算法单，返回有变化的算法单
+pub fn on_spi_output(session_id: &str, msg: &CThostFtdcTraderSpiOutput) -> Vec<AlgoOrder> {
+    let instrument_id = match msg {
+        CThostFtdcTraderSpiOutput::OnRtnTrade(p) => p.p_trade.as_ref().map(|t| &t.InstrumentID),
+        CThostFtdcTraderSpiOutput::OnRtnOrder(p) => p.p_order.as_ref().map(|o| &o.InstrumentID),
+        _ => None,
+    };
+    let Some(instrument_id) = instrument_id.map(|id| gb18030_cstr_to_str_i8(id).to_string()) else {
+        return Vec::new();
+    };
+
+    let now = chrono::Local::now().timestamp_millis();
+    let mut algos = ALGO_ORDERS.lock().unwrap();
+    let Some(session) = algos.get_mut(session_id) else {
+        return Vec::new();
+    };
+    let mut changed = Vec::new();
+    for algo in session
+        .iter_mut()
+        .filter(|a| a.is_active() && a.instrument_id == instrument_id)
+    {
+        let before = (algo.status, algo.filled_volume, algo.working_volume, algo.children.len());
+        algo.step(session_id, now);
+        if (algo.status, algo.filled_volume, algo.working_volume, algo.children.len()) != before {
+            changed.push(algo.clone());
+        }
+    }
+    changed
+}
+
+// 撤掉一组挂单，等待全部撤单结果
+async fn cancel_working(session_id: &str, targets: Vec<OrderInfo>) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + order_store::ORDER_ACK_TIMEOUT;
+    let results = futures::future::join_all(targets.iter().map(|target| async move {
+        let pending = send_cancel(session_id, target)?;
+        await_cancel(session_id, pending, deadline).await
+    }))
+    .await;
+    let errors: Vec<String> = results.into_iter().filter_map(Result::err).collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+// 暂停或撤销：先改状态停止补单，再撤掉全部挂单；已结束的算法单只撤掉剩余挂单
+async fn stop(session_id: &str, algo_id: &str, status: AlgoStatus) -> Result<AlgoOrder, String> {
+    let targets = with_algo(session_id, algo_id, |algo| {
+        let targets = algo.working_orders(session_id);
+        let allowed = match status {
+            AlgoStatus::Paused => algo.status == AlgoStatus::Running,
+            // 已失败的算法单若还有子单在挂单，也允许撤销以撤掉这些子单
+            _ => algo.is_active() || !targets.is_empty(),
+        };
+        if !allowed {
+            return Err(format!("算法单 {} 当前状态为 {:?}，无法执行", algo_id, algo.status));
+        }
+        if algo.is_active() {
+            algo.status = status;
+        }
+        algo.mark_canceled(&targets);
+        Ok(targets)
+    })?;
+    println!("📤 [DEBUG] Algo order {} -> {:?}, canceling {} working orders", algo_id, status, targets.len());
+    let canceled = cancel_working(session_id, targets).await;
+
+    with_algo(session_id, algo_id, |algo| {
+        algo.refresh(session_id);
+        canceled.map(|_| algo.clone())
+    })
+}
+
+pub async fn pause(session_id: &str, algo_id: &str) -> Result<AlgoOrder, String> {
+    stop(session_id, algo_id, AlgoStatus::Paused).await
+}
+
+pub async fn cancel(session_id: &str, algo_id: &str) -> Result<AlgoOrder, String> {
+    stop(session_id, algo_id, AlgoStatus::Canceled).await
+}
+
+/// 恢复暂停的算法单，立即按计划补报；TWAP 暂停期间错过的切片一并报出
+pub fn resume(session_id: &str, algo_id: &str) -> Result<AlgoOrder, String> {
+    with_algo(session_id, algo_id, |algo| {
+        if algo.status != AlgoStatus::Paused {
+            return Err(format!("算法单 {} 未暂停", algo_id));
+        }
+        algo.status = AlgoStatus::Running;
+        algo.step(session_id, chrono::Local::now().timestamp_millis());
+        Ok(algo.clone())
+    })
+}
+
+/// 本会话的全部算法单，子单状态取自报单簿
+pub fn list(session_id: &str) -> Vec<AlgoOrder> {
+    let mut algos = ALGO_ORDERS.lock().unwrap();
+    let Some(session) = algos.get_mut(session_id) else {
+        return Vec::new();
+    };
+    for algo in session.iter_mut() {
+        algo.refresh(session_id);
+    }
+    session.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn algo(kind: AlgoKind, volume: i32) -> AlgoOrder {
+        AlgoOrder {
+            algo_id: "a1".to_string(),
+            instrument_id: "rb2505".to_string(),
+            direction: Direction::Buy,
+            price: 3500.0,
+            volume,
+            offset_flag: None,
+            hedge_flag: None,
+            kind,
+            clip_size: Some(3),
+            duration_secs: Some(60),
+            slices: Some(4),
+            filled_volume: 0,
+            working_volume: 0,
+            status: AlgoStatus::Running,
+            created_at: 0,
+            children: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn child(volume: i32, order_status: &str, filled_volume: i32) -> AlgoChild {
+        AlgoChild {
+            key: OrderKey {
+                front_id: 1,
+                session_id: 1,
+                order_ref: "1".to_string(),
+            },
+            volume,
+            order_status: order_status.to_string(),
+            filled_volume,
+            error: None,
+            canceled_by_algo: false,
+        }
+    }
+
+    #[test]
+    fn twap_releases_volume_slice_by_slice() {
+        let twap = algo(AlgoKind::Twap, 10);
+        assert_eq!(twap.due_volume(0), 2);
+        assert_eq!(twap.due_volume(15_000), 5);
+        assert_eq!(twap.due_volume(44_999), 7);
+        assert_eq!(twap.due_volume(45_000), 10);
+        assert_eq!(twap.due_volume(600_000), 10);
+
+        let mut working = twap.clone();
+        working.children.push(child(2, "3", 0));
+        working.settle();
+        assert_eq!(working.working_volume, 2);
+        assert_eq!(working.next_child_volume(0), 0);
+        assert_eq!(working.next_child_volume(15_000), 3);
+    }
+
+    #[test]
+    fn iceberg_shows_one_clip_at_a_time() {
+        let mut iceberg = algo(AlgoKind::Iceberg, 7);
+        assert_eq!(iceberg.next_child_volume(0), 3);
+
+        iceberg.children.push(child(3, "1", 1));
+        iceberg.settle();
+        assert_eq!(iceberg.next_child_volume(0), 0);
+
+        iceberg.children[0] = child(3, "0", 3);
+        iceberg.children.push(child(3, "0", 3));
+        iceberg.settle();
+        assert_eq!(iceberg.filled_volume, 6);
+        assert_eq!(iceberg.next_child_volume(0), 1);
+
+        iceberg.status = AlgoStatus::Paused;
+        assert_eq!(iceberg.next_child_volume(0), 0);
+    }
+
+    #[test]
+    fn settles_to_completed_or_failed() {
+        let mut done = algo(AlgoKind::Iceberg, 3);
+        done.children.push(child(3, "0", 3));
+        done.settle();
+        assert_eq!(done.status, AlgoStatus::Completed);
+
+        let mut rejected = algo(AlgoKind::Iceberg, 3);
+        let mut bad = child(3, "5", 0);
+        bad.error = Some("资金不足".to_string());
+        rejected.children.push(bad);
+        rejected.settle();
+        assert_eq!(rejected.status, AlgoStatus::Failed);
+        assert_eq!(rejected.working_volume, 0);
+    }
+
+    #[test]
+    fn child_canceled_outside_the_algo_stops_it() {
+        let mut iceberg = algo(AlgoKind::Iceberg, 7);
+        iceberg.children.push(child(3, "5", 1));
+        iceberg.settle();
+        assert_eq!(iceberg.status, AlgoStatus::Canceled);
+        assert!(iceberg.error.is_some());
+        assert_eq!(iceberg.next_child_volume(0), 0);
+
+        let mut paused = algo(AlgoKind::Iceberg, 7);
+        paused.status = AlgoStatus::Paused;
+        let mut own = child(3, "5", 1);
+        own.canceled_by_algo = true;
+        paused.children.push(own);
+        paused.settle();
+        assert_eq!(paused.status, AlgoStatus::Paused);
+        paused.status = AlgoStatus::Running;
+        assert_eq!(paused.next_child_volume(0), 3);
+    }
+
+    #[test]
+    fn validates_algo_parameters() {
+        let request = |kind, clip_size, slices| AlgoRequest {
+            instrument_id: "rb2505".to_string(),
+            direction: Direction::Sell,
+            price: 3500.0,
+            volume: 10,
+            offset_flag: None,
+            hedge_flag: None,
+            kind,
+            clip_size,
+            duration_secs: Some(60),
+            slices,
+        };
+        assert!(validate(&request(AlgoKind::Iceberg, Some(2), None)).is_ok());
+        assert!(validate(&request(AlgoKind::Iceberg, None, None)).is_err());
+        assert!(validate(&request(AlgoKind::Twap, None, Some(5))).is_ok());
+        assert!(validate(&request(AlgoKind::Twap, None, Some(0))).is_err());
+    }
+}
@@ -0,0 +1,444 @@
+// 撤单次数：交易所按合约、按交易日限制撤单次数。这里根据 OnRtnOrder 中确认的撤单状态计数，
+// 按账户写到磁盘，达到预警值时提醒、达到上限后拒绝继续撤单，并把最新计数推送给所有窗口。
+// 已发出尚未确认的撤单先占用次数，批量撤单时不会越过上限
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use tauri_app_vue_lib::trader_api::CThostFtdcTraderSpiOutput;
+use tauri_app_vue_lib::{
+    char_flag_to_string, THOST_FTDC_OSS_InsertRejected, THOST_FTDC_OST_Canceled, THOST_FTDC_TC_IOC,
+};
+
+use crate::order_store::{self, OrderKey};
+use crate::trader_events::OrderInfo;
+
+const STORE_FILE: &str = "cancel_counts.json";
+const LIMITS_FILE: &str = "cancel_limits.json";
+
+/// 撤单模式，与交易面板配置中的 CancelMode 对应
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelMode {
+    /// 达到上限后拒绝撤单
+    #[default]
+    Limited,
+    /// 只计数，不限制
+    Unlimited,
+}
+
+/// 撤单次数上限和预警值，作用于每个合约；按账户写到磁盘，重启后沿用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CancelLimits {
+    pub max_cancels: u32,
+    pub warn_at: u32,
+    pub mode: CancelMode,
+}
+
+impl Default for CancelLimits {
+    fn default() -> Self {
+        CancelLimits {
+            max_cancels: 489,
+            warn_at: 400,
+            mode: CancelMode::Limited,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaLevel {
+    Normal,
+    Warning,
+    Blocked,
+}
+
+/// 某个合约当日的撤单次数，推送给前端
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CancelCount {
+    pub instrument_id: String,
+    pub trading_day: String,
+    pub count: u32,
+    pub max_cancels: u32,
+    pub warn_at: u32,
+    pub level: QuotaLevel,
+}
+
+/// 某个账户在某个交易日的撤单记录
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyCancels {
+    pub trading_day: String,
+    pub counts: HashMap<String, u32>,
+    // 已计数的报单 "ExchangeID:OrderSysID"，私有流重传同一撤单回报时不重复计数
+    pub counted: HashSet<String>,
+}
+
+impl DailyCancels {
+    /// 记录一次确认的撤单，返回该合约最新的次数；已计数过的报单返回 None
+    pub fn record(&mut self, order: &OrderInfo) -> Option<u32> {
+        if !self
+            .counted
+            .insert(format!("{}:{}", order.exchange_id, order.order_sys_id))
+        {
+            return None;
+        }
+        let count = self.counts.entry(order.instrument_id.clone()).or_default();
+        *count += 1;
+        Some(*count)
+    }
+
+    pub fn count(&self, instrument_id: &str) -> u32 {
+        self.counts.get(instrument_id).copied().unwrap_or(0)
+    }
+}
+
+/// 是否是需要计数的撤单：已撤单、非交易所拒单，且不是 FAK/FOK 未成交部分的自动撤销
+pub fn is_counted_cancel(order: &OrderInfo) -> bool {
+    order.order_status == char_flag_to_string(THOST_FTDC_OST_Canceled as i8)
+        && order.order_submit_status != char_flag_to_string(THOST_FTDC_OSS_InsertRejected as i8)
+        && order.time_condition != char_flag_to_string(THOST_FTDC_TC_IOC as i8)
+        && !order.order_sys_id.is_empty()
+}
+
+pub fn level(count: u32, limits: &CancelLimits) -> QuotaLevel {
+    if limits.mode == CancelMode::Unlimited {
+        QuotaLevel::Normal
+    } else if count >= limits.max_cancels {
+        QuotaLevel::Blocked
+    } else if count >= limits.warn_at {
+        QuotaLevel::Warning
+    } else {
+        QuotaLevel::Normal
+    }
+}
+
+// 单个交易会话的计数状态
+struct SessionQuota {
+    account_key: String,
+    limits: CancelLimits,
+    cancels: DailyCancels,
+    // 已发出、尚未确认的撤单，记下报单所属合约
+    in_flight: HashMap<OrderKey, String>,
+}
+
+impl SessionQuota {
+    fn new(account_key: &str, limits: CancelLimits, cancels: DailyCancels) -> Self {
+        SessionQuota {
+            account_key: account_key.to_string(),
+            limits,
+            cancels,
+            in_flight: HashMap::new(),
+        }
+    }
+
+    fn in_flight(&self, instrument_id: &str) -> u32 {
+        self.in_flight.values().filter(|id| *id == instrument_id).count() as u32
+    }
+
+    // 已确认和在途的撤单合计达到上限时拒绝
+    fn check(&self, instrument_id: &str) -> Result<(), String> {
+        let confirmed = self.cancels.count(instrument_id);
+        let in_flight = self.in_flight(instrument_id);
+        if self.limits.mode == CancelMode::Limited && confirmed + in_flight >= self.limits.max_cancels {
+            return Err(format!(
+                "{} 当日撤单次数已达上限 {}/{}（其中 {} 笔等待确认）",
+                instrument_id,
+                confirmed + in_flight,
+                self.limits.max_cancels,
+                in_flight
+            ));
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self, instrument_id: &str) -> CancelCount {
+        let count = self.cancels.count(instrument_id);
+        CancelCount {
+            instrument_id: instrument_id.to_string(),
+            trading_day: self.cancels.trading_day.clone(),
+            count,
+            max_cancels: self.limits.max_cancels,
+            warn_at: self.limits.warn_at,
+            level: level(count, &self.limits),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref QUOTAS: Arc<Mutex<HashMap<String, SessionQuota>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // 计数文件所在目录，启动交易登录时设置
+    static ref STORE_DIR: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
+}
+
+pub fn set_store_dir(dir: Option<PathBuf>) {
+    *STORE_DIR.lock().unwrap() = dir;
+}
+
+fn load_all(dir: &Path) -> HashMap<String, DailyCancels> {
+    fs::read_to_string(dir.join(STORE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(dir: &Path, account_key: &str, cancels: &DailyCancels) -> io::Result<()> {
+    let mut all = load_all(dir);
+    all.insert(account_key.to_string(), cancels.clone());
+    fs::create_dir_all(dir)?;
+    let content = serde_json::to_string_pretty(&all).map_err(io::Error::other)?;
+    fs::write(dir.join(STORE_FILE), content)
+}
+
+fn load_all_limits(dir: &Path) -> HashMap<String, CancelLimits> {
+    fs::read_to_string(dir.join(LIMITS_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_limits(dir: &Path, account_key: &str, limits: &CancelLimits) -> io::Result<()> {
+    let mut all = load_all_limits(dir);
+    all.insert(account_key.to_string(), limits.clone());
+    fs::create_dir_all(dir)?;
+    let content = serde_json::to_string_pretty(&all).map_err(io::Error::other)?;
+    fs::write(dir.join(LIMITS_FILE), content)
+}
+
+/// 磁盘上该账户的记录，交易日不同时从零开始
+pub fn load(dir: Option<&Path>, account_key: &str, trading_day: &str) -> DailyCancels {
+    dir.and_then(|dir| load_all(dir).remove(account_key))
+        .filter(|cancels| cancels.trading_day == trading_day)
+        .unwrap_or_else(|| DailyCancels {
+            trading_day: trading_day.to_string(),
+            ..Default::default()
+        })
+}
+
+/// OnRspUserLogin 后调用，载入该账户本交易日已有的撤单次数
+pub fn start_trading_day(session_id: &str, account_key: &str, trading_day: &str) {
+    let dir = STORE_DIR.lock().unwrap().clone();
+    let cancels = load(dir.as_deref(), account_key, trading_day);
+    let stored_limits = dir
+        .as_deref()
+        .and_then(|dir| load_all_limits(dir).remove(account_key));
+    let mut quotas = QUOTAS.lock().unwrap();
+    // 登录前已设置的限制优先，其次是该账户上次保存的限制
+    let limits = quotas
+        .get(session_id)
+        .map(|quota| quota.limits.clone())
+        .or(stored_limits)
+        .unwrap_or_default();
+    quotas.insert(session_id.to_string(), SessionQuota::new(account_key, limits, cancels));
+}
+
+/// 交易事件泵收到的每条消息都交给这里；撤单计数有变化时返回该合约的最新次数
+pub fn on_spi_output(session_id: &str, msg: &CThostFtdcTraderSpiOutput) -> Option<CancelCount> {
+    let CThostFtdcTraderSpiOutput::OnRtnOrder(p) = msg else {
+        return None;
+    };
+    let order = OrderInfo::from(p.p_order.as_ref()?);
+    if !order_store::is_final_status(&order.order_status) {
+        return None;
+    }
+    let mut quotas = QUOTAS.lock().unwrap();
+    let quota = quotas.get_mut(session_id)?;
+    // 报单已结束：在途撤单转为确认计数，或报单先成交后不再占用
+    quota.in_flight.remove(&OrderKey::of(&order));
+    if !is_counted_cancel(&order) {
+        return None;
+    }
+    quota.cancels.record(&order)?;
+
+    if let Some(dir) = STORE_DIR.lock().unwrap().as_deref() {
+        if let Err(e) = save(dir, &quota.account_key, &quota.cancels) {
+            println!("⚠️ [WARN] Failed to persist cancel counts: {}", e);
+        }
+    }
+    let snapshot = quota.snapshot(&order.instrument_id);
+    if snapshot.level != QuotaLevel::Normal {
+        println!(
+            "⚠️ [WARN] Cancel count for {} is {}/{}",
+            snapshot.instrument_id, snapshot.count, snapshot.max_cancels
+        );
+    }
+    Some(snapshot)
+}
+
+/// 撤单前检查，已确认和在途的撤单达到上限的合约不再发出撤单
+pub fn check(session_id: &str, instrument_id: &str) -> Result<(), String> {
+    let quotas = QUOTAS.lock().unwrap();
+    match quotas.get(session_id) {
+        Some(quota) => quota.check(instrument_id),
+        None => Ok(()),
+    }
+}
+
+/// 发出撤单前检查并占用一次撤单次数，撤单确认后转为正式计数
+pub fn reserve(session_id: &str, target: &OrderInfo) -> Result<(), String> {
+    let mut quotas = QUOTAS.lock().unwrap();
+    let Some(quota) = quotas.get_mut(session_id) else {
+        return Ok(());
+    };
+    let key = OrderKey::of(target);
+    // 同一报单重复撤单只占用一次
+    if quota.in_flight.contains_key(&key) {
+        return Ok(());
+    }
+    quota.check(&target.instrument_id)?;
+    quota.in_flight.insert(key, target.instrument_id.clone());
+    Ok(())
+}
+
+/// 撤单未能发出、被拒或等待超时，归还占用的次数
+pub fn release(session_id: &str, key: &OrderKey) {
+    if let Some(quota) = QUOTAS.lock().unwrap().get_mut(session_id) {
+        quota.in_flight.remove(key);
+    }
+}
+
+/// 本会话当日各合约的撤单次数
+pub fn counts(session_id: &str) -> Vec<CancelCount> {
+    let quotas = QUOTAS.lock().unwrap();
+    let Some(quota) = quotas.get(session_id) else {
+        return Vec::new();
+    };
+    let mut counts: Vec<CancelCount> = quota
+        .cancels
+        .counts
+        .keys()
+        .map(|instrument_id| quota.snapshot(instrument_id))
+        .collect();
+    counts.sort_by(|a, b| a.instrument_id.cmp(&b.instrument_id));
+    counts
+}
+
+pub fn set_limits(session_id: &str, limits: CancelLimits) {
+    let mut quotas = QUOTAS.lock().unwrap();
+    match quotas.get_mut(session_id) {
+        Some(quota) => {
+            if !quota.account_key.is_empty() {
+                if let Some(dir) = STORE_DIR.lock().unwrap().as_deref() {
+                    if let Err(e) = save_limits(dir, &quota.account_key, &limits) {
+                        println!("⚠️ [WARN] Failed to persist cancel limits: {}", e);
+                    }
+                }
+            }
+            quota.limits = limits;
+        }
+        None => {
+            quotas.insert(session_id.to_string(), SessionQuota::new("", limits, DailyCancels::default()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canceled(instrument_id: &str, order_sys_id: &str) -> OrderInfo {
+        OrderInfo {
+            instrument_id: instrument_id.to_string(),
+            exchange_id: "SHFE".to_string(),
+            order_sys_id: order_sys_id.to_string(),
+            order_status: "5".to_string(),
+            order_submit_status: "3".to_string(),
+            time_condition: "3".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn counts_each_cancel_once_per_instrument() {
+        let mut cancels = DailyCancels::default();
+        assert_eq!(cancels.record(&canceled("rb2505", "1")), Some(1));
+        assert_eq!(cancels.record(&canceled("rb2505", "2")), Some(2));
+        assert_eq!(cancels.record(&canceled("rb2505", "1")), None);
+        assert_eq!(cancels.record(&canceled("ag2506", "3")), Some(1));
+        assert_eq!(cancels.count("rb2505"), 2);
+        assert_eq!(cancels.count("cu2505"), 0);
+    }
+
+    #[test]
+    fn only_confirmed_user_cancels_are_counted() {
+        assert!(is_counted_cancel(&canceled("rb2505", "1")));
+
+        let mut rejected = canceled("rb2505", "1");
+        rejected.order_submit_status = "4".to_string();
+        assert!(!is_counted_cancel(&rejected));
+
+        let mut fak = canceled("rb2505", "1");
+        fak.time_condition = "1".to_string();
+        assert!(!is_counted_cancel(&fak));
+
+        let mut queued = canceled("rb2505", "1");
+        queued.order_status = "3".to_string();
+        assert!(!is_counted_cancel(&queued));
+    }
+
+    #[test]
+    fn level_warns_then_blocks() {
+        let limits = CancelLimits {
+            max_cancels: 5,
+            warn_at: 3,
+            mode: CancelMode::Limited,
+        };
+        assert_eq!(level(2, &limits), QuotaLevel::Normal);
+        assert_eq!(level(3, &limits), QuotaLevel::Warning);
+        assert_eq!(level(5, &limits), QuotaLevel::Blocked);
+
+        let unlimited = CancelLimits {
+            mode: CancelMode::Unlimited,
+            ..limits
+        };
+        assert_eq!(level(500, &unlimited), QuotaLevel::Normal);
+    }
+
+    #[test]
+    fn in_flight_cancels_count_against_the_limit() {
+        let limits = CancelLimits {
+            max_cancels: 3,
+            warn_at: 2,
+            mode: CancelMode::Limited,
+        };
+        let mut quota = SessionQuota::new("9999:a", limits, DailyCancels::default());
+        quota.cancels.record(&canceled("rb2505", "1"));
+        for order_ref in ["2", "3"] {
+            assert!(quota.check("rb2505").is_ok());
+            let key = OrderKey {
+                front_id: 1,
+                session_id: 1,
+                order_ref: order_ref.to_string(),
+            };
+            quota.in_flight.insert(key, "rb2505".to_string());
+        }
+        assert!(quota.check("rb2505").is_err());
+        assert!(quota.check("ag2506").is_ok());
+    }
+
+    #[test]
+    fn persisted_counts_reset_on_a_new_trading_day() {
+        let dir = std::env::temp_dir().join(format!("cancel_quota_test_{}", std::process::id()));
+        let mut cancels = load(Some(&dir), "9999:a", "20250102");
+        cancels.record(&canceled("rb2505", "1"));
+        save(&dir, "9999:a", &cancels).unwrap();
+
+        assert_eq!(load(Some(&dir), "9999:a", "20250102").count("rb2505"), 1);
+        assert_eq!(load(Some(&dir), "9999:a", "20250103").count("rb2505"), 0);
+        assert_eq!(load(Some(&dir), "9999:b", "20250102").count("rb2505"), 0);
+
+        let limits = CancelLimits {
+            max_cancels: 300,
+            warn_at: 250,
+            mode: CancelMode::Unlimited,
+        };
+        save_limits(&dir, "9999:a", &limits).unwrap();
+        assert_eq!(load_all_limits(&dir).remove("9999:a"), Some(limits));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
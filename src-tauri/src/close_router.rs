@@ -0,0 +1,199 @@
+// 平仓拆单：上期所/能源中心把普通"平仓"当作平昨，今仓必须用"平今"才能平掉。
+// 这里按合并后的持仓把一笔平仓拆成平昨、平今两笔子单（其他交易所为一笔平仓），并作为一个父单跟踪
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use tauri_app_vue_lib::{char_flag_to_string, THOST_FTDC_PD_Long, THOST_FTDC_PD_Short};
+
+use crate::ctp_flags::{Direction, OffsetFlag};
+use crate::order_store::{self, OrderKey};
+use crate::position::MergedPosition;
+
+/// 今昨仓分开记录、平仓需要区分平今平昨的交易所
+pub const SPLIT_CLOSE_EXCHANGES: [&str; 2] = ["SHFE", "INE"];
+
+/// 拆分后的一笔子单
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CloseLeg {
+    pub offset_flag: OffsetFlag,
+    pub volume: i32,
+}
+
+/// 平仓方向对应的持仓方向：买入平空头，卖出平多头
+pub fn closed_posi_direction(direction: Direction) -> String {
+    match direction {
+        Direction::Buy => char_flag_to_string(THOST_FTDC_PD_Short as i8),
+        Direction::Sell => char_flag_to_string(THOST_FTDC_PD_Long as i8),
+    }
+}
+
+/// 按可平数量拆分平仓；上期所/能源中心先平昨再平今
+pub fn split_close(position: &MergedPosition, volume: i32) -> Result<Vec<CloseLeg>, String> {
+    if volume <= 0 {
+        return Err(format!("平仓数量必须大于 0: {}", volume));
+    }
+    if !SPLIT_CLOSE_EXCHANGES.contains(&position.exchange_id.as_str()) {
+        if volume > position.closable {
+            return Err(format!(
+                "{} 可平数量不足: 可平 {}，请求 {}",
+                position.instrument_id, position.closable, volume
+            ));
+        }
+        return Ok(vec![CloseLeg {
+            offset_flag: OffsetFlag::Close,
+            volume,
+        }]);
+    }
+
+    if volume > position.yd_closable + position.today_closable {
+        return Err(format!(
+            "{} 可平数量不足: 可平昨 {}，可平今 {}，请求 {}",
+            position.instrument_id, position.yd_closable, position.today_closable, volume
+        ));
+    }
+    let yesterday = volume.min(position.yd_closable);
+    let today = volume - yesterday;
+    let legs = [
+        (OffsetFlag::CloseYesterday, yesterday),
+        (OffsetFlag::CloseToday, today),
+    ];
+    Ok(legs
+        .into_iter()
+        .filter(|&(_, volume)| volume > 0)
+        .map(|(offset_flag, volume)| CloseLeg {
+            offset_flag,
+            volume,
+        })
+        .collect())
+}
+
+/// 父单下的一笔子单及其最新状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloseChild {
+    pub offset_flag: OffsetFlag,
+    pub volume: i32,
+    /// 未能发出的子单没有报单标识，只有 error
+    pub key: Option<OrderKey>,
+    pub order_status: String,
+    pub filled_volume: i32,
+    pub error: Option<String>,
+}
+
+/// 一笔逻辑平仓
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloseParent {
+    pub parent_id: String,
+    pub instrument_id: String,
+    pub exchange_id: String,
+    pub direction: Direction,
+    pub price: f64,
+    pub volume: i32,
+    pub filled_volume: i32,
+    pub created_at: i64,
+    pub children: Vec<CloseChild>,
+}
+
+impl CloseParent {
+    /// 从报单簿刷新子单状态和父单成交数量
+    fn refresh(&mut self, session_id: &str) {
+        order_store::with_book(session_id, |book| {
+            for child in &mut self.children {
+                let managed = child.key.as_ref().and_then(|key| book.get_by_key(key));
+                if let Some(managed) = managed {
+                    child.order_status = managed.order.order_status.clone();
+                    child.filled_volume = managed.filled_volume;
+                }
+            }
+        });
+        self.filled_volume = self.children.iter().map(|child| child.filled_volume).sum();
+    }
+}
+
+lazy_static::lazy_static! {
+    // 每个交易会话当日的平仓父单
+    static ref CLOSE_PARENTS: Arc<Mutex<HashMap<String, Vec<CloseParent>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+pub fn store(session_id: &str, parent: CloseParent) {
+    CLOSE_PARENTS
+        .lock()
+        .unwrap()
+        .entry(session_id.to_string())
+        .or_default()
+        .push(parent);
+}
+
+/// 本会话的全部平仓父单，子单状态取自报单簿
+pub fn parents(session_id: &str) -> Vec<CloseParent> {
+    let mut parents = CLOSE_PARENTS
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .unwrap_or_default();
+    for parent in &mut parents {
+        parent.refresh(session_id);
+    }
+    parents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(exchange_id: &str, today_closable: i32, yd_closable: i32) -> MergedPosition {
+        MergedPosition {
+            instrument_id: "rb2505".to_string(),
+            exchange_id: exchange_id.to_string(),
+            posi_direction: "2".to_string(),
+            closable: today_closable + yd_closable,
+            today_closable,
+            yd_closable,
+            ..Default::default()
+        }
+    }
+
+    fn leg(offset_flag: OffsetFlag, volume: i32) -> CloseLeg {
+        CloseLeg {
+            offset_flag,
+            volume,
+        }
+    }
+
+    #[test]
+    fn shfe_closes_yesterday_first_then_today() {
+        let long = position("SHFE", 3, 2);
+        assert_eq!(
+            split_close(&long, 4).unwrap(),
+            vec![leg(OffsetFlag::CloseYesterday, 2), leg(OffsetFlag::CloseToday, 2)]
+        );
+        assert_eq!(split_close(&long, 1).unwrap(), vec![leg(OffsetFlag::CloseYesterday, 1)]);
+        assert_eq!(
+            split_close(&position("INE", 3, 0), 3).unwrap(),
+            vec![leg(OffsetFlag::CloseToday, 3)]
+        );
+    }
+
+    #[test]
+    fn other_exchanges_use_a_single_close() {
+        let mut long = position("DCE", 0, 0);
+        long.closable = 5;
+        assert_eq!(split_close(&long, 5).unwrap(), vec![leg(OffsetFlag::Close, 5)]);
+        assert!(split_close(&long, 6).is_err());
+    }
+
+    #[test]
+    fn rejects_more_than_closable() {
+        assert!(split_close(&position("SHFE", 1, 1), 3).is_err());
+        assert!(split_close(&position("SHFE", 1, 1), 0).is_err());
+    }
+
+    #[test]
+    fn close_direction_targets_the_opposite_position() {
+        assert_eq!(closed_posi_direction(Direction::Sell), "2");
+        assert_eq!(closed_posi_direction(Direction::Buy), "3");
+    }
+}
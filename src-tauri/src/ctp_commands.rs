@@ -1,31 +1,55 @@
-use tauri::command;
+use tauri::{command, Manager};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 
 // 引入 CTP 相关模块
 use tauri_app_vue_lib::{get_api_version as ctp_get_api_version, md_api, trader_api};
+use crate::account::{self, AccountInfo, AccountSnapshot};
+use crate::algo_orders::{self, AlgoOrder, AlgoRequest};
+use crate::cancel_quota::{self, CancelCount, CancelLimits};
+use crate::close_router::{self, CloseChild, CloseParent};
+use crate::ctp_flags::{
+    ContingentCondition, Direction, HedgeFlag, OffsetFlag, OrderPriceType, TimeCondition,
+    VolumeCondition,
+};
+use crate::instruments::{self, InstrumentCatalogue, InstrumentFilter, InstrumentInfo};
+use crate::local_triggers::{self, LocalTrigger, LocalTriggerRequest};
+use crate::md_spi;
+use crate::order_ref;
+use crate::order_store::{self, ManagedOrder, OrderFilter, OrderKey, OrderLookup};
+use crate::parked_orders::{self, ParkedCancelInfo, ParkedOrderInfo, ParkedOrders};
+use crate::position::{self, PositionInfo, PositionSummary};
+use crate::price_chaser::{self, ChaseRequest, PriceChase};
+use crate::query_scheduler::{self, QueryPriority, QUERY_TIMEOUT};
+use crate::risk::{self, RiskConfig};
+use crate::rsp_correlator;
+use crate::self_trade::{self, SelfTradeAction};
+use crate::subscriptions::{self, SubscriptionInfo};
+use crate::trader_events::{OrderInfo, TradeInfo};
+use crate::trader_spi::{self, TraderLoginInfo};
 
 // 全局状态管理
 lazy_static::lazy_static! {
-    static ref MD_APIS: Arc<Mutex<HashMap<String, Box<tauri_app_vue_lib::CThostFtdcMdApi>>>> =
+    pub(crate) static ref MD_APIS: Arc<Mutex<HashMap<String, Box<tauri_app_vue_lib::CThostFtdcMdApi>>>> =
         Arc::new(Mutex::new(HashMap::new()));
-    static ref TRADER_APIS: Arc<Mutex<HashMap<String, Box<tauri_app_vue_lib::CThostFtdcTraderApi>>>> =
+    pub(crate) static ref TRADER_APIS: Arc<Mutex<HashMap<String, Box<tauri_app_vue_lib::CThostFtdcTraderApi>>>> =
         Arc::new(Mutex::new(HashMap::new()));
     // 跟踪交易API的登录状态
-    static ref TRADER_LOGIN_STATUS: Arc<Mutex<HashMap<String, bool>>> =
+    pub(crate) static ref TRADER_LOGIN_STATUS: Arc<Mutex<HashMap<String, bool>>> =
         Arc::new(Mutex::new(HashMap::new()));
     // 全局请求ID计数器
     static ref REQUEST_ID_COUNTER: Arc<Mutex<i32>> = Arc::new(Mutex::new(1));
-    // 全局订单引用计数器
-    static ref ORDER_REF_COUNTER: Arc<Mutex<i32>> = Arc::new(Mutex::new(1));
     // 存储会话的登录信息
-    static ref SESSION_LOGIN_INFO: Arc<Mutex<HashMap<String, CtpAccountConfig>>> =
+    pub(crate) static ref SESSION_LOGIN_INFO: Arc<Mutex<HashMap<String, CtpAccountConfig>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // 登录成功后 CTP 返回的前置/会话信息
+    pub(crate) static ref TRADER_SESSION_INFO: Arc<Mutex<HashMap<String, TraderLoginInfo>>> =
         Arc::new(Mutex::new(HashMap::new()));
 }
 
 // 数据结构定义
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CtpAccountConfig {
     pub broker_id: String,
     pub account: String,
@@ -52,172 +76,112 @@ pub struct MarketDataRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OrderRequest {
     pub instrument_id: String,
-    pub direction: String, // "0" for buy, "1" for sell
+    pub direction: Direction, // "0" for buy, "1" for sell
     pub price: f64,
     pub volume: i32,
-    pub order_type: String, // "1" for limit order, "2" for limit price
-    pub offset_flag: Option<String>, // "0" for open, "1" for close
-    pub hedge_flag: Option<String>, // "1" for speculation, "2" for arbitrage, "3" for hedge
-    pub time_condition: Option<String>, // "1" for IOC, "3" for GFD
-    pub volume_condition: Option<String>, // "1" for any volume
+    pub order_type: OrderPriceType, // "2" for limit price, "1" for any price (market)
+    pub offset_flag: Option<OffsetFlag>, // "0" for open, "1" for close, "3" for close today
+    pub hedge_flag: Option<HedgeFlag>, // "1" for speculation, "2" for arbitrage, "3" for hedge
+    pub time_condition: Option<TimeCondition>, // "1" for IOC, "3" for GFD
+    pub volume_condition: Option<VolumeCondition>, // "1" for any volume, "3" for complete volume
+    pub contingent_condition: Option<ContingentCondition>, // "1" for immediately
+    pub stop_price: Option<f64>, // 条件单的触发价
+}
+
+impl OrderRequest {
+    /// 普通限价单，可选字段都不填
+    pub fn limit(instrument_id: &str, direction: Direction, price: f64, volume: i32) -> Self {
+        OrderRequest {
+            instrument_id: instrument_id.to_string(),
+            direction,
+            price,
+            volume,
+            order_type: OrderPriceType::LimitPrice,
+            offset_flag: None,
+            hedge_flag: None,
+            time_condition: None,
+            volume_condition: None,
+            contingent_condition: None,
+            stop_price: None,
+        }
+    }
+
+    /// 指定开平和投保标志
+    pub fn with_flags(self, offset_flag: Option<OffsetFlag>, hedge_flag: Option<HedgeFlag>) -> Self {
+        OrderRequest {
+            offset_flag,
+            hedge_flag,
+            ..self
+        }
+    }
 }
 
+/// 平仓请求：由后端按持仓拆成平今/平昨子单
 #[derive(Debug, Serialize, Deserialize)]
-pub struct CancelOrderRequest {
-    pub order_ref: String,
-    pub front_id: Option<i32>,
-    pub session_id: Option<i32>,
-    pub exchange_id: Option<String>,
-    pub order_sys_id: Option<String>,
+pub struct CloseRequest {
     pub instrument_id: String,
+    pub direction: Direction, // "1" 卖出平多，"0" 买入平空
+    pub price: f64,
+    pub volume: i32,
+    pub order_type: OrderPriceType,
+    pub hedge_flag: Option<HedgeFlag>,
+    pub time_condition: Option<TimeCondition>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AccountInfo {
-    pub broker_id: String,
-    pub account_id: String,
-    pub pre_mortgage: f64,
-    pub pre_credit: f64,
-    pub pre_deposit: f64,
-    pub pre_balance: f64,
-    pub pre_margin: f64,
-    pub interest_base: f64,
-    pub interest: f64,
-    pub deposit: f64,
-    pub withdraw: f64,
-    pub frozen_margin: f64,
-    pub frozen_cash: f64,
-    pub frozen_commission: f64,
-    pub curr_margin: f64,
-    pub cash_in: f64,
-    pub commission: f64,
-    pub close_profit: f64,
-    pub position_profit: f64,
-    pub balance: f64,
-    pub available: f64,
-    pub withdraw_quota: f64,
-    pub reserve: f64,
-    pub trading_day: String,
-    pub settlement_id: i32,
-    pub credit: f64,
-    pub mortgage: f64,
-    pub exchange_margin: f64,
-    pub delivery_margin: f64,
-    pub exchange_delivery_margin: f64,
-    pub reserve_balance: f64,
-    pub currency_id: String,
-    pub pre_fund_mortgage_in: f64,
-    pub pre_fund_mortgage_out: f64,
-    pub fund_mortgage_in: f64,
-    pub fund_mortgage_out: f64,
-    pub fund_mortgage_available: f64,
-    pub mortgage_able_fund: f64,
-    pub spec_product_margin: f64,
-    pub spec_product_frozen_margin: f64,
-    pub spec_product_commission: f64,
-    pub spec_product_frozen_commission: f64,
-    pub spec_product_position_profit: f64,
-    pub spec_product_close_profit: f64,
-    pub spec_product_position_profit_by_alg: f64,
-    pub spec_product_exchange_margin: f64,
-    pub bis_margin: f64,
-    pub bis_frozen_margin: f64,
-    pub bis_commission: f64,
-    pub bis_frozen_commission: f64,
-    pub bis_position_profit: f64,
-    pub bis_close_profit: f64,
-    pub bis_position_profit_by_alg: f64,
-    pub bis_exchange_margin: f64,
-    pub frozen_swap: f64,
-    pub remain_swap: f64,
+/// insert_order 的返回，wait_for_ack 时包含交易所确认后的报单编号和状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderInsertResult {
+    pub order_ref: String,
+    pub front_id: i32,
+    pub session_id: i32,
+    pub request_id: i32,
+    pub order_sys_id: String,
+    pub order_status: String,
+    pub acknowledged: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct InstrumentInfo {
-    pub instrument_id: String,
+/// cancel_order 的返回：撤单所用的完整报单标识和撤单后的报单状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderCancelResult {
+    pub order_ref: String,
+    pub front_id: i32,
+    pub session_id: i32,
     pub exchange_id: String,
-    pub instrument_name: String,
-    pub exchange_inst_id: String,
-    pub product_id: String,
-    pub product_class: String,
-    pub delivery_year: i32,
-    pub delivery_month: i32,
-    pub max_market_order_volume: i32,
-    pub min_market_order_volume: i32,
-    pub max_limit_order_volume: i32,
-    pub min_limit_order_volume: i32,
-    pub volume_multiple: i32,
-    pub price_tick: f64,
-    pub create_date: String,
-    pub open_date: String,
-    pub expire_date: String,
-    pub start_deliv_date: String,
-    pub end_deliv_date: String,
-    pub inst_life_phase: String,
-    pub is_trading: i32,
-    pub position_type: String,
-    pub position_date_type: String,
-    pub long_margin_ratio: f64,
-    pub short_margin_ratio: f64,
-    pub max_margin_side_algorithm: String,
-    pub underlying_inst_id: String,
-    pub strike_price: f64,
-    pub options_type: String,
-    pub underlying_multiple: f64,
-    pub combination_type: String,
+    pub order_sys_id: String,
+    pub action_ref: i32,
+    pub request_id: i32,
+    pub order_status: String,
+    pub volume_traded: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PositionInfo {
-    pub instrument_id: String,
-    pub broker_id: String,
-    pub investor_id: String,
-    pub posi_direction: String,
-    pub hedge_flag: String,
-    pub position_date: String,
-    pub yd_position: i32,
-    pub position: i32,
-    pub long_frozen: i32,
-    pub short_frozen: i32,
-    pub long_frozen_amount: f64,
-    pub short_frozen_amount: f64,
-    pub open_volume: i32,
-    pub close_volume: i32,
-    pub open_amount: f64,
-    pub close_amount: f64,
-    pub position_cost: f64,
-    pub pre_margin: f64,
-    pub use_margin: f64,
-    pub frozen_margin: f64,
-    pub frozen_cash: f64,
-    pub frozen_commission: f64,
-    pub cash_in: f64,
-    pub commission: f64,
-    pub close_profit: f64,
-    pub position_profit: f64,
-    pub pre_settlement_price: f64,
-    pub settlement_price: f64,
-    pub trading_day: String,
-    pub settlement_id: i32,
-    pub open_cost: f64,
-    pub exchange_margin: f64,
-    pub comb_position: i32,
-    pub comb_long_frozen: i32,
-    pub comb_short_frozen: i32,
-    pub close_profit_by_date: f64,
-    pub close_profit_by_trade: f64,
-    pub today_position: i32,
-    pub margin_rate_by_money: f64,
-    pub margin_rate_by_volume: f64,
-    pub strike_frozen: i32,
-    pub strike_frozen_amount: f64,
-    pub abandon_frozen: i32,
+/// 批量撤单中单笔报单的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelOutcome {
+    pub order_ref: String,
+    pub front_id: i32,
+    pub session_id: i32,
     pub exchange_id: String,
-    pub yd_strike_frozen: i32,
-    pub invest_unit_id: String,
-    pub position_cost_offset: f64,
-    pub tas_position: i32,
-    pub tas_position_cost: f64,
+    pub order_sys_id: String,
+    pub instrument_id: String,
+    pub direction: String,
+    pub limit_price: f64,
+    pub success: bool,
+    pub order_status: String,
+    pub error: Option<String>,
+}
+
+// 已发出的报单，receiver 在等待交易所确认时存在
+pub(crate) struct PendingInsert {
+    pub result: OrderInsertResult,
+    pub key: OrderKey,
+    pub receiver: Option<tokio::sync::oneshot::Receiver<order_store::AckResult>>,
+}
+
+// 已发出、等待结果的撤单
+pub(crate) struct PendingCancel {
+    result: OrderCancelResult,
+    key: OrderKey,
+    receiver: tokio::sync::oneshot::Receiver<order_store::AckResult>,
 }
 
 // 验证交易会话是否存在
@@ -247,23 +211,15 @@ fn get_ctp_cache_path(session_id: &str) -> Result<String, String> {
 }
 
 // 获取下一个请求ID
-fn get_next_request_id() -> i32 {
+pub(crate) fn get_next_request_id() -> i32 {
     let mut counter = REQUEST_ID_COUNTER.lock().unwrap();
     let id = *counter;
     *counter += 1;
     id
 }
 
-// 获取下一个订单引用
-fn get_next_order_ref() -> String {
-    let mut counter = ORDER_REF_COUNTER.lock().unwrap();
-    let ref_id = *counter;
-    *counter += 1;
-    format!("{}", ref_id)
-}
-
 // 安全地复制字符串到CTP字符数组
-fn copy_str_to_ctp_array(src: &str, dst: &mut [i8]) {
+pub(crate) fn copy_str_to_ctp_array(src: &str, dst: &mut [i8]) {
     let src_bytes = src.as_bytes();
     let max_len = std::cmp::min(src_bytes.len(), dst.len() - 1);
 
@@ -341,11 +297,18 @@ fn create_md_api_safe(
 
 // 安全释放 MD API 资源
 fn release_md_api_safe(session_id: &str) -> Result<(), String> {
-    let mut apis = MD_APIS.lock().map_err(|e| format!("Failed to lock MD_APIS: {}", e))?;
+    let removed = MD_APIS
+        .lock()
+        .map_err(|e| format!("Failed to lock MD_APIS: {}", e))?
+        .remove(session_id);
 
-    if let Some(mut api) = apis.remove(session_id) {
+    if let Some(mut api) = removed {
         println!("🔍 [DEBUG] Releasing MD API for session: {}", session_id);
 
+        // 先放开 MD_APIS 再清理会话状态，关窗时不再对已释放的会话退订
+        md_spi::forget_session(session_id);
+        subscriptions::remove_session(session_id);
+
         // 安全地释放 CTP API 资源
         let release_result = std::panic::catch_unwind(|| {
             // 注意：CTP API 的 release 方法可能会导致崩溃
@@ -486,55 +449,62 @@ pub fn create_trader_api(
 }
 
 // 行情 API 命令
+// 注册 SPI 并完成 OnFrontConnected → ReqUserLogin 握手，登录成功后返回交易日
 #[command]
-pub fn md_login(
+pub async fn md_login(
+    app: tauri::AppHandle,
     session_id: String,
     config: CtpAccountConfig,
 ) -> ApiResponse<String> {
     println!("🔍 [DEBUG] md_login called with session_id: {}", session_id);
-    println!("🔍 [DEBUG] config: {:?}", config);
-
-    match std::panic::catch_unwind(|| {
-        let mut apis = MD_APIS.lock().unwrap();
-
-        if let Some(api) = apis.get_mut(&session_id) {
-            println!("✅ [DEBUG] Found MD API for session: {}", session_id);
+    println!("🔍 [DEBUG] md_front: {}", config.md_front);
 
-            // 注册前置服务器
-            let md_front = std::ffi::CString::new(config.md_front.clone()).unwrap();
-            api.register_front(md_front);
-            println!("✅ [DEBUG] Registered front: {}", config.md_front);
-
-            // 初始化API
-            api.init();
-            println!("✅ [DEBUG] MD API initialized");
-
-            // 注意：实际的登录需要在前置连接成功后进行
-            // 这里我们返回成功，表示登录流程已启动
-            Ok(format!("MD login initiated for session: {}", session_id))
-        } else {
-            Err(format!("Session ID {} not found", session_id))
-        }
-    }) {
-        Ok(Ok(message)) => {
-            println!("✅ [SUCCESS] MD login: {}", message);
-            ApiResponse {
-                success: true,
-                data: Some(message),
-                error: None,
-            }
-        },
+    let receiver = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        md_spi::start_login(&session_id, config, app)
+    })) {
+        Ok(Ok(receiver)) => receiver,
         Ok(Err(error)) => {
             println!("❌ [ERROR] MD login failed: {}", error);
-            ApiResponse {
+            return ApiResponse {
                 success: false,
                 data: None,
                 error: Some(error),
-            }
+            };
         },
         Err(_) => {
             let error = "MD login panicked".to_string();
             println!("❌ [ERROR] {}", error);
+            return ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            };
+        }
+    };
+
+    let result = match tokio::time::timeout(md_spi::LOGIN_TIMEOUT, receiver).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("行情登录流程已中断".to_string()),
+        Err(_) => {
+            md_spi::cancel_login(&session_id);
+            Err(format!(
+                "行情登录超时（{}秒内未收到 OnRspUserLogin）",
+                md_spi::LOGIN_TIMEOUT.as_secs()
+            ))
+        },
+    };
+
+    match result {
+        Ok(trading_day) => {
+            println!("✅ [SUCCESS] MD login successful, trading_day: {}", trading_day);
+            ApiResponse {
+                success: true,
+                data: Some(trading_day),
+                error: None,
+            }
+        },
+        Err(error) => {
+            println!("❌ [ERROR] MD login failed: {}", error);
             ApiResponse {
                 success: false,
                 data: None,
@@ -544,39 +514,34 @@ pub fn md_login(
     }
 }
 
+// 订阅按调用窗口登记引用计数，只有第一个关注该合约的窗口才会触发 CTP 订阅
 #[command]
 pub fn subscribe_market_data(
+    window: tauri::WebviewWindow,
     session_id: String,
     request: MarketDataRequest,
 ) -> ApiResponse<String> {
-    println!("🔍 [DEBUG] subscribe_market_data called with session_id: {}", session_id);
+    let window_label = window.label().to_string();
+    println!("🔍 [DEBUG] subscribe_market_data called with session_id: {}, window: {}", session_id, window_label);
     println!("🔍 [DEBUG] instruments: {:?}", request.instrument_ids);
 
     match std::panic::catch_unwind(|| {
-        let mut apis = MD_APIS.lock().unwrap();
-
-        if let Some(api) = apis.get_mut(&session_id) {
-            println!("✅ [DEBUG] Found MD API for session: {}", session_id);
-
-            // 转换合约代码为CString
-            let instruments: Vec<std::ffi::CString> = request.instrument_ids
-                .iter()
-                .map(|id| std::ffi::CString::new(id.as_str()).unwrap())
-                .collect();
-
-            let count = instruments.len() as std::os::raw::c_int;
-
-            // 订阅行情
-            let result = api.subscribe_market_data(instruments, count);
+        if !MD_APIS.lock().unwrap().contains_key(&session_id) {
+            return Err(format!("Session ID {} not found", session_id));
+        }
 
-            if result == 0 {
-                Ok(format!("Successfully subscribed to {} instruments", request.instrument_ids.len()))
-            } else {
-                Err(format!("Failed to subscribe market data, error code: {}", result))
-            }
-        } else {
-            Err(format!("Session ID {} not found", session_id))
+        let newly_watched = subscriptions::add(&session_id, &window_label, &request.instrument_ids);
+        if let Err(error) = md_spi::subscribe_instruments(&session_id, &newly_watched) {
+            // 订阅失败时撤销本次登记，保持订阅表与 CTP 一致
+            subscriptions::remove(&session_id, &window_label, &request.instrument_ids);
+            return Err(error);
         }
+
+        Ok(format!(
+            "Successfully subscribed to {} instruments ({} new at CTP)",
+            request.instrument_ids.len(),
+            newly_watched.len()
+        ))
     }) {
         Ok(Ok(message)) => {
             println!("✅ [SUCCESS] Subscribe market data: {}", message);
@@ -606,48 +571,32 @@ pub fn subscribe_market_data(
     }
 }
 
+// 释放调用窗口的订阅，最后一个关注该合约的窗口释放时才向 CTP 退订
 #[command]
 pub fn unsubscribe_market_data(
+    window: tauri::WebviewWindow,
     session_id: String,
     request: MarketDataRequest,
 ) -> ApiResponse<String> {
-    // 实现取消订阅行情数据的逻辑
-    ApiResponse {
-        success: true,
-        data: Some(format!("Unsubscribed from {} instruments", request.instrument_ids.len())),
-        error: None,
-    }
-}
-
-// 交易 API 命令
-#[command]
-pub fn trader_login(
-    session_id: String,
-    config: CtpAccountConfig,
-) -> ApiResponse<String> {
-    println!("🔍 [DEBUG] trader_login called with session_id: {}", session_id);
+    let window_label = window.label().to_string();
+    println!("🔍 [DEBUG] unsubscribe_market_data called with session_id: {}, window: {}", session_id, window_label);
 
     match std::panic::catch_unwind(|| {
-        let apis = TRADER_APIS.lock().unwrap();
-
-        if apis.get(&session_id).is_some() {
-            // 模拟登录成功 - 在实际实现中，这里应该调用真正的CTP登录API
-            // 现在我们只是标记为已登录状态
-            let mut login_status = TRADER_LOGIN_STATUS.lock().unwrap();
-            login_status.insert(session_id.clone(), true);
+        if !MD_APIS.lock().unwrap().contains_key(&session_id) {
+            return Err(format!("Session ID {} not found", session_id));
+        }
 
-            // 保存登录信息供后续使用
-            let mut session_info = SESSION_LOGIN_INFO.lock().unwrap();
-            session_info.insert(session_id.clone(), config);
+        let released = subscriptions::remove(&session_id, &window_label, &request.instrument_ids);
+        md_spi::unsubscribe_instruments(&session_id, &released)?;
 
-            println!("✅ [DEBUG] Trader login successful for session: {}", session_id);
-            Ok("Trader login successful".to_string())
-        } else {
-            Err(format!("Session ID {} not found", session_id))
-        }
+        Ok(format!(
+            "Unsubscribed from {} instruments ({} released at CTP)",
+            request.instrument_ids.len(),
+            released.len()
+        ))
     }) {
         Ok(Ok(message)) => {
-            println!("✅ [SUCCESS] {}", message);
+            println!("✅ [SUCCESS] Unsubscribe market data: {}", message);
             ApiResponse {
                 success: true,
                 data: Some(message),
@@ -655,7 +604,7 @@ pub fn trader_login(
             }
         },
         Ok(Err(error)) => {
-            println!("❌ [ERROR] Trader login failed: {}", error);
+            println!("❌ [ERROR] Unsubscribe market data failed: {}", error);
             ApiResponse {
                 success: false,
                 data: None,
@@ -663,7 +612,7 @@ pub fn trader_login(
             }
         },
         Err(_) => {
-            let error = "Trader login panicked".to_string();
+            let error = "Unsubscribe market data panicked".to_string();
             println!("❌ [ERROR] {}", error);
             ApiResponse {
                 success: false,
@@ -674,243 +623,292 @@ pub fn trader_login(
     }
 }
 
+// 查看行情会话中各合约被哪些窗口订阅
 #[command]
-pub fn insert_order(
-    session_id: String,
-    order: OrderRequest,
-) -> ApiResponse<String> {
-    println!("🔍 [DEBUG] insert_order called with session_id: {}, order: {:?}", session_id, order);
+pub fn list_subscriptions(session_id: String) -> ApiResponse<Vec<SubscriptionInfo>> {
+    ApiResponse {
+        success: true,
+        data: Some(subscriptions::snapshot(&session_id)),
+        error: None,
+    }
+}
 
-    match std::panic::catch_unwind(|| {
-        // 首先检查登录状态
-        let login_status = TRADER_LOGIN_STATUS.lock().unwrap();
-        if !login_status.get(&session_id).unwrap_or(&false) {
-            return Err("CTP 交易 API 未连接，请先登录".to_string());
+// 窗口销毁时释放它持有的全部行情订阅
+pub fn release_window_subscriptions(window_label: &str) {
+    for (session_id, released) in subscriptions::remove_window(window_label) {
+        println!("🔍 [DEBUG] Window {} closed, releasing {:?} on session {}", window_label, released, session_id);
+        if let Err(error) = md_spi::unsubscribe_instruments(&session_id, &released) {
+            println!("❌ [ERROR] Unsubscribe market data failed: {}", error);
         }
-        drop(login_status); // 释放锁
-
-        let mut apis = TRADER_APIS.lock().unwrap();
-        let login_info = SESSION_LOGIN_INFO.lock().unwrap();
-
-        if let (Some(api), Some(account_config)) = (apis.get_mut(&session_id), login_info.get(&session_id)) {
-            println!("✅ [DEBUG] Found Trader API and login info for session: {}", session_id);
-
-            // 引入CTP相关类型
-            use tauri_app_vue_lib::*;
-
-            // 创建输入报单结构
-            let mut input_order = CThostFtdcInputOrderField::default();
-
-            // 填充基本信息
-            copy_str_to_ctp_array(&account_config.broker_id, &mut input_order.BrokerID);
-            copy_str_to_ctp_array(&account_config.account, &mut input_order.InvestorID);
-            copy_str_to_ctp_array(&account_config.account, &mut input_order.UserID);
-            copy_str_to_ctp_array(&order.instrument_id, &mut input_order.InstrumentID);
-
-            // 设置订单引用
-            let order_ref = get_next_order_ref();
-            copy_str_to_ctp_array(&order_ref, &mut input_order.OrderRef);
-
-            // 设置买卖方向
-            input_order.Direction = if order.direction == "0" {
-                THOST_FTDC_D_Buy as i8
-            } else {
-                THOST_FTDC_D_Sell as i8
-            };
-
-            // 设置价格和数量
-            input_order.LimitPrice = order.price;
-            input_order.VolumeTotalOriginal = order.volume;
-
-            // 设置报单价格条件 (默认限价单)
-            input_order.OrderPriceType = if order.order_type == "1" {
-                THOST_FTDC_OPT_AnyPrice as i8  // 市价单
-            } else {
-                THOST_FTDC_OPT_LimitPrice as i8  // 限价单
-            };
+    }
+}
 
-            // 设置开平标志 (默认开仓)
-            input_order.CombOffsetFlag[0] = order.offset_flag
-                .as_deref()
-                .unwrap_or("0")
-                .parse::<u8>()
-                .unwrap_or(THOST_FTDC_OF_Open) as i8;
-
-            // 设置投机套保标志 (默认投机)
-            input_order.CombHedgeFlag[0] = order.hedge_flag
-                .as_deref()
-                .unwrap_or("1")
-                .parse::<u8>()
-                .unwrap_or(THOST_FTDC_HF_Speculation) as i8;
-
-            // 设置有效期类型 (默认当日有效)
-            input_order.TimeCondition = order.time_condition
-                .as_deref()
-                .unwrap_or("3")
-                .parse::<u8>()
-                .unwrap_or(THOST_FTDC_TC_GFD) as i8;
-
-            // 设置成交量类型 (默认任何数量)
-            input_order.VolumeCondition = order.volume_condition
-                .as_deref()
-                .unwrap_or("1")
-                .parse::<u8>()
-                .unwrap_or(THOST_FTDC_VC_AV) as i8;
-
-            // 设置触发条件 (默认立即)
-            input_order.ContingentCondition = THOST_FTDC_CC_Immediately as i8;
-
-            // 设置最小成交量
-            input_order.MinVolume = 1;
-
-            // 设置强平原因 (非强平)
-            input_order.ForceCloseReason = 0; // THOST_FTDC_FCC_NotForceClose
-
-            // 设置自动挂起标志
-            input_order.IsAutoSuspend = 0; // 不自动挂起
-
-            // 获取请求ID
-            let request_id = get_next_request_id();
-
-            println!("📤 [DEBUG] Calling ReqOrderInsert with order_ref: {}, request_id: {}", order_ref, request_id);
-
-            // 调用CTP API插入订单
-            let result = api.req_order_insert(&mut input_order, request_id);
-
-            if result == 0 {
-                println!("✅ [DEBUG] ReqOrderInsert successful");
-                Ok(format!("订单已提交，订单引用: {}", order_ref))
-            } else {
-                println!("❌ [DEBUG] ReqOrderInsert failed with code: {}", result);
-                Err(format!("提交订单失败，错误代码: {}", result))
-            }
-        } else {
-            Err("未找到交易API会话或登录信息".to_string())
-        }
-    }) {
-        Ok(Ok(message)) => ApiResponse {
+// 设置行情合并窗口（毫秒），0 表示逐笔推送
+#[command]
+pub fn set_market_data_conflation(
+    session_id: String,
+    interval_ms: u64,
+) -> ApiResponse<String> {
+    match md_spi::set_conflation(&session_id, interval_ms) {
+        Ok(_) => ApiResponse {
             success: true,
-            data: Some(message),
+            data: Some(format!("Market data conflation set to {} ms", interval_ms)),
             error: None,
         },
-        Ok(Err(error)) => ApiResponse {
+        Err(error) => ApiResponse {
             success: false,
             data: None,
             error: Some(error),
         },
-        Err(_) => ApiResponse {
-            success: false,
-            data: None,
-            error: Some("插入订单时发生系统错误".to_string()),
-        },
     }
 }
 
+// 交易 API 命令
+// 完整的登录握手：OnFrontConnected → ReqAuthenticate → ReqUserLogin → 结算单确认，
+// 只有收到 OnRspUserLogin 后才返回
 #[command]
-pub fn cancel_order(
+pub async fn trader_login(
+    app: tauri::AppHandle,
     session_id: String,
-    cancel_request: CancelOrderRequest,
-) -> ApiResponse<String> {
-    println!("🔍 [DEBUG] cancel_order called with session_id: {}, cancel_request: {:?}", session_id, cancel_request);
+    config: CtpAccountConfig,
+) -> ApiResponse<TraderLoginInfo> {
+    println!("🔍 [DEBUG] trader_login called with session_id: {}", session_id);
 
-    match std::panic::catch_unwind(|| {
-        // 首先检查登录状态
-        let login_status = TRADER_LOGIN_STATUS.lock().unwrap();
-        if !login_status.get(&session_id).unwrap_or(&false) {
-            return Err("CTP 交易 API 未连接，请先登录".to_string());
+    let receiver = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        trader_spi::start_login(&session_id, config, app)
+    })) {
+        Ok(Ok(receiver)) => receiver,
+        Ok(Err(error)) => {
+            println!("❌ [ERROR] Trader login failed: {}", error);
+            return ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            };
+        },
+        Err(_) => {
+            let error = "Trader login panicked".to_string();
+            println!("❌ [ERROR] {}", error);
+            return ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            };
         }
-        drop(login_status); // 释放锁
+    };
 
-        let mut apis = TRADER_APIS.lock().unwrap();
-        let login_info = SESSION_LOGIN_INFO.lock().unwrap();
+    let result = match tokio::time::timeout(trader_spi::LOGIN_TIMEOUT, receiver).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("交易登录流程已中断".to_string()),
+        Err(_) => {
+            trader_spi::cancel_login(&session_id);
+            Err(format!(
+                "交易登录超时（{}秒内未收到 OnRspUserLogin）",
+                trader_spi::LOGIN_TIMEOUT.as_secs()
+            ))
+        },
+    };
 
-        if let (Some(api), Some(account_config)) = (apis.get_mut(&session_id), login_info.get(&session_id)) {
-            println!("✅ [DEBUG] Found Trader API and login info for session: {}", session_id);
+    match result {
+        Ok(info) => {
+            println!(
+                "✅ [SUCCESS] Trader login successful, front_id: {}, session_id: {}, trading_day: {}",
+                info.front_id, info.session_id, info.trading_day
+            );
+            // 登录后先取一次持仓，作为风控净持仓检查的基准
+            let session = session_id.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(error) = fetch_positions(&session).await {
+                    println!("⚠️ [WARN] Initial position query failed: {}", error);
+                }
+            });
+            ApiResponse {
+                success: true,
+                data: Some(info),
+                error: None,
+            }
+        },
+        Err(error) => {
+            println!("❌ [ERROR] Trader login failed: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
 
-            // 引入CTP相关类型
-            use tauri_app_vue_lib::*;
+// 发出一笔报单；wait_for_ack 时发出前先登记等待交易所确认
+pub(crate) fn send_order(
+    session_id: &str,
+    order: &OrderRequest,
+    wait_for_ack: bool,
+) -> Result<PendingInsert, String> {
+    let contingent_condition = order
+        .contingent_condition
+        .unwrap_or(ContingentCondition::Immediately);
+    if contingent_condition == ContingentCondition::ParkedOrder {
+        return Err("预埋单不能直接报单，请使用预埋单接口".to_string());
+    }
+    if contingent_condition.needs_stop_price() && order.stop_price.is_none_or(|price| price <= 0.0) {
+        return Err(format!("条件单（触发条件 {}）需要有效的触发价", contingent_condition));
+    }
 
-            // 创建输入报单操作结构
-            let mut input_order_action = CThostFtdcInputOrderActionField::default();
+    let slot = risk::check(session_id, order).map_err(|rejection| {
+        println!("❌ [ERROR] {}", rejection);
+        rejection.to_string()
+    })?;
 
-            // 填充基本信息
-            copy_str_to_ctp_array(&account_config.broker_id, &mut input_order_action.BrokerID);
-            copy_str_to_ctp_array(&account_config.account, &mut input_order_action.InvestorID);
-            copy_str_to_ctp_array(&account_config.account, &mut input_order_action.UserID);
-            copy_str_to_ctp_array(&cancel_request.instrument_id, &mut input_order_action.InstrumentID);
-            copy_str_to_ctp_array(&cancel_request.order_ref, &mut input_order_action.OrderRef);
+    let submitted = submit_order(session_id, order, wait_for_ack);
+    if submitted.is_err() {
+        // 报单没有发出，不占用报单频率
+        slot.release();
+    }
+    submitted
+}
 
-            // 设置操作引用 (OrderActionRef是整数类型)
-            let action_ref = get_next_order_ref();
-            input_order_action.OrderActionRef = action_ref.parse::<i32>().unwrap_or(1);
+// 风控通过后检查自成交，填写报单并调用 req_order_insert
+fn submit_order(session_id: &str, order: &OrderRequest, wait_for_ack: bool) -> Result<PendingInsert, String> {
+    // 追价、算法、本地触发和平仓的子单同样不能与本账户挂单成交
+    let investor_id = SESSION_LOGIN_INFO
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .map(|config| config.account.clone());
+    if let Some(investor_id) = investor_id {
+        self_trade::check(session_id, &investor_id, order).map_err(|error| {
+            println!("❌ [ERROR] {}", error);
+            error
+        })?;
+    }
 
-            // 设置操作标志为删除
-            input_order_action.ActionFlag = THOST_FTDC_AF_Delete as i8;
+    let mut apis = TRADER_APIS.lock().unwrap();
+    let login_info = SESSION_LOGIN_INFO.lock().unwrap();
 
-            // 设置前置编号和会话编号（如果提供）
-            if let Some(front_id) = cancel_request.front_id {
-                input_order_action.FrontID = front_id;
-            }
-            if let Some(session_id_num) = cancel_request.session_id {
-                input_order_action.SessionID = session_id_num;
-            }
+    let (Some(api), Some(account_config)) = (apis.get_mut(session_id), login_info.get(session_id)) else {
+        return Err("未找到交易API会话或登录信息".to_string());
+    };
+    println!("✅ [DEBUG] Found Trader API and login info for session: {}", session_id);
 
-            // 设置交易所代码（如果提供）
-            if let Some(exchange_id) = &cancel_request.exchange_id {
-                copy_str_to_ctp_array(exchange_id, &mut input_order_action.ExchangeID);
-            }
+    // 引入CTP相关类型
+    use tauri_app_vue_lib::*;
 
-            // 设置报单编号（如果提供）
-            if let Some(order_sys_id) = &cancel_request.order_sys_id {
-                copy_str_to_ctp_array(order_sys_id, &mut input_order_action.OrderSysID);
-            }
+    // 创建输入报单结构
+    let mut input_order = CThostFtdcInputOrderField::default();
 
-            // 获取请求ID
-            let request_id = get_next_request_id();
+    // 填充基本信息
+    copy_str_to_ctp_array(&account_config.broker_id, &mut input_order.BrokerID);
+    copy_str_to_ctp_array(&account_config.account, &mut input_order.InvestorID);
+    copy_str_to_ctp_array(&account_config.account, &mut input_order.UserID);
+    copy_str_to_ctp_array(&order.instrument_id, &mut input_order.InstrumentID);
 
-            println!("📤 [DEBUG] Calling ReqOrderAction with order_ref: {}, action_ref: {}, request_id: {}",
-                     cancel_request.order_ref, action_ref, request_id);
+    // 设置订单引用
+    let order_ref = order_ref::next_order_ref(session_id)?;
+    copy_str_to_ctp_array(&order_ref, &mut input_order.OrderRef);
 
-            // 调用CTP API撤销订单
-            let result = api.req_order_action(&mut input_order_action, request_id);
+    // 设置买卖方向
+    input_order.Direction = order.direction.to_ctp();
 
-            if result == 0 {
-                println!("✅ [DEBUG] ReqOrderAction successful");
-                Ok(format!("撤单请求已提交，订单引用: {}, 操作引用: {}", cancel_request.order_ref, action_ref))
-            } else {
-                println!("❌ [DEBUG] ReqOrderAction failed with code: {}", result);
-                Err(format!("提交撤单失败，错误代码: {}", result))
-            }
-        } else {
-            Err("未找到交易API会话或登录信息".to_string())
-        }
-    }) {
-        Ok(Ok(message)) => ApiResponse {
-            success: true,
-            data: Some(message),
-            error: None,
-        },
-        Ok(Err(error)) => ApiResponse {
-            success: false,
-            data: None,
-            error: Some(error),
-        },
-        Err(_) => ApiResponse {
-            success: false,
-            data: None,
-            error: Some("撤销订单时发生系统错误".to_string()),
-        },
+    // 设置价格和数量
+    input_order.LimitPrice = order.price;
+    input_order.VolumeTotalOriginal = order.volume;
+
+    // 设置报单价格条件
+    input_order.OrderPriceType = order.order_type.to_ctp();
+
+    // 设置开平标志 (默认开仓)
+    input_order.CombOffsetFlag[0] = order.offset_flag.unwrap_or(OffsetFlag::Open).to_ctp();
+
+    // 设置投机套保标志 (默认投机)
+    input_order.CombHedgeFlag[0] = order.hedge_flag.unwrap_or(HedgeFlag::Speculation).to_ctp();
+
+    // 设置有效期类型 (默认当日有效)
+    input_order.TimeCondition = order.time_condition.unwrap_or(TimeCondition::Gfd).to_ctp();
+
+    // 设置成交量类型 (默认任何数量)
+    input_order.VolumeCondition = order
+        .volume_condition
+        .unwrap_or(VolumeCondition::AnyVolume)
+        .to_ctp();
+
+    // 设置触发条件 (默认立即)
+    input_order.ContingentCondition = contingent_condition.to_ctp();
+    input_order.StopPrice = order.stop_price.unwrap_or_default();
+
+    // 设置最小成交量
+    input_order.MinVolume = 1;
+
+    // 设置强平原因 (非强平)
+    input_order.ForceCloseReason = THOST_FTDC_FCC_NotForceClose as i8;
+
+    // 设置自动挂起标志
+    input_order.IsAutoSuspend = 0; // 不自动挂起
+
+    // 获取请求ID
+    let request_id = get_next_request_id();
+    input_order.RequestID = request_id;
+
+    // 等待确认时先登记，避免回报先于登记到达
+    let (front_id, ctp_session_id) = order_store::own_front_session(session_id);
+    let key = OrderKey {
+        front_id,
+        session_id: ctp_session_id,
+        order_ref: order_ref.clone(),
+    };
+    let receiver = wait_for_ack
+        .then(|| order_store::register_ack_waiter(session_id, key.clone()));
+
+    println!("📤 [DEBUG] Calling ReqOrderInsert with order_ref: {}, request_id: {}", order_ref, request_id);
+
+    // 调用CTP API插入订单
+    let result = api.req_order_insert(&mut input_order, request_id);
+
+    if result == 0 {
+        println!("✅ [DEBUG] ReqOrderInsert successful");
+        order_store::record_submitted(session_id, &input_order);
+        Ok(PendingInsert {
+            result: OrderInsertResult {
+                order_ref,
+                front_id,
+                session_id: ctp_session_id,
+                request_id,
+                order_sys_id: String::new(),
+                order_status: String::new(),
+                acknowledged: false,
+            },
+            key,
+            receiver,
+        })
+    } else {
+        println!("❌ [DEBUG] ReqOrderInsert failed with code: {}", result);
+        order_store::remove_ack_waiter(session_id, &key);
+        Err(format!("提交订单失败，错误代码: {}", result))
     }
 }
 
-// 查询账户资金
 #[command]
-pub fn query_account(
+pub async fn insert_order(
     session_id: String,
-) -> ApiResponse<AccountInfo> {
-    println!("🔍 [DEBUG] query_account called with session_id: {}", session_id);
+    order: OrderRequest,
+    wait_for_ack: Option<bool>,
+    self_trade: Option<SelfTradeAction>,
+) -> ApiResponse<OrderInsertResult> {
+    println!("🔍 [DEBUG] insert_order called with session_id: {}, order: {:?}", session_id, order);
+    let wait_for_ack = wait_for_ack.unwrap_or(false);
 
-    match std::panic::catch_unwind(|| {
+    if self_trade.unwrap_or_default() == SelfTradeAction::CancelResting {
+        if let Err(error) = cancel_self_trade(&session_id, &order).await {
+            println!("❌ [ERROR] {}", error);
+            return ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            };
+        }
+    }
+
+    let submitted = std::panic::catch_unwind(|| {
         // 首先检查登录状态
         let login_status = TRADER_LOGIN_STATUS.lock().unwrap();
         if !login_status.get(&session_id).unwrap_or(&false) {
@@ -918,205 +916,1117 @@ pub fn query_account(
         }
         drop(login_status); // 释放锁
 
-        let apis = TRADER_APIS.lock().unwrap();
-
-        if let Some(_api) = apis.get(&session_id) {
-            println!("✅ [DEBUG] Found Trader API for session: {}", session_id);
-
-            // 注意：这里应该调用真实的CTP查询账户API
-            // 由于当前的实现是基于异步流的，这里返回模拟数据
-            let account_info = AccountInfo {
-                broker_id: "9999".to_string(),
-                account_id: "test_account".to_string(),
-                pre_mortgage: 0.0,
-                pre_credit: 0.0,
-                pre_deposit: 0.0,
-                pre_balance: 100000.0,
-                pre_margin: 0.0,
-                interest_base: 0.0,
-                interest: 0.0,
-                deposit: 0.0,
-                withdraw: 0.0,
-                frozen_margin: 0.0,
-                frozen_cash: 0.0,
-                frozen_commission: 0.0,
-                curr_margin: 0.0,
-                cash_in: 0.0,
-                commission: 0.0,
-                close_profit: 0.0,
-                position_profit: 0.0,
-                balance: 100000.0,
-                available: 95000.0,
-                withdraw_quota: 95000.0,
-                reserve: 0.0,
-                trading_day: "20241221".to_string(),
-                settlement_id: 1,
-                credit: 0.0,
-                mortgage: 0.0,
-                exchange_margin: 0.0,
-                delivery_margin: 0.0,
-                exchange_delivery_margin: 0.0,
-                reserve_balance: 0.0,
-                currency_id: "CNY".to_string(),
-                pre_fund_mortgage_in: 0.0,
-                pre_fund_mortgage_out: 0.0,
-                fund_mortgage_in: 0.0,
-                fund_mortgage_out: 0.0,
-                fund_mortgage_available: 0.0,
-                mortgage_able_fund: 0.0,
-                spec_product_margin: 0.0,
-                spec_product_frozen_margin: 0.0,
-                spec_product_commission: 0.0,
-                spec_product_frozen_commission: 0.0,
-                spec_product_position_profit: 0.0,
-                spec_product_close_profit: 0.0,
-                spec_product_position_profit_by_alg: 0.0,
-                spec_product_exchange_margin: 0.0,
-                bis_margin: 0.0,
-                bis_frozen_margin: 0.0,
-                bis_commission: 0.0,
-                bis_frozen_commission: 0.0,
-                bis_position_profit: 0.0,
-                bis_close_profit: 0.0,
-                bis_position_profit_by_alg: 0.0,
-                bis_exchange_margin: 0.0,
-                frozen_swap: 0.0,
-                remain_swap: 0.0,
-            };
+        send_order(&session_id, &order, wait_for_ack)
+    });
 
-            Ok(account_info)
-        } else {
-            Err(format!("Session ID {} not found", session_id))
-        }
-    }) {
-        Ok(Ok(account_info)) => {
-            println!("✅ [SUCCESS] Account query successful");
-            ApiResponse {
-                success: true,
-                data: Some(account_info),
-                error: None,
-            }
-        },
+    let PendingInsert { result: mut submitted, key, receiver } = match submitted {
+        Ok(Ok(submitted)) => submitted,
         Ok(Err(error)) => {
-            println!("❌ [ERROR] Account query failed: {}", error);
-            ApiResponse {
+            return ApiResponse {
                 success: false,
                 data: None,
                 error: Some(error),
-            }
+            };
         },
         Err(_) => {
-            let error = "Account query panicked".to_string();
-            println!("❌ [ERROR] {}", error);
-            ApiResponse {
+            return ApiResponse {
                 success: false,
                 data: None,
-                error: Some(error),
-            }
+                error: Some("插入订单时发生系统错误".to_string()),
+            };
         }
-    }
-}
+    };
 
-// 查询持仓信息
-#[command]
-pub fn query_position(
-    session_id: String,
-) -> ApiResponse<Vec<PositionInfo>> {
-    println!("🔍 [DEBUG] query_position called with session_id: {}", session_id);
+    let Some(receiver) = receiver else {
+        return ApiResponse {
+            success: true,
+            data: Some(submitted),
+            error: None,
+        };
+    };
+
+    let result = match tokio::time::timeout(order_store::ORDER_ACK_TIMEOUT, receiver).await {
+        Ok(Ok(Ok(order))) => {
+            submitted.order_sys_id = order.order_sys_id;
+            submitted.order_status = order.order_status;
+            submitted.acknowledged = true;
+            Ok(submitted)
+        },
+        Ok(Ok(Err(reason))) => Err(format!("报单被拒绝: {}", reason)),
+        Ok(Err(_)) => Err("等待报单确认已中断".to_string()),
+        Err(_) => {
+            order_store::remove_ack_waiter(&session_id, &key);
+            Err(format!(
+                "等待报单 {} 确认超时（{}秒），报单状态未知，请查询报单",
+                submitted.order_ref,
+                order_store::ORDER_ACK_TIMEOUT.as_secs()
+            ))
+        }
+    };
+
+    match result {
+        Ok(submitted) => {
+            println!(
+                "✅ [SUCCESS] Order acknowledged, order_ref: {}, order_sys_id: {}",
+                submitted.order_ref, submitted.order_sys_id
+            );
+            ApiResponse {
+                success: true,
+                data: Some(submitted),
+                error: None,
+            }
+        },
+        Err(error) => {
+            println!("❌ [ERROR] Order not acknowledged: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 选择 CancelResting 时先撤掉会与新报单成交的本账户挂单；风控不通过的报单不撤任何挂单。
+// 默认的 Reject 在 send_order 中检查
+async fn cancel_self_trade(session_id: &str, order: &OrderRequest) -> Result<(), String> {
+    let Ok(config) = logged_in_trader_config(session_id) else {
+        return Ok(());
+    };
+    let crossing = self_trade::crossing_orders(session_id, &config.account, order);
+    if crossing.is_empty() {
+        return Ok(());
+    }
+    // 报单频率在 send_order 中计入
+    risk::check_without_rate(session_id, order).map_err(|rejection| rejection.to_string())?;
+
+    let refs: Vec<&str> = crossing.iter().map(|o| o.order_ref.as_str()).collect();
+    println!("📤 [DEBUG] Canceling resting orders {:?} to prevent self-trade", refs);
+    let deadline = tokio::time::Instant::now() + order_store::ORDER_ACK_TIMEOUT;
+    for resting in &crossing {
+        let pending = send_cancel(session_id, resting)?;
+        let canceled = await_cancel(session_id, pending, deadline).await?;
+        println!("✅ [DEBUG] Resting order {} canceled before insert", canceled.order_ref);
+    }
+    Ok(())
+}
+
+// 对报单簿中的一笔报单发出撤单，发出前先登记等待撤单结果
+pub(crate) fn send_cancel(session_id: &str, target: &OrderInfo) -> Result<PendingCancel, String> {
+    let mut apis = TRADER_APIS.lock().unwrap();
+    let login_info = SESSION_LOGIN_INFO.lock().unwrap();
+
+    let (Some(api), Some(account_config)) = (apis.get_mut(session_id), login_info.get(session_id)) else {
+        return Err("未找到交易API会话或登录信息".to_string());
+    };
+
+    // 先占用撤单次数，批量撤单时在途的撤单也计入上限
+    cancel_quota::reserve(session_id, target)?;
+
+    // 引入CTP相关类型
+    use tauri_app_vue_lib::*;
+
+    // 创建输入报单操作结构
+    let mut input_order_action = CThostFtdcInputOrderActionField::default();
+
+    // 填充基本信息
+    copy_str_to_ctp_array(&account_config.broker_id, &mut input_order_action.BrokerID);
+    copy_str_to_ctp_array(&account_config.account, &mut input_order_action.InvestorID);
+    copy_str_to_ctp_array(&account_config.account, &mut input_order_action.UserID);
+    copy_str_to_ctp_array(&target.instrument_id, &mut input_order_action.InstrumentID);
+    copy_str_to_ctp_array(&target.order_ref, &mut input_order_action.OrderRef);
+
+    // 设置操作引用，与报单引用分开编号
+    let action_ref = order_ref::next_action_ref(session_id);
+    input_order_action.OrderActionRef = action_ref;
+
+    // 设置操作标志为删除
+    input_order_action.ActionFlag = THOST_FTDC_AF_Delete as i8;
+
+    // 两组标识都填上，柜台任选其一定位报单
+    input_order_action.FrontID = target.front_id;
+    input_order_action.SessionID = target.session_id;
+    copy_str_to_ctp_array(&target.exchange_id, &mut input_order_action.ExchangeID);
+    copy_str_to_ctp_array(&target.order_sys_id, &mut input_order_action.OrderSysID);
+
+    // 获取请求ID
+    let request_id = get_next_request_id();
+    input_order_action.RequestID = request_id;
+
+    // 先登记等待撤单结果，避免回报先于登记到达
+    let key = OrderKey::of(target);
+    let receiver = order_store::register_cancel_waiter(session_id, key.clone());
+
+    println!("📤 [DEBUG] Calling ReqOrderAction with order_ref: {}, action_ref: {}, request_id: {}",
+             target.order_ref, action_ref, request_id);
+
+    // 调用CTP API撤销订单
+    let result = api.req_order_action(&mut input_order_action, request_id);
+
+    if result == 0 {
+        println!("✅ [DEBUG] ReqOrderAction successful");
+        Ok(PendingCancel {
+            result: OrderCancelResult {
+                order_ref: target.order_ref.clone(),
+                front_id: target.front_id,
+                session_id: target.session_id,
+                exchange_id: target.exchange_id.clone(),
+                order_sys_id: target.order_sys_id.clone(),
+                action_ref,
+                request_id,
+                order_status: target.order_status.clone(),
+                volume_traded: target.volume_traded,
+            },
+            key,
+            receiver,
+        })
+    } else {
+        println!("❌ [DEBUG] ReqOrderAction failed with code: {}", result);
+        order_store::remove_cancel_waiter(session_id, &key);
+        cancel_quota::release(session_id, &key);
+        Err(format!("提交撤单失败，错误代码: {}", result))
+    }
+}
+
+// 等待撤单结果：OnRtnOrder（已撤单）或 OnRspOrderAction / OnErrRtnOrderAction（被拒）
+pub(crate) async fn await_cancel(
+    session_id: &str,
+    pending: PendingCancel,
+    deadline: tokio::time::Instant,
+) -> Result<OrderCancelResult, String> {
+    let PendingCancel { mut result, key, receiver } = pending;
+    match tokio::time::timeout_at(deadline, receiver).await {
+        Ok(Ok(Ok(order))) => {
+            result.order_status = order.order_status;
+            result.volume_traded = order.volume_traded;
+            Ok(result)
+        },
+        Ok(Ok(Err(reason))) => {
+            cancel_quota::release(session_id, &key);
+            Err(format!("撤单失败: {}", reason))
+        },
+        Ok(Err(_)) => {
+            cancel_quota::release(session_id, &key);
+            Err("等待撤单回报已中断".to_string())
+        },
+        Err(_) => {
+            order_store::remove_cancel_waiter(session_id, &key);
+            cancel_quota::release(session_id, &key);
+            Err(format!(
+                "撤单请求已提交，{}秒内未收到报单 {} 的撤单回报",
+                order_store::ORDER_ACK_TIMEOUT.as_secs(),
+                result.order_ref
+            ))
+        }
+    }
+}
+
+#[command]
+pub async fn cancel_order(
+    session_id: String,
+    cancel_request: OrderLookup,
+) -> ApiResponse<OrderCancelResult> {
+    println!("🔍 [DEBUG] cancel_order called with session_id: {}, cancel_request: {:?}", session_id, cancel_request);
+
+    let sent = std::panic::catch_unwind(|| {
+        // 首先检查登录状态
+        let login_status = TRADER_LOGIN_STATUS.lock().unwrap();
+        if !login_status.get(&session_id).unwrap_or(&false) {
+            return Err("CTP 交易 API 未连接，请先登录".to_string());
+        }
+        drop(login_status); // 释放锁
+
+        // 从报单簿补全撤单所需的 FrontID/SessionID/ExchangeID/OrderSysID
+        let own = order_store::own_front_session(&session_id);
+        let target = order_store::with_book(&session_id, |book| {
+            cancel_request.find(book, own).map(|managed| managed.order.clone())
+        })
+        .ok_or_else(|| "未找到要撤销的报单".to_string())?;
+        if order_store::is_final_status(&target.order_status) {
+            return Err(format!(
+                "报单 {} 已结束（状态 {}），无法撤单",
+                target.order_ref, target.order_status
+            ));
+        }
+
+        send_cancel(&session_id, &target)
+    });
+
+    let pending = match sent {
+        Ok(Ok(pending)) => pending,
+        Ok(Err(error)) => {
+            println!("❌ [ERROR] Cancel not sent: {}", error);
+            return ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            };
+        },
+        Err(_) => {
+            return ApiResponse {
+                success: false,
+                data: None,
+                error: Some("撤销订单时发生系统错误".to_string()),
+            };
+        }
+    };
+
+    let deadline = tokio::time::Instant::now() + order_store::ORDER_ACK_TIMEOUT;
+    match await_cancel(&session_id, pending, deadline).await {
+        Ok(cancel) => {
+            println!("✅ [SUCCESS] Order canceled, order_ref: {}", cancel.order_ref);
+            ApiResponse {
+                success: true,
+                data: Some(cancel),
+                error: None,
+            }
+        },
+        Err(error) => {
+            println!("❌ [ERROR] Cancel failed: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 批量撤单：按条件从报单簿选出未结束的报单，逐笔发出 ReqOrderAction 后一起等待结果。
+// ReqBatchOrderAction 只适用于做市商报价，普通报单不能用它撤销。
+#[command]
+pub async fn cancel_orders(
+    session_id: String,
+    filter: Option<OrderFilter>,
+) -> ApiResponse<Vec<CancelOutcome>> {
+    println!("🔍 [DEBUG] cancel_orders called with session_id: {}, filter: {:?}", session_id, filter);
+
+    if !*TRADER_LOGIN_STATUS.lock().unwrap().get(&session_id).unwrap_or(&false) {
+        return ApiResponse {
+            success: false,
+            data: None,
+            error: Some("CTP 交易 API 未连接，请先登录".to_string()),
+        };
+    }
+
+    let filter = filter.unwrap_or_default();
+    let targets = order_store::with_book(&session_id, |book| book.working_orders(&filter));
+    println!("📤 [DEBUG] Canceling {} working orders", targets.len());
+
+    let sent: Vec<_> = targets
+        .iter()
+        .map(|target| send_cancel(&session_id, target))
+        .collect();
+
+    let deadline = tokio::time::Instant::now() + order_store::ORDER_ACK_TIMEOUT;
+    let results = futures::future::join_all(sent.into_iter().map(|sent| {
+        let session_id = session_id.as_str();
+        async move {
+            match sent {
+                Ok(pending) => await_cancel(session_id, pending, deadline).await,
+                Err(error) => Err(error),
+            }
+        }
+    }))
+    .await;
+
+    let outcomes: Vec<CancelOutcome> = targets
+        .into_iter()
+        .zip(results)
+        .map(|(target, result)| {
+            let (order_status, error) = match result {
+                Ok(cancel) => (cancel.order_status, None),
+                Err(error) => (target.order_status.clone(), Some(error)),
+            };
+            CancelOutcome {
+                success: error.is_none(),
+                order_ref: target.order_ref,
+                front_id: target.front_id,
+                session_id: target.session_id,
+                exchange_id: target.exchange_id,
+                order_sys_id: target.order_sys_id,
+                instrument_id: target.instrument_id,
+                direction: target.direction,
+                limit_price: target.limit_price,
+                order_status,
+                error,
+            }
+        })
+        .collect();
+
+    let failed = outcomes.iter().filter(|o| !o.success).count();
+    if failed > 0 {
+        println!("⚠️ [WARN] {} of {} cancels failed", failed, outcomes.len());
+    } else {
+        println!("✅ [SUCCESS] Canceled {} orders", outcomes.len());
+    }
+
+    ApiResponse {
+        success: true,
+        data: Some(outcomes),
+        error: None,
+    }
+}
+
+// 一键撤销本会话全部未结束的报单
+#[command]
+pub async fn cancel_all_orders(session_id: String) -> ApiResponse<Vec<CancelOutcome>> {
+    cancel_orders(session_id, None).await
+}
+
+// 已登录交易会话的账户配置，查询类请求用它填写 BrokerID/InvestorID
+fn logged_in_trader_config(session_id: &str) -> Result<CtpAccountConfig, String> {
+    if !*TRADER_LOGIN_STATUS.lock().unwrap().get(session_id).unwrap_or(&false) {
+        return Err("CTP 交易 API 未连接，请先登录".to_string());
+    }
+    SESSION_LOGIN_INFO
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .ok_or_else(|| format!("未找到会话 {} 的登录信息", session_id))
+}
+
+// 查询当日委托，经查询队列按流控发出
+#[command]
+pub async fn query_order(
+    session_id: String,
+    priority: Option<QueryPriority>,
+) -> ApiResponse<Vec<OrderInfo>> {
+    println!("🔍 [DEBUG] query_order called with session_id: {}", session_id);
+
+    let result = match logged_in_trader_config(&session_id) {
+        Ok(config) => {
+            let mut req = tauri_app_vue_lib::CThostFtdcQryOrderField::default();
+            copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+            copy_str_to_ctp_array(&config.account, &mut req.InvestorID);
+            query_scheduler::query(
+                &session_id,
+                priority.unwrap_or_default(),
+                QUERY_TIMEOUT,
+                |api, request_id| api.req_qry_order(&mut req, request_id),
+                |msg| match msg {
+                    trader_api::CThostFtdcTraderSpiOutput::OnRspQryOrder(p) => {
+                        p.p_order.as_ref().map(OrderInfo::from)
+                    }
+                    _ => None,
+                },
+            )
+            .await
+        }
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(orders) => {
+            println!("✅ [SUCCESS] Order query successful, {} orders", orders.len());
+            ApiResponse {
+                success: true,
+                data: Some(orders),
+                error: None,
+            }
+        },
+        Err(error) => {
+            println!("❌ [ERROR] Order query failed: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 查询当日成交
+#[command]
+pub async fn query_trade(
+    session_id: String,
+    priority: Option<QueryPriority>,
+) -> ApiResponse<Vec<TradeInfo>> {
+    println!("🔍 [DEBUG] query_trade called with session_id: {}", session_id);
+
+    let result = match logged_in_trader_config(&session_id) {
+        Ok(config) => {
+            let mut req = tauri_app_vue_lib::CThostFtdcQryTradeField::default();
+            copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+            copy_str_to_ctp_array(&config.account, &mut req.InvestorID);
+            query_scheduler::query(
+                &session_id,
+                priority.unwrap_or_default(),
+                QUERY_TIMEOUT,
+                |api, request_id| api.req_qry_trade(&mut req, request_id),
+                |msg| match msg {
+                    trader_api::CThostFtdcTraderSpiOutput::OnRspQryTrade(p) => {
+                        p.p_trade.as_ref().map(TradeInfo::from)
+                    }
+                    _ => None,
+                },
+            )
+            .await
+        }
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(trades) => {
+            println!("✅ [SUCCESS] Trade query successful, {} trades", trades.len());
+            ApiResponse {
+                success: true,
+                data: Some(trades),
+                error: None,
+            }
+        },
+        Err(error) => {
+            println!("❌ [ERROR] Trade query failed: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 本地报单簿中的当日报单，active_only 为 true 时只返回未完结的报单
+#[command]
+pub fn list_orders(session_id: String, active_only: Option<bool>) -> ApiResponse<Vec<ManagedOrder>> {
+    let active_only = active_only.unwrap_or(false);
+    let orders = order_store::with_book(&session_id, |book| {
+        book.orders()
+            .iter()
+            .filter(|order| !active_only || order.is_active())
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+    ApiResponse {
+        success: true,
+        data: Some(orders),
+        error: None,
+    }
+}
+
+// 按 OrderRef 或 ExchangeID + OrderSysID 查找单个报单
+#[command]
+pub fn get_order(session_id: String, lookup: OrderLookup) -> ApiResponse<ManagedOrder> {
+    let own = order_store::own_front_session(&session_id);
+    match order_store::with_book(&session_id, |book| lookup.find(book, own).cloned()) {
+        Some(order) => ApiResponse {
+            success: true,
+            data: Some(order),
+            error: None,
+        },
+        None => ApiResponse {
+            success: false,
+            data: None,
+            error: Some(format!("未找到报单: {:?}", lookup)),
+        },
+    }
+}
+
+// 本地报单簿中的当日成交，可按合约筛选
+#[command]
+pub fn list_trades(session_id: String, instrument_id: Option<String>) -> ApiResponse<Vec<TradeInfo>> {
+    let trades = order_store::with_book(&session_id, |book| {
+        book.trades()
+            .iter()
+            .filter(|trade| {
+                instrument_id
+                    .as_deref()
+                    .is_none_or(|id| id.is_empty() || trade.instrument_id == id)
+            })
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+    ApiResponse {
+        success: true,
+        data: Some(trades),
+        error: None,
+    }
+}
+
+// 查询账户资金，同时刷新该会话的资金快照
+// currency_id 为空时优先返回人民币账户
+#[command]
+pub async fn query_account(
+    session_id: String,
+    currency_id: Option<String>,
+) -> ApiResponse<AccountInfo> {
+    println!("🔍 [DEBUG] query_account called with session_id: {}", session_id);
+
+    let result = match logged_in_trader_config(&session_id) {
+        Ok(config) => {
+            // CurrencyID 留空查询全部币种
+            let mut req = tauri_app_vue_lib::CThostFtdcQryTradingAccountField::default();
+            copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+            copy_str_to_ctp_array(&config.account, &mut req.InvestorID);
+            query_scheduler::query(
+                &session_id,
+                QueryPriority::High,
+                QUERY_TIMEOUT,
+                |api, request_id| api.req_qry_trading_account(&mut req, request_id),
+                |msg| match msg {
+                    trader_api::CThostFtdcTraderSpiOutput::OnRspQryTradingAccount(p) => {
+                        p.p_trading_account.as_ref().map(AccountInfo::from)
+                    }
+                    _ => None,
+                },
+            )
+            .await
+        }
+        Err(error) => Err(error),
+    };
+
+    let result = result.and_then(|accounts| {
+        let snapshot = AccountSnapshot::new(accounts);
+        let selected = snapshot.select(currency_id.as_deref()).cloned();
+        account::store_snapshot(&session_id, snapshot);
+        selected.ok_or_else(|| {
+            format!(
+                "未查询到币种 {} 的资金账户",
+                currency_id.as_deref().unwrap_or(account::DEFAULT_CURRENCY_ID)
+            )
+        })
+    });
+
+    match result {
+        Ok(account_info) => {
+            println!(
+                "✅ [SUCCESS] Account query successful, currency: {}, available: {}",
+                account_info.currency_id, account_info.available
+            );
+            ApiResponse {
+                success: true,
+                data: Some(account_info),
+                error: None,
+            }
+        },
+        Err(error) => {
+            println!("❌ [ERROR] Account query failed: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 最近一次查询成功的全部币种资金及其时间，不发起新的查询
+#[command]
+pub fn get_account_snapshot(session_id: String) -> ApiResponse<AccountSnapshot> {
+    match account::cached_snapshot(&session_id) {
+        Some(snapshot) => ApiResponse {
+            success: true,
+            data: Some(snapshot),
+            error: None,
+        },
+        None => ApiResponse {
+            success: false,
+            data: None,
+            error: Some("尚未查询过资金账户".to_string()),
+        },
+    }
+}
+
+// 查询全部持仓记录
+async fn fetch_positions(session_id: &str) -> Result<Vec<PositionInfo>, String> {
+    let config = logged_in_trader_config(session_id)?;
+    let mut req = tauri_app_vue_lib::CThostFtdcQryInvestorPositionField::default();
+    copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+    copy_str_to_ctp_array(&config.account, &mut req.InvestorID);
+    query_scheduler::query(
+        session_id,
+        QueryPriority::Normal,
+        QUERY_TIMEOUT,
+        |api, request_id| api.req_qry_investor_position(&mut req, request_id),
+        |msg| match msg {
+            trader_api::CThostFtdcTraderSpiOutput::OnRspQryInvestorPosition(p) => {
+                p.p_investor_position.as_ref().map(PositionInfo::from)
+            }
+            _ => None,
+        },
+    )
+    .await
+    .inspect(|rows| risk::record_positions(session_id, rows))
+}
+
+// 平仓：查询最新持仓，按交易所规则拆成平今/平昨子单后逐笔发出，作为一个父单跟踪
+#[command]
+pub async fn close_position(
+    session_id: String,
+    request: CloseRequest,
+) -> ApiResponse<CloseParent> {
+    println!("🔍 [DEBUG] close_position called with session_id: {}, request: {:?}", session_id, request);
+
+    let hedge_flag = request.hedge_flag.unwrap_or(HedgeFlag::Speculation);
+    let posi_direction = close_router::closed_posi_direction(request.direction);
+    let planned = fetch_positions(&session_id).await.and_then(|rows| {
+        let position = position::merge_positions(&rows)
+            .into_iter()
+            .find(|p| {
+                p.instrument_id == request.instrument_id
+                    && p.posi_direction == posi_direction
+                    && p.hedge_flag == hedge_flag.to_string()
+            })
+            .ok_or_else(|| format!("{} 没有可平的持仓", request.instrument_id))?;
+        let legs = close_router::split_close(&position, request.volume)?;
+        Ok((position.exchange_id, legs))
+    });
+    let (exchange_id, legs) = match planned {
+        Ok(planned) => planned,
+        Err(error) => {
+            println!("❌ [ERROR] Close rejected: {}", error);
+            return ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            };
+        }
+    };
+
+    let children: Vec<CloseChild> = legs
+        .into_iter()
+        .map(|leg| {
+            let child_order = OrderRequest {
+                instrument_id: request.instrument_id.clone(),
+                direction: request.direction,
+                price: request.price,
+                volume: leg.volume,
+                order_type: request.order_type,
+                offset_flag: Some(leg.offset_flag),
+                hedge_flag: Some(hedge_flag),
+                time_condition: request.time_condition,
+                volume_condition: None,
+                contingent_condition: None,
+                stop_price: None,
+            };
+            let sent = send_order(&session_id, &child_order, false);
+            CloseChild {
+                offset_flag: leg.offset_flag,
+                volume: leg.volume,
+                key: sent.as_ref().ok().map(|pending| pending.key.clone()),
+                order_status: String::new(),
+                filled_volume: 0,
+                error: sent.err(),
+            }
+        })
+        .collect();
+
+    let created_at = chrono::Local::now().timestamp_millis();
+    let parent = CloseParent {
+        parent_id: format!("close_{}_{}", created_at, get_next_request_id()),
+        instrument_id: request.instrument_id,
+        exchange_id,
+        direction: request.direction,
+        price: request.price,
+        volume: request.volume,
+        filled_volume: 0,
+        created_at,
+        children,
+    };
+    close_router::store(&session_id, parent.clone());
+
+    let failed: Vec<&str> = parent
+        .children
+        .iter()
+        .filter_map(|child| child.error.as_deref())
+        .collect();
+    if failed.len() == parent.children.len() {
+        println!("❌ [ERROR] Close orders not sent: {:?}", failed);
+        return ApiResponse {
+            success: false,
+            data: None,
+            error: Some(format!("平仓子单发送失败: {}", failed.join("; "))),
+        };
+    }
+    println!("✅ [SUCCESS] Close {} sent as {} child orders", parent.parent_id, parent.children.len());
+    ApiResponse {
+        success: true,
+        data: Some(parent),
+        error: None,
+    }
+}
+
+// 本会话的平仓父单及子单最新状态
+#[command]
+pub fn list_close_orders(session_id: String) -> ApiResponse<Vec<CloseParent>> {
+    ApiResponse {
+        success: true,
+        data: Some(close_router::parents(&session_id)),
+        error: None,
+    }
+}
+
+// 提交本地条件单，由行情驱动触发
+#[command]
+pub fn place_local_trigger(
+    session_id: String,
+    request: LocalTriggerRequest,
+) -> ApiResponse<LocalTrigger> {
+    println!("🔍 [DEBUG] place_local_trigger called with session_id: {}, request: {:?}", session_id, request);
+
+    let placed = logged_in_trader_config(&session_id).and_then(|config| {
+        let account_key = order_ref::account_key(&config.broker_id, &config.account);
+        local_triggers::place(&session_id, &account_key, request)
+    });
+    match placed {
+        Ok(trigger) => {
+            println!("✅ [SUCCESS] Local trigger {} placed", trigger.trigger_id);
+            ApiResponse {
+                success: true,
+                data: Some(trigger),
+                error: None,
+            }
+        }
+        Err(error) => {
+            println!("❌ [ERROR] Failed to place local trigger: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+#[command]
+pub fn cancel_local_trigger(session_id: String, trigger_id: String) -> ApiResponse<LocalTrigger> {
+    println!("🔍 [DEBUG] cancel_local_trigger called with session_id: {}, trigger_id: {}", session_id, trigger_id);
+
+    match local_triggers::cancel(&session_id, &trigger_id) {
+        Ok(trigger) => ApiResponse {
+            success: true,
+            data: Some(trigger),
+            error: None,
+        },
+        Err(error) => ApiResponse {
+            success: false,
+            data: None,
+            error: Some(error),
+        },
+    }
+}
+
+// 本会话的本地条件单，含已触发和已撤销的记录
+#[command]
+pub fn list_local_triggers(session_id: String) -> ApiResponse<Vec<LocalTrigger>> {
+    ApiResponse {
+        success: true,
+        data: Some(local_triggers::list(&session_id)),
+        error: None,
+    }
+}
+
+// 发出预埋单相关请求并等待柜台应答，应答中带错误信息时返回错误
+async fn parked_request<T>(
+    session_id: &str,
+    send: impl FnOnce(&mut tauri_app_vue_lib::CThostFtdcTraderApi, i32) -> i32,
+    extract: impl FnMut(trader_api::CThostFtdcTraderSpiOutput) -> Option<T>,
+) -> Result<T, String> {
+    rsp_correlator::send(session_id, send)
+        .map_err(|e| e.to_string())?
+        .wait(order_store::ORDER_ACK_TIMEOUT, extract)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "柜台未返回预埋单应答".to_string())
+}
+
+// 预埋报单：存在柜台，开盘后由柜台自动报出
+#[command]
+pub async fn park_order(session_id: String, order: OrderRequest) -> ApiResponse<ParkedOrderInfo> {
+    println!("🔍 [DEBUG] park_order called with session_id: {}, order: {:?}", session_id, order);
+
+    // 预埋单开盘后由柜台发出，不再经过本地风控，这里先检查（报单频率除外）
+    let field = logged_in_trader_config(&session_id).and_then(|config| {
+        risk::check_without_rate(&session_id, &order).map_err(|rejection| rejection.to_string())?;
+        parked_orders::parked_order_field(&config, &order)
+    });
+    let result = match field {
+        Ok(mut field) => {
+            parked_request(
+                &session_id,
+                |api, request_id| {
+                    field.RequestID = request_id;
+                    api.req_parked_order_insert(&mut field, request_id)
+                },
+                |msg| match msg {
+                    trader_api::CThostFtdcTraderSpiOutput::OnRspParkedOrderInsert(p) => {
+                        p.p_parked_order.as_ref().map(ParkedOrderInfo::from)
+                    }
+                    _ => None,
+                },
+            )
+            .await
+        }
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(parked) => {
+            println!("✅ [SUCCESS] Order parked, parked_order_id: {}", parked.parked_order_id);
+            ApiResponse {
+                success: true,
+                data: Some(parked),
+                error: None,
+            }
+        }
+        Err(error) => {
+            println!("❌ [ERROR] Failed to park order: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 预埋撤单：对报单簿中的报单登记撤单，开盘后由柜台自动发出
+#[command]
+pub async fn park_cancel(session_id: String, cancel_request: OrderLookup) -> ApiResponse<ParkedCancelInfo> {
+    println!("🔍 [DEBUG] park_cancel called with session_id: {}, cancel_request: {:?}", session_id, cancel_request);
+
+    let field = logged_in_trader_config(&session_id).and_then(|config| {
+        let own = order_store::own_front_session(&session_id);
+        let target = order_store::with_book(&session_id, |book| {
+            cancel_request.find(book, own).map(|managed| managed.order.clone())
+        })
+        .ok_or_else(|| "未找到要撤销的报单".to_string())?;
+        if order_store::is_final_status(&target.order_status) {
+            return Err(format!(
+                "报单 {} 已结束（状态 {}），无法撤单",
+                target.order_ref, target.order_status
+            ));
+        }
+        cancel_quota::check(&session_id, &target.instrument_id)?;
+        Ok(parked_orders::parked_cancel_field(&config, &target))
+    });
+    let result = match field {
+        Ok(mut field) => {
+            parked_request(
+                &session_id,
+                |api, request_id| {
+                    field.RequestID = request_id;
+                    api.req_parked_order_action(&mut field, request_id)
+                },
+                |msg| match msg {
+                    trader_api::CThostFtdcTraderSpiOutput::OnRspParkedOrderAction(p) => {
+                        p.p_parked_order_action.as_ref().map(ParkedCancelInfo::from)
+                    }
+                    _ => None,
+                },
+            )
+            .await
+        }
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(parked) => {
+            println!("✅ [SUCCESS] Cancel parked, parked_order_action_id: {}", parked.parked_order_action_id);
+            ApiResponse {
+                success: true,
+                data: Some(parked),
+                error: None,
+            }
+        }
+        Err(error) => {
+            println!("❌ [ERROR] Failed to park cancel: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 查询预埋报单和预埋撤单，含已发送和已删除的记录
+#[command]
+pub async fn query_parked_orders(session_id: String) -> ApiResponse<ParkedOrders> {
+    println!("🔍 [DEBUG] query_parked_orders called with session_id: {}", session_id);
+
+    let result = match logged_in_trader_config(&session_id) {
+        Ok(config) => {
+            let mut req = tauri_app_vue_lib::CThostFtdcQryParkedOrderField::default();
+            copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+            copy_str_to_ctp_array(&config.account, &mut req.InvestorID);
+            let orders = query_scheduler::query(
+                &session_id,
+                QueryPriority::Normal,
+                QUERY_TIMEOUT,
+                |api, request_id| api.req_qry_parked_order(&mut req, request_id),
+                |msg| match msg {
+                    trader_api::CThostFtdcTraderSpiOutput::OnRspQryParkedOrder(p) => {
+                        p.p_parked_order.as_ref().map(ParkedOrderInfo::from)
+                    }
+                    _ => None,
+                },
+            )
+            .await;
+
+            let mut req = tauri_app_vue_lib::CThostFtdcQryParkedOrderActionField::default();
+            copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+            copy_str_to_ctp_array(&config.account, &mut req.InvestorID);
+            let cancels = query_scheduler::query(
+                &session_id,
+                QueryPriority::Normal,
+                QUERY_TIMEOUT,
+                |api, request_id| api.req_qry_parked_order_action(&mut req, request_id),
+                |msg| match msg {
+                    trader_api::CThostFtdcTraderSpiOutput::OnRspQryParkedOrderAction(p) => {
+                        p.p_parked_order_action.as_ref().map(ParkedCancelInfo::from)
+                    }
+                    _ => None,
+                },
+            )
+            .await;
+
+            orders.and_then(|orders| cancels.map(|cancels| ParkedOrders { orders, cancels }))
+        }
+        Err(error) => Err(error),
+    };
 
-    match std::panic::catch_unwind(|| {
-        // 首先检查登录状态
-        let login_status = TRADER_LOGIN_STATUS.lock().unwrap();
-        if !login_status.get(&session_id).unwrap_or(&false) {
-            return Err("CTP 交易 API 未连接，请先登录".to_string());
+    match result {
+        Ok(parked) => {
+            println!(
+                "✅ [SUCCESS] Parked order query successful, {} orders, {} cancels",
+                parked.orders.len(),
+                parked.cancels.len()
+            );
+            ApiResponse {
+                success: true,
+                data: Some(parked),
+                error: None,
+            }
         }
-        drop(login_status); // 释放锁
+        Err(error) => {
+            println!("❌ [ERROR] Parked order query failed: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
 
-        let apis = TRADER_APIS.lock().unwrap();
-
-        if let Some(_api) = apis.get(&session_id) {
-            println!("✅ [DEBUG] Found Trader API for session: {}", session_id);
-
-            // 注意：这里应该调用真实的CTP查询持仓API
-            // 由于当前的实现是基于异步流的，这里返回模拟数据
-            let positions = vec![
-                PositionInfo {
-                    instrument_id: "rb2509".to_string(),
-                    broker_id: "9999".to_string(),
-                    investor_id: "test_account".to_string(),
-                    posi_direction: "2".to_string(), // 2=多头, 3=空头
-                    hedge_flag: "1".to_string(),
-                    position_date: "1".to_string(),
-                    yd_position: 0,
-                    position: 2,
-                    long_frozen: 0,
-                    short_frozen: 0,
-                    long_frozen_amount: 0.0,
-                    short_frozen_amount: 0.0,
-                    open_volume: 2,
-                    close_volume: 0,
-                    open_amount: 6140.0,
-                    close_amount: 0.0,
-                    position_cost: 6140.0,
-                    pre_margin: 0.0,
-                    use_margin: 1228.0,
-                    frozen_margin: 0.0,
-                    frozen_cash: 0.0,
-                    frozen_commission: 0.0,
-                    cash_in: 0.0,
-                    commission: 12.0,
-                    close_profit: 0.0,
-                    position_profit: -40.0,
-                    pre_settlement_price: 3070.0,
-                    settlement_price: 3070.0,
-                    trading_day: "20241221".to_string(),
-                    settlement_id: 1,
-                    open_cost: 6140.0,
-                    exchange_margin: 1228.0,
-                    comb_position: 0,
-                    comb_long_frozen: 0,
-                    comb_short_frozen: 0,
-                    close_profit_by_date: 0.0,
-                    close_profit_by_trade: 0.0,
-                    today_position: 2,
-                    margin_rate_by_money: 0.2,
-                    margin_rate_by_volume: 0.0,
-                    strike_frozen: 0,
-                    strike_frozen_amount: 0.0,
-                    abandon_frozen: 0,
-                    exchange_id: "SHFE".to_string(),
-                    yd_strike_frozen: 0,
-                    invest_unit_id: "".to_string(),
-                    position_cost_offset: 0.0,
-                    tas_position: 0,
-                    tas_position_cost: 0.0,
-                }
-            ];
+// 删除尚未发送的预埋报单，返回被删除的预埋报单编号
+#[command]
+pub async fn remove_parked_order(session_id: String, parked_order_id: String) -> ApiResponse<String> {
+    println!("🔍 [DEBUG] remove_parked_order called with session_id: {}, parked_order_id: {}", session_id, parked_order_id);
+
+    let result = match logged_in_trader_config(&session_id) {
+        Ok(config) => {
+            let mut req = tauri_app_vue_lib::CThostFtdcRemoveParkedOrderField::default();
+            copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+            copy_str_to_ctp_array(&config.account, &mut req.InvestorID);
+            copy_str_to_ctp_array(&parked_order_id, &mut req.ParkedOrderID);
+            parked_request(
+                &session_id,
+                |api, request_id| api.req_remove_parked_order(&mut req, request_id),
+                |msg| match msg {
+                    trader_api::CThostFtdcTraderSpiOutput::OnRspRemoveParkedOrder(p) => p
+                        .p_remove_parked_order
+                        .as_ref()
+                        .map(|r| tauri_app_vue_lib::gb18030_cstr_to_str_i8(&r.ParkedOrderID).trim().to_string()),
+                    _ => None,
+                },
+            )
+            .await
+        }
+        Err(error) => Err(error),
+    };
 
-            Ok(positions)
-        } else {
-            Err(format!("Session ID {} not found", session_id))
+    match result {
+        Ok(removed) => {
+            println!("✅ [SUCCESS] Parked order {} removed", removed);
+            ApiResponse {
+                success: true,
+                data: Some(removed),
+                error: None,
+            }
         }
-    }) {
-        Ok(Ok(positions)) => {
-            println!("✅ [SUCCESS] Position query successful, {} positions", positions.len());
+        Err(error) => {
+            println!("❌ [ERROR] Failed to remove parked order: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 删除尚未发送的预埋撤单，返回被删除的预埋撤单编号
+#[command]
+pub async fn remove_parked_cancel(session_id: String, parked_order_action_id: String) -> ApiResponse<String> {
+    println!(
+        "🔍 [DEBUG] remove_parked_cancel called with session_id: {}, parked_order_action_id: {}",
+        session_id, parked_order_action_id
+    );
+
+    let result = match logged_in_trader_config(&session_id) {
+        Ok(config) => {
+            let mut req = tauri_app_vue_lib::CThostFtdcRemoveParkedOrderActionField::default();
+            copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+            copy_str_to_ctp_array(&config.account, &mut req.InvestorID);
+            copy_str_to_ctp_array(&parked_order_action_id, &mut req.ParkedOrderActionID);
+            parked_request(
+                &session_id,
+                |api, request_id| api.req_remove_parked_order_action(&mut req, request_id),
+                |msg| match msg {
+                    trader_api::CThostFtdcTraderSpiOutput::OnRspRemoveParkedOrderAction(p) => p
+                        .p_remove_parked_order_action
+                        .as_ref()
+                        .map(|r| tauri_app_vue_lib::gb18030_cstr_to_str_i8(&r.ParkedOrderActionID).trim().to_string()),
+                    _ => None,
+                },
+            )
+            .await
+        }
+        Err(error) => Err(error),
+    };
+
+    match result {
+        Ok(removed) => {
+            println!("✅ [SUCCESS] Parked cancel {} removed", removed);
             ApiResponse {
                 success: true,
-                data: Some(positions),
+                data: Some(removed),
                 error: None,
             }
-        },
-        Ok(Err(error)) => {
-            println!("❌ [ERROR] Position query failed: {}", error);
+        }
+        Err(error) => {
+            println!("❌ [ERROR] Failed to remove parked cancel: {}", error);
             ApiResponse {
                 success: false,
                 data: None,
                 error: Some(error),
             }
-        },
-        Err(_) => {
-            let error = "Position query panicked".to_string();
-            println!("❌ [ERROR] {}", error);
+        }
+    }
+}
+
+// 提交追价单：按最新行情挂出首笔子单，之后由行情驱动撤单重报
+#[command]
+pub fn start_price_chase(session_id: String, request: ChaseRequest) -> ApiResponse<PriceChase> {
+    println!("🔍 [DEBUG] start_price_chase called with session_id: {}, request: {:?}", session_id, request);
+
+    let started = logged_in_trader_config(&session_id)
+        .and_then(|_| price_chaser::start(&session_id, request));
+    match started {
+        Ok(chase) => {
+            println!("✅ [SUCCESS] Price chase {} started at {}", chase.chase_id, chase.initial_price);
+            ApiResponse {
+                success: true,
+                data: Some(chase),
+                error: None,
+            }
+        }
+        Err(error) => {
+            println!("❌ [ERROR] Failed to start price chase: {}", error);
             ApiResponse {
                 success: false,
                 data: None,
@@ -1126,183 +2036,244 @@ pub fn query_position(
     }
 }
 
-// 获取常见合约数据
-fn get_common_instruments() -> Vec<InstrumentInfo> {
-    vec![
-        // 螺纹钢合约
-        InstrumentInfo {
-            instrument_id: "rb2501".to_string(),
-            exchange_id: "SHFE".to_string(),
-            instrument_name: "螺纹钢2501".to_string(),
-            exchange_inst_id: "rb2501".to_string(),
-            product_id: "rb".to_string(),
-            product_class: "1".to_string(),
-            delivery_year: 2025,
-            delivery_month: 1,
-            max_market_order_volume: 100,
-            min_market_order_volume: 1,
-            max_limit_order_volume: 500,
-            min_limit_order_volume: 1,
-            volume_multiple: 10,
-            price_tick: 1.0,
-            create_date: "20240101".to_string(),
-            open_date: "20240301".to_string(),
-            expire_date: "20250115".to_string(),
-            start_deliv_date: "20250116".to_string(),
-            end_deliv_date: "20250120".to_string(),
-            inst_life_phase: "1".to_string(),
-            is_trading: 1,
-            position_type: "1".to_string(),
-            position_date_type: "1".to_string(),
-            long_margin_ratio: 0.08,
-            short_margin_ratio: 0.08,
-            max_margin_side_algorithm: "1".to_string(),
-            underlying_inst_id: "".to_string(),
-            strike_price: 0.0,
-            options_type: "0".to_string(),
-            underlying_multiple: 0.0,
-            combination_type: "0".to_string(),
+#[command]
+pub async fn cancel_price_chase(session_id: String, chase_id: String) -> ApiResponse<PriceChase> {
+    println!("🔍 [DEBUG] cancel_price_chase called with session_id: {}, chase_id: {}", session_id, chase_id);
+
+    match price_chaser::cancel(&session_id, &chase_id).await {
+        Ok(chase) => ApiResponse {
+            success: true,
+            data: Some(chase),
+            error: None,
         },
-        InstrumentInfo {
-            instrument_id: "rb2505".to_string(),
-            exchange_id: "SHFE".to_string(),
-            instrument_name: "螺纹钢2505".to_string(),
-            exchange_inst_id: "rb2505".to_string(),
-            product_id: "rb".to_string(),
-            product_class: "1".to_string(),
-            delivery_year: 2025,
-            delivery_month: 5,
-            max_market_order_volume: 100,
-            min_market_order_volume: 1,
-            max_limit_order_volume: 500,
-            min_limit_order_volume: 1,
-            volume_multiple: 10,
-            price_tick: 1.0,
-            create_date: "20240101".to_string(),
-            open_date: "20240301".to_string(),
-            expire_date: "20250515".to_string(),
-            start_deliv_date: "20250516".to_string(),
-            end_deliv_date: "20250520".to_string(),
-            inst_life_phase: "1".to_string(),
-            is_trading: 1,
-            position_type: "1".to_string(),
-            position_date_type: "1".to_string(),
-            long_margin_ratio: 0.08,
-            short_margin_ratio: 0.08,
-            max_margin_side_algorithm: "1".to_string(),
-            underlying_inst_id: "".to_string(),
-            strike_price: 0.0,
-            options_type: "0".to_string(),
-            underlying_multiple: 0.0,
-            combination_type: "0".to_string(),
+        Err(error) => {
+            println!("❌ [ERROR] Failed to cancel price chase: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 本会话的追价单及子单最新状态
+#[command]
+pub fn list_price_chases(session_id: String) -> ApiResponse<Vec<PriceChase>> {
+    ApiResponse {
+        success: true,
+        data: Some(price_chaser::list(&session_id)),
+        error: None,
+    }
+}
+
+// 提交算法单（TWAP / 冰山），立即报出首笔子单
+#[command]
+pub fn start_algo_order(
+    app: tauri::AppHandle,
+    session_id: String,
+    request: AlgoRequest,
+) -> ApiResponse<AlgoOrder> {
+    println!("🔍 [DEBUG] start_algo_order called with session_id: {}, request: {:?}", session_id, request);
+
+    let started = logged_in_trader_config(&session_id)
+        .and_then(|_| algo_orders::start(&app, &session_id, request));
+    match started {
+        Ok(algo) => {
+            println!("✅ [SUCCESS] Algo order {} started", algo.algo_id);
+            ApiResponse {
+                success: true,
+                data: Some(algo),
+                error: None,
+            }
+        }
+        Err(error) => {
+            println!("❌ [ERROR] Failed to start algo order: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 暂停算法单：撤掉挂单，不再报出新子单
+#[command]
+pub async fn pause_algo_order(session_id: String, algo_id: String) -> ApiResponse<AlgoOrder> {
+    println!("🔍 [DEBUG] pause_algo_order called with session_id: {}, algo_id: {}", session_id, algo_id);
+
+    match algo_orders::pause(&session_id, &algo_id).await {
+        Ok(algo) => ApiResponse {
+            success: true,
+            data: Some(algo),
+            error: None,
         },
-        // 热卷合约
-        InstrumentInfo {
-            instrument_id: "hc2501".to_string(),
-            exchange_id: "SHFE".to_string(),
-            instrument_name: "热卷2501".to_string(),
-            exchange_inst_id: "hc2501".to_string(),
-            product_id: "hc".to_string(),
-            product_class: "1".to_string(),
-            delivery_year: 2025,
-            delivery_month: 1,
-            max_market_order_volume: 100,
-            min_market_order_volume: 1,
-            max_limit_order_volume: 500,
-            min_limit_order_volume: 1,
-            volume_multiple: 10,
-            price_tick: 1.0,
-            create_date: "20240101".to_string(),
-            open_date: "20240301".to_string(),
-            expire_date: "20250115".to_string(),
-            start_deliv_date: "20250116".to_string(),
-            end_deliv_date: "20250120".to_string(),
-            inst_life_phase: "1".to_string(),
-            is_trading: 1,
-            position_type: "1".to_string(),
-            position_date_type: "1".to_string(),
-            long_margin_ratio: 0.08,
-            short_margin_ratio: 0.08,
-            max_margin_side_algorithm: "1".to_string(),
-            underlying_inst_id: "".to_string(),
-            strike_price: 0.0,
-            options_type: "0".to_string(),
-            underlying_multiple: 0.0,
-            combination_type: "0".to_string(),
+        Err(error) => {
+            println!("❌ [ERROR] Failed to pause algo order: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+#[command]
+pub fn resume_algo_order(session_id: String, algo_id: String) -> ApiResponse<AlgoOrder> {
+    println!("🔍 [DEBUG] resume_algo_order called with session_id: {}, algo_id: {}", session_id, algo_id);
+
+    match algo_orders::resume(&session_id, &algo_id) {
+        Ok(algo) => ApiResponse {
+            success: true,
+            data: Some(algo),
+            error: None,
         },
-        // 苹果合约
-        InstrumentInfo {
-            instrument_id: "AP501".to_string(),
-            exchange_id: "CZCE".to_string(),
-            instrument_name: "苹果501".to_string(),
-            exchange_inst_id: "AP501".to_string(),
-            product_id: "AP".to_string(),
-            product_class: "1".to_string(),
-            delivery_year: 2025,
-            delivery_month: 1,
-            max_market_order_volume: 50,
-            min_market_order_volume: 1,
-            max_limit_order_volume: 200,
-            min_limit_order_volume: 1,
-            volume_multiple: 10,
-            price_tick: 1.0,
-            create_date: "20240101".to_string(),
-            open_date: "20240301".to_string(),
-            expire_date: "20250115".to_string(),
-            start_deliv_date: "20250116".to_string(),
-            end_deliv_date: "20250120".to_string(),
-            inst_life_phase: "1".to_string(),
-            is_trading: 1,
-            position_type: "1".to_string(),
-            position_date_type: "1".to_string(),
-            long_margin_ratio: 0.10,
-            short_margin_ratio: 0.10,
-            max_margin_side_algorithm: "1".to_string(),
-            underlying_inst_id: "".to_string(),
-            strike_price: 0.0,
-            options_type: "0".to_string(),
-            underlying_multiple: 0.0,
-            combination_type: "0".to_string(),
+        Err(error) => {
+            println!("❌ [ERROR] Failed to resume algo order: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 撤销算法单：撤掉全部挂单，已成交部分保留
+#[command]
+pub async fn cancel_algo_order(session_id: String, algo_id: String) -> ApiResponse<AlgoOrder> {
+    println!("🔍 [DEBUG] cancel_algo_order called with session_id: {}, algo_id: {}", session_id, algo_id);
+
+    match algo_orders::cancel(&session_id, &algo_id).await {
+        Ok(algo) => ApiResponse {
+            success: true,
+            data: Some(algo),
+            error: None,
         },
-        // 豆粕合约
-        InstrumentInfo {
-            instrument_id: "m2501".to_string(),
-            exchange_id: "DCE".to_string(),
-            instrument_name: "豆粕2501".to_string(),
-            exchange_inst_id: "m2501".to_string(),
-            product_id: "m".to_string(),
-            product_class: "1".to_string(),
-            delivery_year: 2025,
-            delivery_month: 1,
-            max_market_order_volume: 100,
-            min_market_order_volume: 1,
-            max_limit_order_volume: 500,
-            min_limit_order_volume: 1,
-            volume_multiple: 10,
-            price_tick: 1.0,
-            create_date: "20240101".to_string(),
-            open_date: "20240301".to_string(),
-            expire_date: "20250115".to_string(),
-            start_deliv_date: "20250116".to_string(),
-            end_deliv_date: "20250120".to_string(),
-            inst_life_phase: "1".to_string(),
-            is_trading: 1,
-            position_type: "1".to_string(),
-            position_date_type: "1".to_string(),
-            long_margin_ratio: 0.08,
-            short_margin_ratio: 0.08,
-            max_margin_side_algorithm: "1".to_string(),
-            underlying_inst_id: "".to_string(),
-            strike_price: 0.0,
-            options_type: "0".to_string(),
-            underlying_multiple: 0.0,
-            combination_type: "0".to_string(),
+        Err(error) => {
+            println!("❌ [ERROR] Failed to cancel algo order: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+// 本会话的算法单及子单最新状态
+#[command]
+pub fn list_algo_orders(session_id: String) -> ApiResponse<Vec<AlgoOrder>> {
+    ApiResponse {
+        success: true,
+        data: Some(algo_orders::list(&session_id)),
+        error: None,
+    }
+}
+
+// 本会话当日各合约的撤单次数
+#[command]
+pub fn get_cancel_counts(session_id: String) -> ApiResponse<Vec<CancelCount>> {
+    ApiResponse {
+        success: true,
+        data: Some(cancel_quota::counts(&session_id)),
+        error: None,
+    }
+}
+
+// 设置撤单次数上限和预警值，返回按新限制计算的撤单次数
+#[command]
+pub fn set_cancel_limits(session_id: String, limits: CancelLimits) -> ApiResponse<Vec<CancelCount>> {
+    println!("🔍 [DEBUG] set_cancel_limits called with session_id: {}, limits: {:?}", session_id, limits);
+    cancel_quota::set_limits(&session_id, limits);
+    ApiResponse {
+        success: true,
+        data: Some(cancel_quota::counts(&session_id)),
+        error: None,
+    }
+}
+
+// 本会话的报单前风控参数，未设置时为默认参数
+#[command]
+pub fn get_risk_config(session_id: String) -> ApiResponse<RiskConfig> {
+    ApiResponse {
+        success: true,
+        data: Some(risk::config(&session_id)),
+        error: None,
+    }
+}
+
+#[command]
+pub fn set_risk_config(session_id: String, config: RiskConfig) -> ApiResponse<RiskConfig> {
+    println!("🔍 [DEBUG] set_risk_config called with session_id: {}, config: {:?}", session_id, config);
+    risk::set_config(&session_id, config.clone());
+    ApiResponse {
+        success: true,
+        data: Some(config),
+        error: None,
+    }
+}
+
+// 查询持仓信息，返回 CTP 的原始持仓记录
+#[command]
+pub async fn query_position(
+    session_id: String,
+) -> ApiResponse<Vec<PositionInfo>> {
+    println!("🔍 [DEBUG] query_position called with session_id: {}", session_id);
+
+    match fetch_positions(&session_id).await {
+        Ok(positions) => {
+            println!("✅ [SUCCESS] Position query successful, {} positions", positions.len());
+            ApiResponse {
+                success: true,
+                data: Some(positions),
+                error: None,
+            }
         },
-    ]
+        Err(error) => {
+            println!("❌ [ERROR] Position query failed: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
 }
 
-// 全局查询请求计数器，用于生成唯一的请求ID
-static mut QUERY_REQUEST_ID: i32 = 1;
+// 查询持仓，同时返回原始记录和按合约、方向合并的今仓/昨仓/可平数量
+#[command]
+pub async fn query_position_summary(
+    session_id: String,
+) -> ApiResponse<PositionSummary> {
+    println!("🔍 [DEBUG] query_position_summary called with session_id: {}", session_id);
+
+    match fetch_positions(&session_id).await {
+        Ok(positions) => {
+            let merged = position::merge_positions(&positions);
+            println!(
+                "✅ [SUCCESS] Position summary successful, {} rows, {} merged",
+                positions.len(),
+                merged.len()
+            );
+            ApiResponse {
+                success: true,
+                data: Some(PositionSummary { positions, merged }),
+                error: None,
+            }
+        },
+        Err(error) => {
+            println!("❌ [ERROR] Position summary failed: {}", error);
+            ApiResponse {
+                success: false,
+                data: None,
+                error: Some(error),
+            }
+        }
+    }
+}
 
 // 测试下单接口
 #[command]
@@ -1312,14 +2283,16 @@ pub fn test_order_interface() -> ApiResponse<String> {
     // 创建测试订单
     let test_order = OrderRequest {
         instrument_id: "rb2501".to_string(),
-        direction: "0".to_string(),  // 买入
+        direction: Direction::Buy,
         price: 3500.0,
         volume: 1,
-        order_type: "2".to_string(), // 限价单
-        offset_flag: Some("0".to_string()), // 开仓
-        hedge_flag: Some("1".to_string()),  // 投机
-        time_condition: Some("3".to_string()), // 当日有效
-        volume_condition: Some("1".to_string()), // 任何数量
+        order_type: OrderPriceType::LimitPrice,
+        offset_flag: Some(OffsetFlag::Open),
+        hedge_flag: Some(HedgeFlag::Speculation),
+        time_condition: Some(TimeCondition::Gfd),
+        volume_condition: Some(VolumeCondition::AnyVolume),
+        contingent_condition: None,
+        stop_price: None,
     };
 
     println!("✅ [TEST] Order interface structure is valid");
@@ -1332,70 +2305,96 @@ pub fn test_order_interface() -> ApiResponse<String> {
     }
 }
 
-// 查询合约信息
+// 当日全量合约：内存 → 磁盘缓存 → CTP 查询，refresh 为 true 时强制重新查询
+async fn load_instruments(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    refresh: bool,
+) -> Result<Vec<InstrumentInfo>, String> {
+    logged_in_trader_config(session_id)?;
+    let trading_day = TRADER_SESSION_INFO
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .map(|info| info.trading_day.clone())
+        .ok_or_else(|| format!("未找到会话 {} 的交易日", session_id))?;
+    let cache_dir = app
+        .path()
+        .app_cache_dir()
+        .ok()
+        .map(|dir| dir.join("instruments"));
+
+    if !refresh {
+        if let Some(instruments) = instruments::cached(&trading_day) {
+            return Ok(instruments);
+        }
+        if let Some(catalogue) = cache_dir
+            .as_deref()
+            .and_then(|dir| instruments::load_cache(dir, &trading_day))
+        {
+            println!(
+                "✅ [DEBUG] Loaded {} instruments from disk cache, trading_day: {}",
+                catalogue.instruments.len(),
+                trading_day
+            );
+            let list = catalogue.instruments.clone();
+            instruments::store(catalogue);
+            return Ok(list);
+        }
+    }
+
+    let mut req = tauri_app_vue_lib::CThostFtdcQryInstrumentField::default();
+    let list = query_scheduler::query(
+        session_id,
+        QueryPriority::Low,
+        instruments::INSTRUMENT_QUERY_TIMEOUT,
+        |api, request_id| api.req_qry_instrument(&mut req, request_id),
+        |msg| match msg {
+            trader_api::CThostFtdcTraderSpiOutput::OnRspQryInstrument(p) => {
+                p.p_instrument.as_ref().map(InstrumentInfo::from)
+            }
+            _ => None,
+        },
+    )
+    .await?;
+    println!(
+        "✅ [DEBUG] Fetched {} instruments from CTP, trading_day: {}",
+        list.len(),
+        trading_day
+    );
+
+    let catalogue = InstrumentCatalogue {
+        trading_day,
+        instruments: list.clone(),
+    };
+    if let Some(dir) = cache_dir.as_deref() {
+        if let Err(error) = instruments::save_cache(dir, &catalogue) {
+            println!("⚠️ [WARN] Failed to write instrument cache: {}", error);
+        }
+    }
+    instruments::store(catalogue);
+    Ok(list)
+}
+
+// 查询合约信息，可按交易所、品种、产品类型、到期日、标的筛选
 #[command]
-pub fn query_instruments(
+pub async fn query_instruments(
+    app: tauri::AppHandle,
     session_id: String,
+    filter: Option<InstrumentFilter>,
+    refresh: Option<bool>,
 ) -> ApiResponse<Vec<InstrumentInfo>> {
     println!("🔍 [DEBUG] query_instruments called with session_id: {}", session_id);
 
-    match std::panic::catch_unwind(|| {
-        // 首先检查登录状态
-        let login_status = TRADER_LOGIN_STATUS.lock().unwrap();
-        if !login_status.get(&session_id).unwrap_or(&false) {
-            return Err("CTP 交易 API 未连接，请先登录".to_string());
-        }
-        drop(login_status); // 释放锁
-
-        let apis = TRADER_APIS.lock().unwrap();
-
-        if let Some(api) = apis.get(&session_id) {
-            println!("✅ [DEBUG] Found Trader API for session: {}", session_id);
-
-            // 由于CTP查询接口的限制和异步特性，这里直接返回模拟数据
-            // 在实际生产环境中，应该：
-            // 1. 实现回调处理机制
-            // 2. 添加查询频率控制
-            // 3. 处理异步响应
-
-            println!("⚠️ [INFO] Using simulated instrument data due to CTP API limitations");
-            let instruments = get_common_instruments();
-            Ok(instruments)
-
-            // 注释掉的真实CTP查询代码，因为需要复杂的回调处理
-            /*
-            use tauri_app_vue_lib::*;
-
-            // 创建查询合约请求结构
-            let mut req = CThostFtdcQryInstrumentField::default();
-
-            // 获取唯一的请求ID
-            let request_id = get_next_request_id();
-
-            // 调用查询合约接口
-            let result = unsafe {
-                api.req_qry_instrument(&mut req, request_id)
-            };
+    let result = load_instruments(&app, &session_id, refresh.unwrap_or(false))
+        .await
+        .map(|all| match filter {
+            Some(filter) => filter.apply(&all),
+            None => all,
+        });
 
-            if result == 0 {
-                println!("✅ [DEBUG] req_qry_instrument called successfully with request_id: {}", request_id);
-                // 由于CTP是异步回调模式，这里返回一些基础合约数据
-                // 实际项目中应该通过回调收集数据
-                let instruments = get_common_instruments();
-                Ok(instruments)
-            } else {
-                println!("❌ [ERROR] req_qry_instrument failed with code: {}", result);
-                // 如果查询失败，返回模拟数据作为备选
-                println!("⚠️ [FALLBACK] Using simulated data due to query failure");
-                let instruments = get_common_instruments();
-                Ok(instruments)
-            }
-            */
-        } else {
-            Err(format!("Session ID {} not found", session_id))
-        }
-    }) {
-        Ok(Ok(instruments)) => {
+    match result {
+        Ok(instruments) => {
             println!("✅ [SUCCESS] Instruments query successful, found {} instruments", instruments.len());
             ApiResponse {
                 success: true,
@@ -1403,22 +2402,13 @@ pub fn query_instruments(
                 error: None,
             }
         },
-        Ok(Err(error)) => {
+        Err(error) => {
             println!("❌ [ERROR] Instruments query failed: {}", error);
             ApiResponse {
                 success: false,
                 data: None,
                 error: Some(error),
             }
-        },
-        Err(_) => {
-            let error = "Instruments query panicked".to_string();
-            println!("❌ [ERROR] {}", error);
-            ApiResponse {
-                success: false,
-                data: None,
-                error: Some(error),
-            }
         }
     }
 }
@@ -0,0 +1,240 @@
+// CTP 报单字段中的单字符标志：前端和 CTP 都使用同一个字符（如开仓为 "0"），
+// 这里用枚举表示，序列化为该字符，反序列化时拒绝未知取值
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use tauri_app_vue_lib::*;
+
+macro_rules! ctp_flag {
+    (
+        $(#[$meta:meta])*
+        $name:ident, $label:literal {
+            $($(#[$variant_meta:meta])* $variant:ident = $flag:ident,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)+
+        }
+
+        impl $name {
+            #[cfg(test)]
+            pub const ALL: &'static [$name] = &[$($name::$variant,)+];
+
+            /// 对应的 CTP 字符
+            pub fn to_ctp(self) -> i8 {
+                match self {
+                    $($name::$variant => $flag as i8,)+
+                }
+            }
+
+            pub fn from_ctp(flag: i8) -> Option<Self> {
+                $(if flag == $flag as i8 {
+                    return Some($name::$variant);
+                })+
+                None
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.to_ctp() as u8 as char)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii() => $name::from_ctp(c as u8 as i8),
+                    _ => None,
+                }
+                .ok_or_else(|| format!("无效的{}: {:?}", $label, s))
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+ctp_flag! {
+    /// 买卖方向
+    Direction, "买卖方向" {
+        Buy = THOST_FTDC_D_Buy,
+        Sell = THOST_FTDC_D_Sell,
+    }
+}
+
+ctp_flag! {
+    /// 开平标志
+    OffsetFlag, "开平标志" {
+        Open = THOST_FTDC_OF_Open,
+        Close = THOST_FTDC_OF_Close,
+        ForceClose = THOST_FTDC_OF_ForceClose,
+        /// 平今，仅上期所/能源中心区分今昨仓
+        CloseToday = THOST_FTDC_OF_CloseToday,
+        CloseYesterday = THOST_FTDC_OF_CloseYesterday,
+        ForceOff = THOST_FTDC_OF_ForceOff,
+        LocalForceClose = THOST_FTDC_OF_LocalForceClose,
+    }
+}
+
+ctp_flag! {
+    /// 投机套保标志
+    HedgeFlag, "投机套保标志" {
+        Speculation = THOST_FTDC_HF_Speculation,
+        Arbitrage = THOST_FTDC_HF_Arbitrage,
+        Hedge = THOST_FTDC_HF_Hedge,
+        MarketMaker = THOST_FTDC_HF_MarketMaker,
+        SpecHedge = THOST_FTDC_HF_SpecHedge,
+        HedgeSpec = THOST_FTDC_HF_HedgeSpec,
+    }
+}
+
+ctp_flag! {
+    /// 有效期类型
+    TimeCondition, "有效期类型" {
+        /// 立即完成，否则撤销（FAK/FOK）
+        Ioc = THOST_FTDC_TC_IOC,
+        Gfs = THOST_FTDC_TC_GFS,
+        Gfd = THOST_FTDC_TC_GFD,
+        Gtd = THOST_FTDC_TC_GTD,
+        Gtc = THOST_FTDC_TC_GTC,
+        Gfa = THOST_FTDC_TC_GFA,
+    }
+}
+
+ctp_flag! {
+    /// 成交量类型
+    VolumeCondition, "成交量类型" {
+        AnyVolume = THOST_FTDC_VC_AV,
+        MinVolume = THOST_FTDC_VC_MV,
+        /// 全部数量，与 IOC 组合即 FOK
+        CompleteVolume = THOST_FTDC_VC_CV,
+    }
+}
+
+ctp_flag! {
+    /// 报单价格条件
+    OrderPriceType, "报单价格条件" {
+        /// 任意价（市价）
+        AnyPrice = THOST_FTDC_OPT_AnyPrice,
+        LimitPrice = THOST_FTDC_OPT_LimitPrice,
+        BestPrice = THOST_FTDC_OPT_BestPrice,
+        LastPrice = THOST_FTDC_OPT_LastPrice,
+        LastPricePlusOneTicks = THOST_FTDC_OPT_LastPricePlusOneTicks,
+        LastPricePlusTwoTicks = THOST_FTDC_OPT_LastPricePlusTwoTicks,
+        LastPricePlusThreeTicks = THOST_FTDC_OPT_LastPricePlusThreeTicks,
+        AskPrice1 = THOST_FTDC_OPT_AskPrice1,
+        AskPrice1PlusOneTicks = THOST_FTDC_OPT_AskPrice1PlusOneTicks,
+        AskPrice1PlusTwoTicks = THOST_FTDC_OPT_AskPrice1PlusTwoTicks,
+        AskPrice1PlusThreeTicks = THOST_FTDC_OPT_AskPrice1PlusThreeTicks,
+        BidPrice1 = THOST_FTDC_OPT_BidPrice1,
+        BidPrice1PlusOneTicks = THOST_FTDC_OPT_BidPrice1PlusOneTicks,
+        BidPrice1PlusTwoTicks = THOST_FTDC_OPT_BidPrice1PlusTwoTicks,
+        BidPrice1PlusThreeTicks = THOST_FTDC_OPT_BidPrice1PlusThreeTicks,
+        FiveLevelPrice = THOST_FTDC_OPT_FiveLevelPrice,
+    }
+}
+
+ctp_flag! {
+    /// 触发条件，除 Immediately 外均为柜台条件单
+    ContingentCondition, "触发条件" {
+        Immediately = THOST_FTDC_CC_Immediately,
+        Touch = THOST_FTDC_CC_Touch,
+        TouchProfit = THOST_FTDC_CC_TouchProfit,
+        ParkedOrder = THOST_FTDC_CC_ParkedOrder,
+        LastPriceGreaterThanStopPrice = THOST_FTDC_CC_LastPriceGreaterThanStopPrice,
+        LastPriceGreaterEqualStopPrice = THOST_FTDC_CC_LastPriceGreaterEqualStopPrice,
+        LastPriceLesserThanStopPrice = THOST_FTDC_CC_LastPriceLesserThanStopPrice,
+        LastPriceLesserEqualStopPrice = THOST_FTDC_CC_LastPriceLesserEqualStopPrice,
+        AskPriceGreaterThanStopPrice = THOST_FTDC_CC_AskPriceGreaterThanStopPrice,
+        AskPriceGreaterEqualStopPrice = THOST_FTDC_CC_AskPriceGreaterEqualStopPrice,
+        AskPriceLesserThanStopPrice = THOST_FTDC_CC_AskPriceLesserThanStopPrice,
+        AskPriceLesserEqualStopPrice = THOST_FTDC_CC_AskPriceLesserEqualStopPrice,
+        BidPriceGreaterThanStopPrice = THOST_FTDC_CC_BidPriceGreaterThanStopPrice,
+        BidPriceGreaterEqualStopPrice = THOST_FTDC_CC_BidPriceGreaterEqualStopPrice,
+        BidPriceLesserThanStopPrice = THOST_FTDC_CC_BidPriceLesserThanStopPrice,
+        BidPriceLesserEqualStopPrice = THOST_FTDC_CC_BidPriceLesserEqualStopPrice,
+    }
+}
+
+impl ContingentCondition {
+    /// 是否为需要触发价的柜台条件单（预埋单不算）
+    pub fn needs_stop_price(self) -> bool {
+        !matches!(self, ContingentCondition::Immediately | ContingentCondition::ParkedOrder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个取值经 CTP 字符和 JSON 往返后不变
+    fn assert_round_trip<T>(all: &[T], to_ctp: fn(T) -> i8, from_ctp: fn(i8) -> Option<T>)
+    where
+        T: Copy + PartialEq + fmt::Debug + Serialize + for<'de> Deserialize<'de>,
+    {
+        for &flag in all {
+            assert_eq!(from_ctp(to_ctp(flag)), Some(flag));
+            let json = serde_json::to_string(&flag).unwrap();
+            assert_eq!(json, format!("\"{}\"", to_ctp(flag) as u8 as char));
+            assert_eq!(serde_json::from_str::<T>(&json).unwrap(), flag);
+        }
+    }
+
+    #[test]
+    fn every_flag_round_trips() {
+        assert_round_trip(Direction::ALL, Direction::to_ctp, Direction::from_ctp);
+        assert_round_trip(OffsetFlag::ALL, OffsetFlag::to_ctp, OffsetFlag::from_ctp);
+        assert_round_trip(HedgeFlag::ALL, HedgeFlag::to_ctp, HedgeFlag::from_ctp);
+        assert_round_trip(TimeCondition::ALL, TimeCondition::to_ctp, TimeCondition::from_ctp);
+        assert_round_trip(VolumeCondition::ALL, VolumeCondition::to_ctp, VolumeCondition::from_ctp);
+        assert_round_trip(OrderPriceType::ALL, OrderPriceType::to_ctp, OrderPriceType::from_ctp);
+        assert_round_trip(
+            ContingentCondition::ALL,
+            ContingentCondition::to_ctp,
+            ContingentCondition::from_ctp,
+        );
+    }
+
+    #[test]
+    fn characters_match_thost_constants() {
+        assert_eq!(serde_json::from_str::<OffsetFlag>("\"0\"").unwrap(), OffsetFlag::Open);
+        assert_eq!(OffsetFlag::Open.to_ctp(), THOST_FTDC_OF_Open as i8);
+        assert_eq!(OffsetFlag::CloseToday.to_ctp(), b'3' as i8);
+        assert_eq!("0".parse::<Direction>().unwrap().to_ctp(), THOST_FTDC_D_Buy as i8);
+        assert_eq!("1".parse::<HedgeFlag>().unwrap(), HedgeFlag::Speculation);
+        assert_eq!("3".parse::<TimeCondition>().unwrap(), TimeCondition::Gfd);
+        // "1" 是任意价（市价），限价是 "2"
+        assert_eq!("1".parse::<OrderPriceType>().unwrap(), OrderPriceType::AnyPrice);
+        assert_eq!("2".parse::<OrderPriceType>().unwrap().to_ctp(), THOST_FTDC_OPT_LimitPrice as i8);
+        assert_eq!("C".parse::<ContingentCondition>().unwrap(), ContingentCondition::AskPriceLesserEqualStopPrice);
+    }
+
+    #[test]
+    fn unknown_values_are_rejected() {
+        assert!("2".parse::<Direction>().is_err());
+        assert!("".parse::<OffsetFlag>().is_err());
+        assert!("01".parse::<OffsetFlag>().is_err());
+        assert!("开".parse::<OffsetFlag>().is_err());
+        let error = serde_json::from_str::<VolumeCondition>("\"9\"").unwrap_err();
+        assert!(error.to_string().contains("成交量类型"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::CThostFtdcRspInfoField;
 use encoding::all::GB18030;
 use encoding::{DecoderTrap, Encoding};
 use simple_error::SimpleError;
@@ -107,6 +108,27 @@ pub fn gb18030_cstr_to_str(v: &[u8]) -> Cow<str> {
     }
 }
 
+/// CTP 的单字符枚举（如 Direction、OrderStatus）转为字符串，'\0' 转为空串
+pub fn char_flag_to_string(c: i8) -> String {
+    if c == 0 {
+        String::new()
+    } else {
+        (c as u8 as char).to_string()
+    }
+}
+
+/// 把 CTP 响应信息转换为错误描述，ErrorID 为 0 或没有响应信息时返回 None
+pub fn rsp_info_error(p: Option<&CThostFtdcRspInfoField>) -> Option<String> {
+    match p {
+        Some(info) if info.ErrorID != 0 => Some(format!(
+            "[{}] {}",
+            info.ErrorID,
+            gb18030_cstr_to_str_i8(&info.ErrorMsg)
+        )),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,315 @@
+// 合约目录：CThostFtdcInstrumentField 到前端 InstrumentInfo 的转换、
+// 按交易日缓存到磁盘的全量合约列表，以及 ContractSearch 窗口使用的筛选条件
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use tauri_app_vue_lib::{char_flag_to_string, gb18030_cstr_to_str_i8, CThostFtdcInstrumentField};
+
+/// 全量合约查询的等待时间：合约有上万条，回报包远多于普通查询
+pub const INSTRUMENT_QUERY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 合约信息，字段与 types/ctp.ts 中的 InstrumentInfo 对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstrumentInfo {
+    pub instrument_id: String,
+    pub exchange_id: String,
+    pub instrument_name: String,
+    pub exchange_inst_id: String,
+    pub product_id: String,
+    pub product_class: String,
+    pub delivery_year: i32,
+    pub delivery_month: i32,
+    pub max_market_order_volume: i32,
+    pub min_market_order_volume: i32,
+    pub max_limit_order_volume: i32,
+    pub min_limit_order_volume: i32,
+    pub volume_multiple: i32,
+    pub price_tick: f64,
+    pub create_date: String,
+    pub open_date: String,
+    pub expire_date: String,
+    pub start_deliv_date: String,
+    pub end_deliv_date: String,
+    pub inst_life_phase: String,
+    pub is_trading: i32,
+    pub position_type: String,
+    pub position_date_type: String,
+    pub long_margin_ratio: f64,
+    pub short_margin_ratio: f64,
+    pub max_margin_side_algorithm: String,
+    pub underlying_inst_id: String,
+    pub strike_price: f64,
+    pub options_type: String,
+    pub underlying_multiple: f64,
+    pub combination_type: String,
+}
+
+// CTP 用 DBL_MAX 表示无效的保证金率、行权价等
+fn valid_f64(v: f64) -> f64 {
+    if v.is_finite() && v != f64::MAX {
+        v
+    } else {
+        0.0
+    }
+}
+
+impl From<&CThostFtdcInstrumentField> for InstrumentInfo {
+    fn from(i: &CThostFtdcInstrumentField) -> Self {
+        InstrumentInfo {
+            instrument_id: gb18030_cstr_to_str_i8(&i.InstrumentID).to_string(),
+            exchange_id: gb18030_cstr_to_str_i8(&i.ExchangeID).to_string(),
+            instrument_name: gb18030_cstr_to_str_i8(&i.InstrumentName).to_string(),
+            exchange_inst_id: gb18030_cstr_to_str_i8(&i.ExchangeInstID).to_string(),
+            product_id: gb18030_cstr_to_str_i8(&i.ProductID).to_string(),
+            product_class: char_flag_to_string(i.ProductClass),
+            delivery_year: i.DeliveryYear,
+            delivery_month: i.DeliveryMonth,
+            max_market_order_volume: i.MaxMarketOrderVolume,
+            min_market_order_volume: i.MinMarketOrderVolume,
+            max_limit_order_volume: i.MaxLimitOrderVolume,
+            min_limit_order_volume: i.MinLimitOrderVolume,
+            volume_multiple: i.VolumeMultiple,
+            price_tick: i.PriceTick,
+            create_date: gb18030_cstr_to_str_i8(&i.CreateDate).to_string(),
+            open_date: gb18030_cstr_to_str_i8(&i.OpenDate).to_string(),
+            expire_date: gb18030_cstr_to_str_i8(&i.ExpireDate).to_string(),
+            start_deliv_date: gb18030_cstr_to_str_i8(&i.StartDelivDate).to_string(),
+            end_deliv_date: gb18030_cstr_to_str_i8(&i.EndDelivDate).to_string(),
+            inst_life_phase: char_flag_to_string(i.InstLifePhase),
+            is_trading: i.IsTrading,
+            position_type: char_flag_to_string(i.PositionType),
+            position_date_type: char_flag_to_string(i.PositionDateType),
+            long_margin_ratio: valid_f64(i.LongMarginRatio),
+            short_margin_ratio: valid_f64(i.ShortMarginRatio),
+            max_margin_side_algorithm: char_flag_to_string(i.MaxMarginSideAlgorithm),
+            underlying_inst_id: gb18030_cstr_to_str_i8(&i.UnderlyingInstrID).to_string(),
+            strike_price: valid_f64(i.StrikePrice),
+            options_type: char_flag_to_string(i.OptionsType),
+            underlying_multiple: valid_f64(i.UnderlyingMultiple),
+            combination_type: char_flag_to_string(i.CombinationType),
+        }
+    }
+}
+
+/// query_instruments 的筛选条件，未设置的条件不参与筛选
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstrumentFilter {
+    pub exchange_id: Option<String>,
+    pub product_id: Option<String>,
+    pub product_class: Option<String>,
+    /// 到期日下限（含），格式 YYYYMMDD
+    pub expire_from: Option<String>,
+    /// 到期日上限（含），格式 YYYYMMDD
+    pub expire_to: Option<String>,
+    pub underlying_inst_id: Option<String>,
+    /// 合约代码或名称包含的关键字，不区分大小写
+    pub keyword: Option<String>,
+}
+
+// 空字符串视为未设置
+fn condition(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|v| !v.is_empty())
+}
+
+impl InstrumentFilter {
+    pub fn matches(&self, instrument: &InstrumentInfo) -> bool {
+        if condition(&self.exchange_id).is_some_and(|v| instrument.exchange_id != v) {
+            return false;
+        }
+        // 品种代码大小写在各交易所不统一（rb / AP），按不区分大小写比较
+        if condition(&self.product_id)
+            .is_some_and(|v| !instrument.product_id.eq_ignore_ascii_case(v))
+        {
+            return false;
+        }
+        if condition(&self.product_class).is_some_and(|v| instrument.product_class != v) {
+            return false;
+        }
+        if condition(&self.expire_from).is_some_and(|v| instrument.expire_date.as_str() < v) {
+            return false;
+        }
+        if condition(&self.expire_to).is_some_and(|v| instrument.expire_date.as_str() > v) {
+            return false;
+        }
+        if condition(&self.underlying_inst_id).is_some_and(|v| instrument.underlying_inst_id != v)
+        {
+            return false;
+        }
+        if let Some(keyword) = condition(&self.keyword) {
+            let keyword = keyword.to_lowercase();
+            if !instrument.instrument_id.to_lowercase().contains(&keyword)
+                && !instrument.instrument_name.to_lowercase().contains(&keyword)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn apply(&self, instruments: &[InstrumentInfo]) -> Vec<InstrumentInfo> {
+        instruments
+            .iter()
+            .filter(|instrument| self.matches(instrument))
+            .cloned()
+            .collect()
+    }
+}
+
+/// 某个交易日的全量合约
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstrumentCatalogue {
+    pub trading_day: String,
+    pub instruments: Vec<InstrumentInfo>,
+}
+
+lazy_static::lazy_static! {
+    // 合约列表与账户无关，所有会话共用一份
+    static ref INSTRUMENT_CATALOGUE: Arc<Mutex<Option<InstrumentCatalogue>>> =
+        Arc::new(Mutex::new(None));
+}
+
+/// 内存中指定交易日的合约列表
+pub fn cached(trading_day: &str) -> Option<Vec<InstrumentInfo>> {
+    INSTRUMENT_CATALOGUE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|catalogue| catalogue.trading_day == trading_day)
+        .map(|catalogue| catalogue.instruments.clone())
+}
+
+/// 在内存中的合约列表里按代码查找，尚未加载合约时返回 None
+pub fn find(instrument_id: &str) -> Option<InstrumentInfo> {
+    INSTRUMENT_CATALOGUE
+        .lock()
+        .unwrap()
+        .as_ref()?
+        .instruments
+        .iter()
+        .find(|instrument| instrument.instrument_id == instrument_id)
+        .cloned()
+}
+
+pub fn store(catalogue: InstrumentCatalogue) {
+    *INSTRUMENT_CATALOGUE.lock().unwrap() = Some(catalogue);
+}
+
+fn cache_file(dir: &Path, trading_day: &str) -> PathBuf {
+    dir.join(format!("instruments_{}.json", trading_day))
+}
+
+/// 读取磁盘上指定交易日的缓存，不存在或无法解析时返回 None
+pub fn load_cache(dir: &Path, trading_day: &str) -> Option<InstrumentCatalogue> {
+    let content = fs::read_to_string(cache_file(dir, trading_day)).ok()?;
+    let catalogue: InstrumentCatalogue = serde_json::from_str(&content).ok()?;
+    (catalogue.trading_day == trading_day).then_some(catalogue)
+}
+
+/// 写入缓存，并删除其他交易日的旧缓存
+pub fn save_cache(dir: &Path, catalogue: &InstrumentCatalogue) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let content = serde_json::to_string(catalogue).map_err(io::Error::other)?;
+    let path = cache_file(dir, &catalogue.trading_day);
+    fs::write(&path, content)?;
+
+    for entry in fs::read_dir(dir)?.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with("instruments_") && name.ends_with(".json") && entry.path() != path {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instrument(id: &str, exchange_id: &str, product_id: &str, expire_date: &str) -> InstrumentInfo {
+        InstrumentInfo {
+            instrument_id: id.to_string(),
+            exchange_id: exchange_id.to_string(),
+            product_id: product_id.to_string(),
+            product_class: "1".to_string(),
+            expire_date: expire_date.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn sample() -> Vec<InstrumentInfo> {
+        let mut option = instrument("m2505-C-3000", "DCE", "m_o", "20250407");
+        option.product_class = "2".to_string();
+        option.underlying_inst_id = "m2505".to_string();
+        vec![
+            instrument("rb2505", "SHFE", "rb", "20250515"),
+            instrument("rb2510", "SHFE", "rb", "20251015"),
+            instrument("AP505", "CZCE", "AP", "20250514"),
+            option,
+        ]
+    }
+
+    #[test]
+    fn filters_by_exchange_product_and_expiry() {
+        let all = sample();
+        let filter = InstrumentFilter {
+            exchange_id: Some("SHFE".to_string()),
+            expire_to: Some("20250601".to_string()),
+            ..Default::default()
+        };
+        let found = filter.apply(&all);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].instrument_id, "rb2505");
+
+        let filter = InstrumentFilter {
+            product_id: Some("ap".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(filter.apply(&all)[0].instrument_id, "AP505");
+    }
+
+    #[test]
+    fn filters_options_by_underlying_and_class() {
+        let filter = InstrumentFilter {
+            product_class: Some("2".to_string()),
+            underlying_inst_id: Some("m2505".to_string()),
+            ..Default::default()
+        };
+        let found = filter.apply(&sample());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].instrument_id, "m2505-C-3000");
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = InstrumentFilter {
+            exchange_id: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(filter.apply(&sample()).len(), 4);
+    }
+
+    #[test]
+    fn disk_cache_is_keyed_by_trading_day() {
+        let dir = std::env::temp_dir().join(format!("instrument_cache_test_{}", std::process::id()));
+        let old = InstrumentCatalogue {
+            trading_day: "20250102".to_string(),
+            instruments: sample(),
+        };
+        save_cache(&dir, &old).unwrap();
+        let new = InstrumentCatalogue {
+            trading_day: "20250103".to_string(),
+            instruments: sample()[..1].to_vec(),
+        };
+        save_cache(&dir, &new).unwrap();
+
+        assert!(load_cache(&dir, "20250102").is_none());
+        assert_eq!(load_cache(&dir, "20250103").unwrap().instruments.len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,440 @@
+// 本地条件单：不依赖柜台条件单，止损市价、止损限价和触价单保存在本地，
+// 每笔行情到达时检查同合约的待触发条件单，触发后报出；
+// 未触发的条件单按账户写到磁盘，重启登录后恢复
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ctp_commands::{get_next_request_id, send_order, OrderRequest};
+use crate::ctp_flags::{Direction, HedgeFlag, OffsetFlag};
+use crate::market_data::MarketDataTick;
+
+const STORE_FILE: &str = "local_triggers.json";
+
+/// 条件单触发或状态变化时推送给前端
+pub const LOCAL_TRIGGER_EVENT: &str = "local-trigger-update";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerKind {
+    /// 最新价向不利方向突破触发价后以对手价报单
+    StopMarket,
+    /// 最新价向不利方向突破触发价后以指定限价报单
+    StopLimit,
+    /// 最新价向有利方向触及触发价后以对手价报单
+    MarketIfTouched,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerStatus {
+    Pending,
+    /// 已触发并发出子单
+    Triggered,
+    /// 已触发但子单未能发出
+    Failed,
+    Canceled,
+}
+
+/// 前端提交的条件单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalTriggerRequest {
+    pub instrument_id: String,
+    pub direction: Direction,
+    pub kind: TriggerKind,
+    pub trigger_price: f64,
+    /// 止损限价单的报单价格，其余类型忽略
+    pub limit_price: Option<f64>,
+    pub volume: i32,
+    pub offset_flag: Option<OffsetFlag>,
+    pub hedge_flag: Option<HedgeFlag>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalTrigger {
+    pub trigger_id: String,
+    pub account_key: String,
+    pub instrument_id: String,
+    pub direction: Direction,
+    pub kind: TriggerKind,
+    pub trigger_price: f64,
+    pub limit_price: Option<f64>,
+    pub volume: i32,
+    pub offset_flag: Option<OffsetFlag>,
+    pub hedge_flag: Option<HedgeFlag>,
+    pub status: TriggerStatus,
+    pub created_at: i64,
+    pub triggered_at: Option<i64>,
+    /// 触发时的最新价
+    pub triggered_price: Option<f64>,
+    /// 子单的报单引用
+    pub order_ref: Option<String>,
+    pub error: Option<String>,
+}
+
+impl LocalTrigger {
+    /// 按最新价判断是否触发：止损单买入向上突破、卖出向下突破，触价单相反
+    pub fn is_touched(&self, tick: &MarketDataTick) -> bool {
+        let last = tick.last_price;
+        if last <= 0.0 {
+            return false;
+        }
+        let buy = self.direction == Direction::Buy;
+        match self.kind {
+            TriggerKind::StopMarket | TriggerKind::StopLimit => {
+                if buy {
+                    last >= self.trigger_price
+                } else {
+                    last <= self.trigger_price
+                }
+            }
+            TriggerKind::MarketIfTouched => {
+                if buy {
+                    last <= self.trigger_price
+                } else {
+                    last >= self.trigger_price
+                }
+            }
+        }
+    }
+
+    /// 触发后发出的子单。上期所等不支持市价单，"市价"类条件单用对手价限价单代替
+    pub fn child_order(&self, tick: &MarketDataTick) -> Result<OrderRequest, String> {
+        let price = match self.kind {
+            TriggerKind::StopLimit => self
+                .limit_price
+                .ok_or_else(|| "止损限价单缺少报单价格".to_string())?,
+            TriggerKind::StopMarket | TriggerKind::MarketIfTouched => {
+                let opposite = match self.direction {
+                    Direction::Buy => tick.ask_price1,
+                    Direction::Sell => tick.bid_price1,
+                };
+                if opposite > 0.0 {
+                    opposite
+                } else {
+                    tick.last_price
+                }
+            }
+        };
+        Ok(OrderRequest::limit(&self.instrument_id, self.direction, price, self.volume)
+            .with_flags(self.offset_flag, self.hedge_flag))
+    }
+}
+
+lazy_static::lazy_static! {
+    // 每个交易会话的本地条件单，含已触发和已撤销的记录
+    static ref TRIGGERS: Arc<Mutex<HashMap<String, Vec<LocalTrigger>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // 条件单文件所在目录，启动交易登录时设置
+    static ref STORE_DIR: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
+}
+
+pub fn set_store_dir(dir: Option<PathBuf>) {
+    *STORE_DIR.lock().unwrap() = dir;
+}
+
+fn load_all(dir: &Path) -> HashMap<String, Vec<LocalTrigger>> {
+    fs::read_to_string(dir.join(STORE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 只保存仍待触发的条件单
+fn save_pending(dir: &Path, account_key: &str, triggers: &[LocalTrigger]) -> io::Result<()> {
+    let pending: Vec<LocalTrigger> = triggers
+        .iter()
+        .filter(|t| t.account_key == account_key && t.status == TriggerStatus::Pending)
+        .cloned()
+        .collect();
+    let mut all = load_all(dir);
+    all.insert(account_key.to_string(), pending);
+    fs::create_dir_all(dir)?;
+    let content = serde_json::to_string_pretty(&all).map_err(io::Error::other)?;
+    fs::write(dir.join(STORE_FILE), content)
+}
+
+fn persist(account_key: &str, triggers: &[LocalTrigger]) {
+    if let Some(dir) = STORE_DIR.lock().unwrap().as_deref() {
+        if let Err(e) = save_pending(dir, account_key, triggers) {
+            println!("⚠️ [WARN] Failed to persist local triggers: {}", e);
+        }
+    }
+}
+
+// 把该账户在其他会话中的条件单移到本会话，再补上磁盘上还没有的，返回新加入的条数。
+// 重新登录会得到新的会话，同一笔条件单只能留在一个会话里，否则会重复触发
+fn adopt(
+    triggers: &mut HashMap<String, Vec<LocalTrigger>>,
+    session_id: &str,
+    account_key: &str,
+    stored: Vec<LocalTrigger>,
+) -> usize {
+    let mut moved = Vec::new();
+    for (_, session) in triggers.iter_mut().filter(|(id, _)| id.as_str() != session_id) {
+        let (ours, others): (Vec<_>, Vec<_>) = std::mem::take(session)
+            .into_iter()
+            .partition(|t| t.account_key == account_key);
+        *session = others;
+        moved.extend(ours);
+    }
+    let session = triggers.entry(session_id.to_string()).or_default();
+    let mut restored = 0;
+    // 内存中的状态比磁盘上的新，先放入
+    for trigger in moved.into_iter().chain(stored) {
+        if !session.iter().any(|t| t.trigger_id == trigger.trigger_id) {
+            session.push(trigger);
+            restored += 1;
+        }
+    }
+    restored
+}
+
+/// OnRspUserLogin 后调用，把该账户的条件单（旧会话中的和磁盘上待触发的）恢复到本会话
+pub fn restore(session_id: &str, account_key: &str) {
+    let dir = STORE_DIR.lock().unwrap().clone();
+    let stored = dir
+        .and_then(|dir| load_all(&dir).remove(account_key))
+        .unwrap_or_default();
+    let restored = adopt(&mut TRIGGERS.lock().unwrap(), session_id, account_key, stored);
+    if restored > 0 {
+        println!("✅ [DEBUG] Restored {} local triggers for session {}", restored, session_id);
+    }
+}
+
+pub fn place(
+    session_id: &str,
+    account_key: &str,
+    request: LocalTriggerRequest,
+) -> Result<LocalTrigger, String> {
+    if request.volume <= 0 {
+        return Err(format!("条件单数量必须大于 0: {}", request.volume));
+    }
+    if request.trigger_price <= 0.0 {
+        return Err(format!("无效的触发价: {}", request.trigger_price));
+    }
+    if request.kind == TriggerKind::StopLimit && request.limit_price.is_none_or(|p| p <= 0.0) {
+        return Err("止损限价单需要有效的报单价格".to_string());
+    }
+    let created_at = chrono::Local::now().timestamp_millis();
+    let trigger = LocalTrigger {
+        trigger_id: format!("trigger_{}_{}", created_at, get_next_request_id()),
+        account_key: account_key.to_string(),
+        instrument_id: request.instrument_id,
+        direction: request.direction,
+        kind: request.kind,
+        trigger_price: request.trigger_price,
+        limit_price: request.limit_price,
+        volume: request.volume,
+        offset_flag: request.offset_flag,
+        hedge_flag: request.hedge_flag,
+        status: TriggerStatus::Pending,
+        created_at,
+        triggered_at: None,
+        triggered_price: None,
+        order_ref: None,
+        error: None,
+    };
+    let mut triggers = TRIGGERS.lock().unwrap();
+    let session = triggers.entry(session_id.to_string()).or_default();
+    session.push(trigger.clone());
+    persist(account_key, session);
+    Ok(trigger)
+}
+
+pub fn cancel(session_id: &str, trigger_id: &str) -> Result<LocalTrigger, String> {
+    let mut triggers = TRIGGERS.lock().unwrap();
+    let session = triggers.entry(session_id.to_string()).or_default();
+    let trigger = session
+        .iter_mut()
+        .find(|t| t.trigger_id == trigger_id)
+        .ok_or_else(|| format!("未找到条件单 {}", trigger_id))?;
+    if trigger.status != TriggerStatus::Pending {
+        return Err(format!("条件单 {} 已不在待触发状态", trigger_id));
+    }
+    trigger.status = TriggerStatus::Canceled;
+    let trigger = trigger.clone();
+    persist(&trigger.account_key, session);
+    Ok(trigger)
+}
+
+pub fn list(session_id: &str) -> Vec<LocalTrigger> {
+    TRIGGERS
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+// 取出被这笔行情触发的条件单并标记为已触发，保证每笔条件单只触发一次
+fn take_touched(tick: &MarketDataTick) -> Vec<(String, LocalTrigger)> {
+    let now = chrono::Local::now().timestamp_millis();
+    let mut touched = Vec::new();
+    let mut seen = HashSet::new();
+    let mut triggers = TRIGGERS.lock().unwrap();
+    for (session_id, session) in triggers.iter_mut() {
+        for trigger in session.iter_mut() {
+            if trigger.status == TriggerStatus::Pending
+                && trigger.instrument_id == tick.instrument_id
+                && trigger.is_touched(tick)
+                // 同一笔条件单只发一次子单
+                && seen.insert(trigger.trigger_id.clone())
+            {
+                trigger.status = TriggerStatus::Triggered;
+                trigger.triggered_at = Some(now);
+                trigger.triggered_price = Some(tick.last_price);
+                touched.push((session_id.clone(), trigger.clone()));
+            }
+        }
+    }
+    touched
+}
+
+// 记录子单发送结果并更新磁盘
+fn finish(session_id: &str, trigger_id: &str, sent: Result<String, String>) -> Option<LocalTrigger> {
+    let mut triggers = TRIGGERS.lock().unwrap();
+    let session = triggers.get_mut(session_id)?;
+    let trigger = session.iter_mut().find(|t| t.trigger_id == trigger_id)?;
+    match sent {
+        Ok(order_ref) => trigger.order_ref = Some(order_ref),
+        Err(error) => {
+            trigger.status = TriggerStatus::Failed;
+            trigger.error = Some(error);
+        }
+    }
+    let trigger = trigger.clone();
+    persist(&trigger.account_key, session);
+    Some(trigger)
+}
+
+/// 每笔行情到达时调用：发出被触发条件单的子单，返回状态有变化的条件单
+pub fn on_tick(tick: &MarketDataTick) -> Vec<LocalTrigger> {
+    take_touched(tick)
+        .into_iter()
+        .filter_map(|(session_id, trigger)| {
+            println!(
+                "📤 [DEBUG] Local trigger {} touched at {}, sending child order",
+                trigger.trigger_id, tick.last_price
+            );
+            let sent = trigger
+                .child_order(tick)
+                .and_then(|order| send_order(&session_id, &order, false))
+                .map(|pending| pending.result.order_ref);
+            if let Err(error) = &sent {
+                println!("❌ [ERROR] Local trigger {} failed: {}", trigger.trigger_id, error);
+            }
+            finish(&session_id, &trigger.trigger_id, sent)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctp_flags::OrderPriceType;
+
+    fn trigger(kind: TriggerKind, direction: Direction, trigger_price: f64) -> LocalTrigger {
+        LocalTrigger {
+            trigger_id: "t1".to_string(),
+            account_key: "9999:a".to_string(),
+            instrument_id: "rb2505".to_string(),
+            direction,
+            kind,
+            trigger_price,
+            limit_price: None,
+            volume: 2,
+            offset_flag: None,
+            hedge_flag: None,
+            status: TriggerStatus::Pending,
+            created_at: 0,
+            triggered_at: None,
+            triggered_price: None,
+            order_ref: None,
+            error: None,
+        }
+    }
+
+    fn tick(last_price: f64) -> MarketDataTick {
+        MarketDataTick {
+            instrument_id: "rb2505".to_string(),
+            last_price,
+            bid_price1: last_price - 1.0,
+            ask_price1: last_price + 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stops_trigger_on_adverse_moves_and_touches_on_favourable_ones() {
+        let buy_stop = trigger(TriggerKind::StopMarket, Direction::Buy, 3500.0);
+        assert!(!buy_stop.is_touched(&tick(3499.0)));
+        assert!(buy_stop.is_touched(&tick(3500.0)));
+
+        let sell_stop = trigger(TriggerKind::StopLimit, Direction::Sell, 3500.0);
+        assert!(sell_stop.is_touched(&tick(3499.0)));
+        assert!(!sell_stop.is_touched(&tick(3501.0)));
+
+        let buy_mit = trigger(TriggerKind::MarketIfTouched, Direction::Buy, 3500.0);
+        assert!(buy_mit.is_touched(&tick(3499.0)));
+        assert!(!buy_mit.is_touched(&tick(3501.0)));
+        assert!(!buy_mit.is_touched(&tick(0.0)));
+    }
+
+    #[test]
+    fn child_orders_use_the_opposite_side_or_the_stop_limit() {
+        let buy_stop = trigger(TriggerKind::StopMarket, Direction::Buy, 3500.0);
+        let child = buy_stop.child_order(&tick(3500.0)).unwrap();
+        assert_eq!(child.price, 3501.0);
+        assert_eq!(child.order_type, OrderPriceType::LimitPrice);
+        assert_eq!(child.volume, 2);
+
+        let sell_mit = trigger(TriggerKind::MarketIfTouched, Direction::Sell, 3500.0);
+        assert_eq!(sell_mit.child_order(&tick(3500.0)).unwrap().price, 3499.0);
+
+        let mut stop_limit = trigger(TriggerKind::StopLimit, Direction::Sell, 3500.0);
+        assert!(stop_limit.child_order(&tick(3499.0)).is_err());
+        stop_limit.limit_price = Some(3495.0);
+        assert_eq!(stop_limit.child_order(&tick(3499.0)).unwrap().price, 3495.0);
+    }
+
+    #[test]
+    fn restore_moves_account_triggers_out_of_old_sessions() {
+        let mut other_account = trigger(TriggerKind::StopMarket, Direction::Buy, 3500.0);
+        other_account.trigger_id = "t2".to_string();
+        other_account.account_key = "9999:b".to_string();
+        let mut canceled = trigger(TriggerKind::StopMarket, Direction::Buy, 3500.0);
+        canceled.status = TriggerStatus::Canceled;
+        let mut triggers = HashMap::from([(
+            "trader_1".to_string(),
+            vec![canceled, other_account],
+        )]);
+
+        let stored = vec![trigger(TriggerKind::StopMarket, Direction::Buy, 3500.0)];
+        assert_eq!(adopt(&mut triggers, "trader_2", "9999:a", stored), 1);
+        assert_eq!(triggers["trader_1"].len(), 1);
+        assert_eq!(triggers["trader_1"][0].account_key, "9999:b");
+        // 旧会话里已撤销的状态优先于磁盘上的待触发记录
+        assert_eq!(triggers["trader_2"].len(), 1);
+        assert_eq!(triggers["trader_2"][0].status, TriggerStatus::Canceled);
+    }
+
+    #[test]
+    fn only_pending_triggers_are_persisted() {
+        let dir = std::env::temp_dir().join(format!("local_triggers_test_{}", std::process::id()));
+        let pending = trigger(TriggerKind::StopMarket, Direction::Buy, 3500.0);
+        let mut done = trigger(TriggerKind::StopMarket, Direction::Sell, 3400.0);
+        done.trigger_id = "t2".to_string();
+        done.status = TriggerStatus::Triggered;
+        save_pending(&dir, "9999:a", &[pending, done]).unwrap();
+
+        let stored = load_all(&dir).remove("9999:a").unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].trigger_id, "t1");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -2,6 +2,27 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 mod file_io;
 mod ctp_commands;
+mod account;
+mod algo_orders;
+mod cancel_quota;
+mod close_router;
+mod ctp_flags;
+mod instruments;
+mod local_triggers;
+mod market_data;
+mod md_spi;
+mod order_ref;
+mod order_store;
+mod parked_orders;
+mod position;
+mod price_chaser;
+mod query_scheduler;
+mod risk;
+mod rsp_correlator;
+mod self_trade;
+mod subscriptions;
+mod trader_events;
+mod trader_spi;
 
 fn main() {
     tauri::Builder::default()
@@ -16,14 +37,52 @@ fn main() {
             ctp_commands::trader_login,
             ctp_commands::subscribe_market_data,
             ctp_commands::unsubscribe_market_data,
+            ctp_commands::set_market_data_conflation,
+            ctp_commands::list_subscriptions,
             ctp_commands::insert_order,
             ctp_commands::cancel_order,
+            ctp_commands::cancel_orders,
+            ctp_commands::cancel_all_orders,
+            ctp_commands::close_position,
+            ctp_commands::list_close_orders,
+            ctp_commands::place_local_trigger,
+            ctp_commands::cancel_local_trigger,
+            ctp_commands::list_local_triggers,
+            ctp_commands::park_order,
+            ctp_commands::park_cancel,
+            ctp_commands::query_parked_orders,
+            ctp_commands::remove_parked_order,
+            ctp_commands::remove_parked_cancel,
+            ctp_commands::start_price_chase,
+            ctp_commands::cancel_price_chase,
+            ctp_commands::list_price_chases,
+            ctp_commands::start_algo_order,
+            ctp_commands::pause_algo_order,
+            ctp_commands::resume_algo_order,
+            ctp_commands::cancel_algo_order,
+            ctp_commands::list_algo_orders,
+            ctp_commands::get_cancel_counts,
+            ctp_commands::set_cancel_limits,
+            ctp_commands::get_risk_config,
+            ctp_commands::set_risk_config,
             ctp_commands::query_account,
+            ctp_commands::get_account_snapshot,
             ctp_commands::query_position,
+            ctp_commands::query_position_summary,
+            ctp_commands::query_order,
+            ctp_commands::query_trade,
+            ctp_commands::list_orders,
+            ctp_commands::get_order,
+            ctp_commands::list_trades,
             ctp_commands::query_instruments,
             ctp_commands::validate_trader_session,
             ctp_commands::test_order_interface
         ])
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                ctp_commands::release_window_subscriptions(window.label());
+            }
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
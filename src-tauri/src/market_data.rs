@@ -0,0 +1,268 @@
+// 深度行情：CTP 结构体到前端 tick 的转换、各合约的最新行情，以及按合约的行情合并（conflation）
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use tauri_app_vue_lib::{gb18030_cstr_to_str_i8, CThostFtdcDepthMarketDataField};
+
+/// 推送给前端的行情快照，字段与 types/ctp.ts 中的 MarketDataInfo 对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarketDataTick {
+    pub instrument_id: String,
+    pub exchange_id: String,
+    pub exchange_inst_id: String,
+    pub trading_day: String,
+    pub action_day: String,
+    pub update_time: String,
+    pub update_millisec: i32,
+    pub last_price: f64,
+    pub pre_settlement_price: f64,
+    pub pre_close_price: f64,
+    pub pre_open_interest: f64,
+    pub open_price: f64,
+    pub highest_price: f64,
+    pub lowest_price: f64,
+    pub volume: i32,
+    pub turnover: f64,
+    pub open_interest: f64,
+    pub close_price: f64,
+    pub settlement_price: f64,
+    pub upper_limit_price: f64,
+    pub lower_limit_price: f64,
+    pub average_price: f64,
+    pub bid_price1: f64,
+    pub bid_volume1: i32,
+    pub ask_price1: f64,
+    pub ask_volume1: i32,
+    pub bid_price2: f64,
+    pub bid_volume2: i32,
+    pub ask_price2: f64,
+    pub ask_volume2: i32,
+    pub bid_price3: f64,
+    pub bid_volume3: i32,
+    pub ask_price3: f64,
+    pub ask_volume3: i32,
+    pub bid_price4: f64,
+    pub bid_volume4: i32,
+    pub ask_price4: f64,
+    pub ask_volume4: i32,
+    pub bid_price5: f64,
+    pub bid_volume5: i32,
+    pub ask_price5: f64,
+    pub ask_volume5: i32,
+    pub banding_upper_price: f64,
+    pub banding_lower_price: f64,
+}
+
+// CTP 用 DBL_MAX 表示无效价格，推送前统一转成 0
+fn valid_price(v: f64) -> f64 {
+    if v.is_finite() && v != f64::MAX {
+        v
+    } else {
+        0.0
+    }
+}
+
+impl From<&CThostFtdcDepthMarketDataField> for MarketDataTick {
+    fn from(d: &CThostFtdcDepthMarketDataField) -> Self {
+        MarketDataTick {
+            instrument_id: gb18030_cstr_to_str_i8(&d.InstrumentID).to_string(),
+            exchange_id: gb18030_cstr_to_str_i8(&d.ExchangeID).to_string(),
+            exchange_inst_id: gb18030_cstr_to_str_i8(&d.ExchangeInstID).to_string(),
+            trading_day: gb18030_cstr_to_str_i8(&d.TradingDay).to_string(),
+            action_day: gb18030_cstr_to_str_i8(&d.ActionDay).to_string(),
+            update_time: gb18030_cstr_to_str_i8(&d.UpdateTime).to_string(),
+            update_millisec: d.UpdateMillisec,
+            last_price: valid_price(d.LastPrice),
+            pre_settlement_price: valid_price(d.PreSettlementPrice),
+            pre_close_price: valid_price(d.PreClosePrice),
+            pre_open_interest: d.PreOpenInterest,
+            open_price: valid_price(d.OpenPrice),
+            highest_price: valid_price(d.HighestPrice),
+            lowest_price: valid_price(d.LowestPrice),
+            volume: d.Volume,
+            turnover: d.Turnover,
+            open_interest: d.OpenInterest,
+            close_price: valid_price(d.ClosePrice),
+            settlement_price: valid_price(d.SettlementPrice),
+            upper_limit_price: valid_price(d.UpperLimitPrice),
+            lower_limit_price: valid_price(d.LowerLimitPrice),
+            average_price: valid_price(d.AveragePrice),
+            bid_price1: valid_price(d.BidPrice1),
+            bid_volume1: d.BidVolume1,
+            ask_price1: valid_price(d.AskPrice1),
+            ask_volume1: d.AskVolume1,
+            bid_price2: valid_price(d.BidPrice2),
+            bid_volume2: d.BidVolume2,
+            ask_price2: valid_price(d.AskPrice2),
+            ask_volume2: d.AskVolume2,
+            bid_price3: valid_price(d.BidPrice3),
+            bid_volume3: d.BidVolume3,
+            ask_price3: valid_price(d.AskPrice3),
+            ask_volume3: d.AskVolume3,
+            bid_price4: valid_price(d.BidPrice4),
+            bid_volume4: d.BidVolume4,
+            ask_price4: valid_price(d.AskPrice4),
+            ask_volume4: d.AskVolume4,
+            bid_price5: valid_price(d.BidPrice5),
+            bid_volume5: d.BidVolume5,
+            ask_price5: valid_price(d.AskPrice5),
+            ask_volume5: d.AskVolume5,
+            banding_upper_price: valid_price(d.BandingUpperPrice),
+            banding_lower_price: valid_price(d.BandingLowerPrice),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    // 每个合约收到的最新一笔行情（未经合并），供风控和交易算法读取
+    static ref LAST_TICKS: Arc<Mutex<HashMap<String, MarketDataTick>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+pub fn record_last_tick(tick: &MarketDataTick) {
+    LAST_TICKS
+        .lock()
+        .unwrap()
+        .insert(tick.instrument_id.clone(), tick.clone());
+}
+
+pub fn last_tick(instrument_id: &str) -> Option<MarketDataTick> {
+    LAST_TICKS.lock().unwrap().get(instrument_id).cloned()
+}
+
+/// 单个合约的行情事件名，例如 `market-data-rb2505`
+///
+/// Tauri 事件名只允许字母、数字以及 `-` `/` `:` `_`，组合合约中的空格和 `&` 替换为 `_`
+pub fn market_data_event_name(instrument_id: &str) -> String {
+    let id: String = instrument_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("market-data-{}", id)
+}
+
+/// 按合约合并行情：同一合约在一个时间窗口内只推送最新的一笔
+#[derive(Default)]
+pub struct TickConflator {
+    last_emit: HashMap<String, Instant>,
+    pending: HashMap<String, MarketDataTick>,
+}
+
+impl TickConflator {
+    /// 收到新行情；窗口已过时返回该笔行情立即推送，否则只保留为待推送的最新值
+    pub fn offer(
+        &mut self,
+        tick: MarketDataTick,
+        now: Instant,
+        interval: Duration,
+    ) -> Option<MarketDataTick> {
+        if interval.is_zero() {
+            return Some(tick);
+        }
+        if self.is_due(&tick.instrument_id, now, interval) {
+            self.pending.remove(&tick.instrument_id);
+            self.last_emit.insert(tick.instrument_id.clone(), now);
+            Some(tick)
+        } else {
+            self.pending.insert(tick.instrument_id.clone(), tick);
+            None
+        }
+    }
+
+    /// 取出窗口已过的待推送行情
+    pub fn drain_due(&mut self, now: Instant, interval: Duration) -> Vec<MarketDataTick> {
+        let due: Vec<String> = self
+            .pending
+            .keys()
+            .filter(|id| self.is_due(id, now, interval))
+            .cloned()
+            .collect();
+        due.into_iter()
+            .filter_map(|id| {
+                let tick = self.pending.remove(&id)?;
+                self.last_emit.insert(id, now);
+                Some(tick)
+            })
+            .collect()
+    }
+
+    fn is_due(&self, instrument_id: &str, now: Instant, interval: Duration) -> bool {
+        self.last_emit
+            .get(instrument_id)
+            .is_none_or(|last| now.duration_since(*last) >= interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(instrument_id: &str, last_price: f64) -> MarketDataTick {
+        MarketDataTick {
+            instrument_id: instrument_id.to_string(),
+            last_price,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn conflation_keeps_latest_tick_per_instrument() {
+        let interval = Duration::from_millis(100);
+        let t0 = Instant::now();
+        let mut conflator = TickConflator::default();
+
+        assert!(conflator.offer(tick("rb2505", 3500.0), t0, interval).is_some());
+        assert!(conflator
+            .offer(tick("rb2505", 3501.0), t0 + Duration::from_millis(10), interval)
+            .is_none());
+        assert!(conflator
+            .offer(tick("rb2505", 3502.0), t0 + Duration::from_millis(20), interval)
+            .is_none());
+        // 其他合约不受影响
+        assert!(conflator
+            .offer(tick("hc2505", 3300.0), t0 + Duration::from_millis(20), interval)
+            .is_some());
+
+        assert!(conflator
+            .drain_due(t0 + Duration::from_millis(50), interval)
+            .is_empty());
+        let flushed = conflator.drain_due(t0 + Duration::from_millis(100), interval);
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].last_price, 3502.0);
+        assert!(conflator
+            .drain_due(t0 + Duration::from_millis(300), interval)
+            .is_empty());
+    }
+
+    #[test]
+    fn zero_interval_disables_conflation() {
+        let t0 = Instant::now();
+        let mut conflator = TickConflator::default();
+        assert!(conflator.offer(tick("rb2505", 1.0), t0, Duration::ZERO).is_some());
+        assert!(conflator.offer(tick("rb2505", 2.0), t0, Duration::ZERO).is_some());
+    }
+
+    #[test]
+    fn event_name_is_sanitized() {
+        assert_eq!(market_data_event_name("rb2505"), "market-data-rb2505");
+        assert_eq!(
+            market_data_event_name("SP m2501&m2505"),
+            "market-data-SP_m2501_m2505"
+        );
+    }
+
+    #[test]
+    fn invalid_prices_become_zero() {
+        assert_eq!(valid_price(f64::MAX), 0.0);
+        assert_eq!(valid_price(3500.0), 3500.0);
+    }
+}
@@ -0,0 +1,365 @@
+// 行情 SPI 事件泵：每个行情会话一个后台任务，负责消费 CThostFtdcMdSpiStream
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+use tauri_app_vue_lib::md_api::{self, CThostFtdcMdSpiOutput, CThostFtdcMdSpiStream};
+use tauri_app_vue_lib::{
+    gb18030_cstr_to_str_i8, rsp_info_error, CThostFtdcMdApi, CThostFtdcReqUserLoginField,
+};
+
+use crate::ctp_commands::{copy_str_to_ctp_array, get_next_request_id, CtpAccountConfig, MD_APIS};
+use crate::local_triggers::{self, LOCAL_TRIGGER_EVENT};
+use crate::market_data::{self, market_data_event_name, MarketDataTick, TickConflator};
+use crate::price_chaser;
+use crate::subscriptions;
+
+/// 等待 OnRspUserLogin 的最长时间
+pub const LOGIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 行情合并开启时检查待推送行情的周期
+const CONFLATION_FLUSH_PERIOD: Duration = Duration::from_millis(25);
+
+/// 登录成功时为交易日，失败时为解码后的错误信息
+pub type LoginResult = Result<String, String>;
+
+// 事件泵的运行状态
+struct MdSpiState {
+    config: CtpAccountConfig,
+    front_connected: bool,
+    logged_in: bool,
+    login_waiter: Option<oneshot::Sender<LoginResult>>,
+}
+
+lazy_static::lazy_static! {
+    // 已经注册 SPI 并启动事件泵的行情会话
+    static ref MD_SPI_STATES: Arc<Mutex<HashMap<String, MdSpiState>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // 每个行情会话的合并窗口（毫秒），0 或未设置表示逐笔推送
+    static ref MD_CONFLATION_MS: Arc<Mutex<HashMap<String, u64>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+// 在指定会话的行情 API 上执行操作
+pub(crate) fn with_md_api<R>(
+    session_id: &str,
+    f: impl FnOnce(&mut CThostFtdcMdApi) -> R,
+) -> Result<R, String> {
+    let mut apis = MD_APIS
+        .lock()
+        .map_err(|e| format!("Failed to lock MD_APIS: {}", e))?;
+    match apis.get_mut(session_id) {
+        Some(api) => Ok(f(&mut **api)),
+        None => Err(format!("Session ID {} not found", session_id)),
+    }
+}
+
+/// 行情会话是否已登录
+pub fn is_logged_in(session_id: &str) -> bool {
+    MD_SPI_STATES
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .map(|state| state.logged_in)
+        .unwrap_or(false)
+}
+
+fn to_cstrings(instrument_ids: &[String]) -> Result<Vec<std::ffi::CString>, String> {
+    instrument_ids
+        .iter()
+        .map(|id| {
+            std::ffi::CString::new(id.as_str())
+                .map_err(|e| format!("Invalid instrument id {}: {}", id, e))
+        })
+        .collect()
+}
+
+/// 向 CTP 订阅行情；尚未登录时直接返回，登录成功后会按订阅表统一订阅
+pub fn subscribe_instruments(session_id: &str, instrument_ids: &[String]) -> Result<(), String> {
+    if instrument_ids.is_empty() || !is_logged_in(session_id) {
+        return Ok(());
+    }
+    let instruments = to_cstrings(instrument_ids)?;
+    let count = instruments.len() as std::os::raw::c_int;
+    let result = with_md_api(session_id, |api| api.subscribe_market_data(instruments, count))?;
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(format!("Failed to subscribe market data, error code: {}", result))
+    }
+}
+
+/// 向 CTP 退订行情
+pub fn unsubscribe_instruments(session_id: &str, instrument_ids: &[String]) -> Result<(), String> {
+    if instrument_ids.is_empty() || !is_logged_in(session_id) {
+        return Ok(());
+    }
+    let instruments = to_cstrings(instrument_ids)?;
+    let count = instruments.len() as std::os::raw::c_int;
+    let result = with_md_api(session_id, |api| api.un_subscribe_market_data(instruments, count))?;
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(format!("Failed to unsubscribe market data, error code: {}", result))
+    }
+}
+
+/// 设置行情合并窗口，同一合约在窗口内只推送最新一笔
+pub fn set_conflation(session_id: &str, interval_ms: u64) -> Result<(), String> {
+    if !MD_APIS.lock().unwrap().contains_key(session_id) {
+        return Err(format!("Session ID {} not found", session_id));
+    }
+    MD_CONFLATION_MS
+        .lock()
+        .unwrap()
+        .insert(session_id.to_string(), interval_ms);
+    Ok(())
+}
+
+/// 行情 API 释放后清理会话状态和合并窗口设置
+pub fn forget_session(session_id: &str) {
+    MD_SPI_STATES.lock().unwrap().remove(session_id);
+    MD_CONFLATION_MS.lock().unwrap().remove(session_id);
+}
+
+fn conflation_interval(session_id: &str) -> Duration {
+    let interval_ms = MD_CONFLATION_MS
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .copied()
+        .unwrap_or(0);
+    Duration::from_millis(interval_ms)
+}
+
+// 把登录结果交给正在等待的 md_login 命令
+fn resolve_login(session_id: &str, result: LoginResult) {
+    let waiter = {
+        let mut states = MD_SPI_STATES.lock().unwrap();
+        states
+            .get_mut(session_id)
+            .and_then(|state| state.login_waiter.take())
+    };
+    if let Some(waiter) = waiter {
+        let _ = waiter.send(result);
+    }
+}
+
+/// 等待超时后丢弃登录等待端，下次登录可以重新发起
+pub fn cancel_login(session_id: &str) {
+    let mut states = MD_SPI_STATES.lock().unwrap();
+    if let Some(state) = states.get_mut(session_id) {
+        state.login_waiter = None;
+    }
+}
+
+/// 启动行情登录流程，返回用于等待 OnRspUserLogin 的接收端
+///
+/// 首次登录时注册 SPI、注册前置并调用 init；事件泵已在运行时只更新账户配置，
+/// 前置已连接则直接重新发送登录请求。
+pub fn start_login(
+    session_id: &str,
+    config: CtpAccountConfig,
+    app: AppHandle,
+) -> Result<oneshot::Receiver<LoginResult>, String> {
+    if !MD_APIS.lock().unwrap().contains_key(session_id) {
+        return Err(format!("Session ID {} not found", session_id));
+    }
+
+    let md_front = config.md_front.clone();
+    // 前置地址含 NUL 时在加锁前返回错误
+    let front_address = std::ffi::CString::new(md_front.as_str())
+        .map_err(|e| format!("Invalid md front {}: {}", md_front, e))?;
+    let (sender, receiver) = oneshot::channel();
+    let mut states = MD_SPI_STATES.lock().unwrap();
+    if let Some(state) = states.get_mut(session_id) {
+        // 接收端已丢弃（命令超时或被取消）的等待不再占用登录流程
+        if state.login_waiter.as_ref().is_some_and(|waiter| !waiter.is_closed()) {
+            return Err("行情登录流程正在进行中".to_string());
+        }
+        state.config = config;
+        state.login_waiter = Some(sender);
+        let front_connected = state.front_connected;
+        drop(states);
+        if front_connected {
+            if let Err(error) = send_user_login(session_id) {
+                resolve_login(session_id, Err(error));
+            }
+        }
+        return Ok(receiver);
+    }
+
+    let stream = with_md_api(session_id, |api| {
+        let (stream, pp) = md_api::create_spi();
+        api.register_spi(pp);
+        api.register_front(front_address);
+        println!("✅ [DEBUG] Registered md front: {}", md_front);
+        api.init();
+        println!("✅ [DEBUG] MD API initialized");
+        stream
+    })?;
+
+    states.insert(
+        session_id.to_string(),
+        MdSpiState {
+            config,
+            front_connected: false,
+            logged_in: false,
+            login_waiter: Some(sender),
+        },
+    );
+    drop(states);
+
+    spawn_pump(session_id.to_string(), stream, app);
+    Ok(receiver)
+}
+
+// 每个会话一个后台任务，持有 SPI 流直到进程退出；
+// 行情按合约推送为 `market-data-<合约>` 事件，开启合并时由定时器补推窗口内的最新值
+fn spawn_pump(session_id: String, mut stream: Box<CThostFtdcMdSpiStream>, app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut conflator = TickConflator::default();
+        let mut flush = tokio::time::interval(CONFLATION_FLUSH_PERIOD);
+        flush.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                msg = stream.next() => match msg {
+                    Some(msg) => handle_spi_output(&session_id, msg, &app, &mut conflator),
+                    None => break,
+                },
+                _ = flush.tick() => {
+                    let interval = conflation_interval(&session_id);
+                    for tick in conflator.drain_due(Instant::now(), interval) {
+                        emit_tick(&app, &tick);
+                    }
+                }
+            }
+        }
+        println!("⚠️ [WARN] MD SPI stream ended for session: {}", session_id);
+    });
+}
+
+fn emit_tick(app: &AppHandle, tick: &MarketDataTick) {
+    let event = market_data_event_name(&tick.instrument_id);
+    if let Err(e) = app.emit(&event, tick) {
+        println!("❌ [ERROR] Failed to emit {}: {}", event, e);
+    }
+}
+
+fn handle_spi_output(
+    session_id: &str,
+    msg: CThostFtdcMdSpiOutput,
+    app: &AppHandle,
+    conflator: &mut TickConflator,
+) {
+    use CThostFtdcMdSpiOutput::*;
+    match msg {
+        OnFrontConnected(_) => {
+            println!("✅ [DEBUG] MD front connected for session: {}", session_id);
+            if let Some(state) = MD_SPI_STATES.lock().unwrap().get_mut(session_id) {
+                state.front_connected = true;
+            }
+            // 断线重连后 CTP 会再次回调 OnFrontConnected，需要重新登录
+            if let Err(error) = send_user_login(session_id) {
+                resolve_login(session_id, Err(error));
+            }
+        }
+        OnFrontDisconnected(p) => {
+            println!(
+                "⚠️ [WARN] MD front disconnected for session: {}, reason: {:#x}",
+                session_id, p.n_reason
+            );
+            if let Some(state) = MD_SPI_STATES.lock().unwrap().get_mut(session_id) {
+                state.front_connected = false;
+                state.logged_in = false;
+            }
+        }
+        OnRspUserLogin(p) => {
+            if let Some(error) = rsp_info_error(p.p_rsp_info.as_ref()) {
+                println!("❌ [ERROR] MD ReqUserLogin failed: {}", error);
+                resolve_login(session_id, Err(format!("行情登录失败: {}", error)));
+                return;
+            }
+            let trading_day = p
+                .p_rsp_user_login
+                .as_ref()
+                .map(|u| gb18030_cstr_to_str_i8(&u.TradingDay).to_string())
+                .unwrap_or_default();
+            println!(
+                "✅ [DEBUG] MD logged in for session: {}, trading_day: {}",
+                session_id, trading_day
+            );
+            if let Some(state) = MD_SPI_STATES.lock().unwrap().get_mut(session_id) {
+                state.logged_in = true;
+            }
+            // 重连登录后 CTP 不会保留之前的订阅，按订阅表重新订阅
+            let instruments = subscriptions::instruments(session_id);
+            if let Err(error) = subscribe_instruments(session_id, &instruments) {
+                println!("❌ [ERROR] Resubscribe failed: {}", error);
+            }
+            resolve_login(session_id, Ok(trading_day));
+        }
+        OnRtnDepthMarketData(p) => {
+            if let Some(dmd) = p.p_depth_market_data.as_ref() {
+                let tick = MarketDataTick::from(dmd);
+                market_data::record_last_tick(&tick);
+                for trigger in local_triggers::on_tick(&tick) {
+                    if let Err(e) = app.emit(LOCAL_TRIGGER_EVENT, &trigger) {
+                        println!("❌ [ERROR] Failed to emit {}: {}", LOCAL_TRIGGER_EVENT, e);
+                    }
+                }
+                price_chaser::on_tick(app, &tick);
+                let interval = conflation_interval(session_id);
+                if let Some(tick) = conflator.offer(tick, Instant::now(), interval) {
+                    emit_tick(app, &tick);
+                }
+            }
+        }
+        OnRspSubMarketData(p) => {
+            if let Some(error) = rsp_info_error(p.p_rsp_info.as_ref()) {
+                let instrument_id = p
+                    .p_specific_instrument
+                    .as_ref()
+                    .map(|i| gb18030_cstr_to_str_i8(&i.InstrumentID).to_string())
+                    .unwrap_or_default();
+                println!("❌ [ERROR] Subscribe {} failed: {}", instrument_id, error);
+            }
+        }
+        OnRspError(p) => {
+            if let Some(error) = rsp_info_error(p.p_rsp_info.as_ref()) {
+                println!(
+                    "❌ [ERROR] MD OnRspError, request_id: {}, {}",
+                    p.n_request_id, error
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+fn send_user_login(session_id: &str) -> Result<(), String> {
+    let config = MD_SPI_STATES
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .map(|state| state.config.clone())
+        .ok_or_else(|| format!("未找到会话 {} 的登录信息", session_id))?;
+
+    let mut req = CThostFtdcReqUserLoginField::default();
+    copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+    copy_str_to_ctp_array(&config.account, &mut req.UserID);
+    copy_str_to_ctp_array(&config.password, &mut req.Password);
+
+    let request_id = get_next_request_id();
+    let result = with_md_api(session_id, |api| api.req_user_login(&mut req, request_id))?;
+    if result == 0 {
+        println!("📤 [DEBUG] MD ReqUserLogin sent, request_id: {}", request_id);
+        Ok(())
+    } else {
+        Err(format!("发送行情登录请求失败，错误代码: {}", result))
+    }
+}
@@ -0,0 +1,209 @@
+// 报单引用：每个交易会话从登录应答的 MaxOrderRef 之后开始编号，撤单引用单独编号；
+// 每个账户当日预留到的最大报单引用写到磁盘，同一交易日重启或重连后也不会重复使用。
+// 引用按 REF_BLOCK 成批预留，只有用完一批时才写一次磁盘
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+const STORE_FILE: &str = "order_refs.json";
+
+// 每次写盘预留的报单引用个数；重启后从预留的末尾之后编号，最多跳过这么多个引用
+const REF_BLOCK: i64 = 100;
+
+/// 某个账户在某个交易日已预留的最大报单引用
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighWaterMark {
+    pub trading_day: String,
+    pub max_order_ref: i64,
+}
+
+// 按 "BrokerID:InvestorID" 保存
+type HighWaterMarks = HashMap<String, HighWaterMark>;
+
+// 单个交易会话的编号状态
+struct RefSequence {
+    account_key: String,
+    trading_day: String,
+    next_order_ref: i64,
+    // 已写到磁盘的预留上限
+    reserved_through: i64,
+    next_action_ref: i32,
+}
+
+impl RefSequence {
+    // 取下一个报单引用；超出预留上限时返回需要写盘的新高水位
+    fn take(&mut self) -> (i64, Option<HighWaterMark>) {
+        let order_ref = self.next_order_ref;
+        self.next_order_ref += 1;
+        if order_ref <= self.reserved_through {
+            return (order_ref, None);
+        }
+        self.reserved_through = order_ref + REF_BLOCK - 1;
+        let mark = HighWaterMark {
+            trading_day: self.trading_day.clone(),
+            max_order_ref: self.reserved_through,
+        };
+        (order_ref, Some(mark))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SEQUENCES: Arc<Mutex<HashMap<String, RefSequence>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // 高水位文件所在目录，启动交易登录时设置
+    static ref STORE_DIR: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
+}
+
+pub fn set_store_dir(dir: Option<PathBuf>) {
+    *STORE_DIR.lock().unwrap() = dir;
+}
+
+pub fn account_key(broker_id: &str, investor_id: &str) -> String {
+    format!("{}:{}", broker_id, investor_id)
+}
+
+/// 登录后第一个可用的报单引用：MaxOrderRef 与本交易日已预留的最大引用中较大者加一
+pub fn first_order_ref(max_order_ref: &str, trading_day: &str, used: Option<&HighWaterMark>) -> i64 {
+    let from_login = max_order_ref.trim().parse::<i64>().unwrap_or(0);
+    let from_store = used
+        .filter(|mark| mark.trading_day == trading_day)
+        .map_or(0, |mark| mark.max_order_ref);
+    from_login.max(from_store) + 1
+}
+
+fn load_marks(dir: &Path) -> HighWaterMarks {
+    fs::read_to_string(dir.join(STORE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_mark(dir: &Path, account_key: &str, mark: HighWaterMark) -> io::Result<()> {
+    let mut marks = load_marks(dir);
+    marks.insert(account_key.to_string(), mark);
+    fs::create_dir_all(dir)?;
+    let content = serde_json::to_string_pretty(&marks).map_err(io::Error::other)?;
+    fs::write(dir.join(STORE_FILE), content)
+}
+
+/// OnRspUserLogin 后调用，按登录应答和磁盘上的高水位确定本会话的起始报单引用
+pub fn seed(session_id: &str, account_key: &str, trading_day: &str, max_order_ref: &str) {
+    let dir = STORE_DIR.lock().unwrap().clone();
+    let stored = dir
+        .as_deref()
+        .and_then(|dir| load_marks(dir).remove(account_key));
+
+    let mut sequences = SEQUENCES.lock().unwrap();
+    let mut next_order_ref = first_order_ref(max_order_ref, trading_day, stored.as_ref());
+    // 同一进程内重连时，内存中的编号可能比磁盘上的更新
+    if let Some(current) = sequences.get(session_id) {
+        if current.account_key == account_key && current.trading_day == trading_day {
+            next_order_ref = next_order_ref.max(current.next_order_ref);
+        }
+    }
+    println!(
+        "✅ [DEBUG] Order ref for session {} starts at {} (MaxOrderRef: {})",
+        session_id, next_order_ref, max_order_ref
+    );
+    sequences.insert(
+        session_id.to_string(),
+        RefSequence {
+            account_key: account_key.to_string(),
+            trading_day: trading_day.to_string(),
+            next_order_ref,
+            reserved_through: next_order_ref - 1,
+            next_action_ref: 1,
+        },
+    );
+}
+
+/// 取下一个报单引用，用完一批预留时更新磁盘上的高水位
+pub fn next_order_ref(session_id: &str) -> Result<String, String> {
+    let mut sequences = SEQUENCES.lock().unwrap();
+    let sequence = sequences
+        .get_mut(session_id)
+        .ok_or_else(|| format!("会话 {} 尚未登录，无法分配报单引用", session_id))?;
+    let (order_ref, reserved) = sequence.take();
+
+    if let (Some(mark), Some(dir)) = (reserved, STORE_DIR.lock().unwrap().as_deref()) {
+        if let Err(e) = save_mark(dir, &sequence.account_key, mark) {
+            println!("⚠️ [WARN] Failed to persist order ref high-water mark: {}", e);
+        }
+    }
+    Ok(order_ref.to_string())
+}
+
+/// 取下一个撤单引用（OrderActionRef），与报单引用互不占用
+pub fn next_action_ref(session_id: &str) -> i32 {
+    let mut sequences = SEQUENCES.lock().unwrap();
+    match sequences.get_mut(session_id) {
+        Some(sequence) => {
+            let action_ref = sequence.next_action_ref;
+            sequence.next_action_ref += 1;
+            action_ref
+        }
+        None => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mark(trading_day: &str, max_order_ref: i64) -> HighWaterMark {
+        HighWaterMark {
+            trading_day: trading_day.to_string(),
+            max_order_ref,
+        }
+    }
+
+    #[test]
+    fn starts_after_max_order_ref() {
+        assert_eq!(first_order_ref("        12", "20250102", None), 13);
+        assert_eq!(first_order_ref("", "20250102", None), 1);
+    }
+
+    #[test]
+    fn same_day_high_water_mark_wins() {
+        let used = mark("20250102", 40);
+        assert_eq!(first_order_ref("12", "20250102", Some(&used)), 41);
+        assert_eq!(first_order_ref("50", "20250102", Some(&used)), 51);
+        // 上一交易日的记录不再适用
+        assert_eq!(first_order_ref("12", "20250103", Some(&used)), 13);
+    }
+
+    #[test]
+    fn refs_are_reserved_in_blocks() {
+        let mut sequence = RefSequence {
+            account_key: account_key("9999", "a"),
+            trading_day: "20250102".to_string(),
+            next_order_ref: 13,
+            reserved_through: 12,
+            next_action_ref: 1,
+        };
+        assert_eq!(sequence.take(), (13, Some(mark("20250102", 12 + REF_BLOCK))));
+        for expected in 14..=12 + REF_BLOCK {
+            assert_eq!(sequence.take(), (expected, None));
+        }
+        let (order_ref, reserved) = sequence.take();
+        assert_eq!(order_ref, 13 + REF_BLOCK);
+        assert_eq!(reserved, Some(mark("20250102", 12 + 2 * REF_BLOCK)));
+    }
+
+    #[test]
+    fn high_water_marks_are_kept_per_account() {
+        let dir = std::env::temp_dir().join(format!("order_ref_test_{}", std::process::id()));
+        save_mark(&dir, &account_key("9999", "a"), mark("20250102", 7)).unwrap();
+        save_mark(&dir, &account_key("9999", "b"), mark("20250102", 3)).unwrap();
+        save_mark(&dir, &account_key("9999", "a"), mark("20250102", 8)).unwrap();
+
+        let marks = load_marks(&dir);
+        assert_eq!(marks["9999:a"], mark("20250102", 8));
+        assert_eq!(marks["9999:b"], mark("20250102", 3));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,836 @@
+// 报单管理：按 (FrontID, SessionID, OrderRef) 和 (ExchangeID, OrderSysID) 记录本交易日的报单与成交，
+// 由交易事件泵根据 OnRspOrderInsert / OnRtnOrder / OnRtnTrade / OnErrRtnOrderInsert 更新，
+// 并把报单确认、撤单结果通知给等待中的 insert_order / cancel_order
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use tokio::sync::oneshot;
+
+use tauri_app_vue_lib::trader_api::CThostFtdcTraderSpiOutput;
+use tauri_app_vue_lib::{
+    char_flag_to_string, gb18030_cstr_to_str_i8, CThostFtdcInputOrderField,
+    CThostFtdcRspInfoField, TThostFtdcOrderRefType, THOST_FTDC_OSS_Accepted, THOST_FTDC_OSS_InsertRejected,
+    THOST_FTDC_OST_AllTraded,
+    THOST_FTDC_OST_Canceled, THOST_FTDC_OST_NoTradeNotQueueing, THOST_FTDC_OST_NotTouched,
+    THOST_FTDC_OST_PartTradedNotQueueing, THOST_FTDC_OST_Unknown,
+};
+
+use crate::ctp_commands::TRADER_SESSION_INFO;
+use crate::trader_events::{OrderInfo, TradeInfo};
+
+/// 等待交易所确认报单的最长时间
+pub const ORDER_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 报单确认或撤单的结果：成功时为最新报单，被拒时为原因
+pub type AckResult = Result<OrderInfo, String>;
+
+type Waiters = HashMap<(String, OrderKey), oneshot::Sender<AckResult>>;
+
+/// 报单在柜台的唯一标识
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OrderKey {
+    pub front_id: i32,
+    pub session_id: i32,
+    pub order_ref: String,
+}
+
+impl OrderKey {
+    pub fn of(order: &OrderInfo) -> Self {
+        OrderKey {
+            front_id: order.front_id,
+            session_id: order.session_id,
+            order_ref: order.order_ref.clone(),
+        }
+    }
+
+    // 撤单回报里的 OrderRef 是定长字符数组
+    fn from_ctp(front_id: i32, session_id: i32, order_ref: &TThostFtdcOrderRefType) -> Self {
+        OrderKey {
+            front_id,
+            session_id,
+            order_ref: gb18030_cstr_to_str_i8(order_ref).trim().to_string(),
+        }
+    }
+}
+
+/// 报单状态是否已不会再变化（全部成交、已撤单或不在队列中）
+pub fn is_final_status(order_status: &str) -> bool {
+    [
+        THOST_FTDC_OST_AllTraded,
+        THOST_FTDC_OST_PartTradedNotQueueing,
+        THOST_FTDC_OST_NoTradeNotQueueing,
+        THOST_FTDC_OST_Canceled,
+    ]
+    .iter()
+    .any(|status| order_status == char_flag_to_string(*status as i8))
+}
+
+/// 柜台条件单是否尚未触发
+pub fn is_pending_trigger(order_status: &str) -> bool {
+    order_status == char_flag_to_string(THOST_FTDC_OST_NotTouched as i8)
+}
+
+/// 报单的一次状态变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderStatusEntry {
+    pub order_status: String,
+    pub order_submit_status: String,
+    pub status_msg: String,
+    pub volume_traded: i32,
+    /// 本地收到回报的时间（毫秒时间戳）
+    pub at: i64,
+}
+
+/// list_orders / get_order 返回的报单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManagedOrder {
+    pub order: OrderInfo,
+    pub filled_volume: i32,
+    /// 按成交回报计算的成交均价，尚无成交时为 0
+    pub avg_fill_price: f64,
+    pub trade_ids: Vec<String>,
+    pub status_history: Vec<OrderStatusEntry>,
+    /// 柜台条件单尚未触发
+    pub pending_trigger: bool,
+    /// 条件单触发后报单失败的原因（OnRtnErrorConditionalOrder）
+    pub trigger_error: Option<String>,
+    #[serde(skip)]
+    fill_turnover: f64,
+    #[serde(skip)]
+    fill_volume_from_trades: i32,
+}
+
+impl ManagedOrder {
+    fn new(order: OrderInfo) -> Self {
+        ManagedOrder {
+            order,
+            filled_volume: 0,
+            avg_fill_price: 0.0,
+            trade_ids: Vec::new(),
+            status_history: Vec::new(),
+            pending_trigger: false,
+            trigger_error: None,
+            fill_turnover: 0.0,
+            fill_volume_from_trades: 0,
+        }
+    }
+
+    /// 是否仍可能成交或被撤单
+    pub fn is_active(&self) -> bool {
+        !is_final_status(&self.order.order_status)
+    }
+
+    // 状态或提交状态有变化时追加一条历史
+    fn record_status(&mut self, at: i64) {
+        let changed = self.status_history.last().is_none_or(|last| {
+            last.order_status != self.order.order_status
+                || last.order_submit_status != self.order.order_submit_status
+                || last.volume_traded != self.order.volume_traded
+        });
+        if changed {
+            self.status_history.push(OrderStatusEntry {
+                order_status: self.order.order_status.clone(),
+                order_submit_status: self.order.order_submit_status.clone(),
+                status_msg: self.order.status_msg.clone(),
+                volume_traded: self.order.volume_traded,
+                at,
+            });
+        }
+    }
+
+    fn apply_trade(&mut self, trade: &TradeInfo) {
+        self.trade_ids.push(trade.trade_id.clone());
+        self.fill_turnover += trade.price * trade.volume as f64;
+        self.fill_volume_from_trades += trade.volume;
+        self.refresh_fill();
+    }
+
+    // 成交数量以报单回报和成交回报中较大的为准，两者到达顺序不固定
+    fn refresh_fill(&mut self) {
+        self.filled_volume = self.order.volume_traded.max(self.fill_volume_from_trades);
+        self.avg_fill_price = if self.fill_volume_from_trades > 0 {
+            self.fill_turnover / self.fill_volume_from_trades as f64
+        } else {
+            0.0
+        };
+    }
+}
+
+/// 根据报单录入请求构造尚未收到 OnRtnOrder 的报单
+pub fn order_from_input(input: &CThostFtdcInputOrderField, front_id: i32, session_id: i32) -> OrderInfo {
+    OrderInfo {
+        broker_id: gb18030_cstr_to_str_i8(&input.BrokerID).to_string(),
+        investor_id: gb18030_cstr_to_str_i8(&input.InvestorID).to_string(),
+        order_ref: gb18030_cstr_to_str_i8(&input.OrderRef).trim().to_string(),
+        user_id: gb18030_cstr_to_str_i8(&input.UserID).to_string(),
+        instrument_id: gb18030_cstr_to_str_i8(&input.InstrumentID).to_string(),
+        order_price_type: char_flag_to_string(input.OrderPriceType),
+        direction: char_flag_to_string(input.Direction),
+        combine_offset_flag: gb18030_cstr_to_str_i8(&input.CombOffsetFlag).to_string(),
+        combine_hedge_flag: gb18030_cstr_to_str_i8(&input.CombHedgeFlag).to_string(),
+        limit_price: input.LimitPrice,
+        volume_total_original: input.VolumeTotalOriginal,
+        time_condition: char_flag_to_string(input.TimeCondition),
+        gtd_date: gb18030_cstr_to_str_i8(&input.GTDDate).to_string(),
+        volume_condition: char_flag_to_string(input.VolumeCondition),
+        min_volume: input.MinVolume,
+        contingent_condition: char_flag_to_string(input.ContingentCondition),
+        stop_price: input.StopPrice,
+        force_close_reason: char_flag_to_string(input.ForceCloseReason),
+        is_auto_suspend: input.IsAutoSuspend,
+        business_unit: gb18030_cstr_to_str_i8(&input.BusinessUnit).to_string(),
+        request_id: input.RequestID,
+        exchange_id: gb18030_cstr_to_str_i8(&input.ExchangeID).to_string(),
+        order_status: char_flag_to_string(THOST_FTDC_OST_Unknown as i8),
+        volume_total: input.VolumeTotalOriginal,
+        front_id,
+        session_id,
+        invest_unit_id: gb18030_cstr_to_str_i8(&input.InvestUnitID).to_string(),
+        account_id: gb18030_cstr_to_str_i8(&input.AccountID).to_string(),
+        currency_id: gb18030_cstr_to_str_i8(&input.CurrencyID).to_string(),
+        ..Default::default()
+    }
+}
+
+/// 单个交易会话当日的报单簿
+#[derive(Default)]
+pub struct OrderBook {
+    trading_day: String,
+    // 按首次出现的顺序保存
+    orders: Vec<ManagedOrder>,
+    by_key: HashMap<OrderKey, usize>,
+    by_sys_id: HashMap<(String, String), usize>,
+    trades: Vec<TradeInfo>,
+    seen_trades: HashSet<(String, String, String)>,
+    // 先于对应报单到达的成交，等报单有了 OrderSysID 再归集
+    orphan_trades: Vec<TradeInfo>,
+}
+
+impl OrderBook {
+    /// 登录后交易日变化时清空上一交易日的记录
+    pub fn start_trading_day(&mut self, trading_day: &str) {
+        if self.trading_day != trading_day {
+            *self = OrderBook {
+                trading_day: trading_day.to_string(),
+                ..Default::default()
+            };
+        }
+    }
+
+    fn upsert(&mut self, order: OrderInfo, at: i64) -> usize {
+        let key = OrderKey::of(&order);
+        let index = match self.by_key.get(&key) {
+            Some(&index) => {
+                self.orders[index].order = order;
+                index
+            }
+            None => {
+                self.orders.push(ManagedOrder::new(order));
+                self.by_key.insert(key, self.orders.len() - 1);
+                self.orders.len() - 1
+            }
+        };
+        let managed = &mut self.orders[index];
+        managed.pending_trigger = is_pending_trigger(&managed.order.order_status);
+        managed.refresh_fill();
+        managed.record_status(at);
+        index
+    }
+
+    /// 报单已发出；回报可能先于这里到达，已有记录时不覆盖
+    pub fn on_submitted(&mut self, order: OrderInfo, at: i64) {
+        if !self.by_key.contains_key(&OrderKey::of(&order)) {
+            self.upsert(order, at);
+        }
+    }
+
+    pub fn on_rtn_order(&mut self, order: OrderInfo, at: i64) {
+        let sys_key = (order.exchange_id.clone(), order.order_sys_id.clone());
+        let index = self.upsert(order, at);
+        if !sys_key.1.is_empty() && !self.by_sys_id.contains_key(&sys_key) {
+            self.by_sys_id.insert(sys_key.clone(), index);
+            let (matched, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.orphan_trades)
+                .into_iter()
+                .partition(|t| t.exchange_id == sys_key.0 && t.order_sys_id == sys_key.1);
+            self.orphan_trades = rest;
+            for trade in &matched {
+                self.orders[index].apply_trade(trade);
+            }
+        }
+    }
+
+    pub fn on_rtn_trade(&mut self, trade: TradeInfo) {
+        // 私有流重传时同一笔成交会再次推送
+        let id = (
+            trade.exchange_id.clone(),
+            trade.trade_id.clone(),
+            trade.direction.clone(),
+        );
+        if !self.seen_trades.insert(id) {
+            return;
+        }
+        let sys_key = (trade.exchange_id.clone(), trade.order_sys_id.clone());
+        match self.by_sys_id.get(&sys_key) {
+            Some(&index) => self.orders[index].apply_trade(&trade),
+            None => self.orphan_trades.push(trade.clone()),
+        }
+        self.trades.push(trade);
+    }
+
+    /// 报单被柜台或交易所拒绝，拒单不会再有 OnRtnOrder
+    pub fn on_insert_rejected(&mut self, mut order: OrderInfo, error: String, at: i64) {
+        if let Some(&index) = self.by_key.get(&OrderKey::of(&order)) {
+            order = self.orders[index].order.clone();
+        }
+        order.order_status = char_flag_to_string(THOST_FTDC_OST_Canceled as i8);
+        order.order_submit_status = char_flag_to_string(THOST_FTDC_OSS_InsertRejected as i8);
+        order.status_msg = error;
+        self.upsert(order, at);
+    }
+
+    /// 柜台条件单触发时报单失败，条件单随之结束
+    pub fn on_trigger_failed(&mut self, key: &OrderKey, error: String, at: i64) {
+        let Some(&index) = self.by_key.get(key) else {
+            return;
+        };
+        let mut order = self.orders[index].order.clone();
+        order.order_status = char_flag_to_string(THOST_FTDC_OST_Canceled as i8);
+        order.status_msg = error.clone();
+        self.upsert(order, at);
+        self.orders[index].trigger_error = Some(error);
+    }
+
+    pub fn orders(&self) -> &[ManagedOrder] {
+        &self.orders
+    }
+
+    pub fn get_by_key(&self, key: &OrderKey) -> Option<&ManagedOrder> {
+        self.by_key.get(key).map(|&index| &self.orders[index])
+    }
+
+    pub fn get_by_sys_id(&self, exchange_id: &str, order_sys_id: &str) -> Option<&ManagedOrder> {
+        self.by_sys_id
+            .get(&(exchange_id.to_string(), order_sys_id.to_string()))
+            .map(|&index| &self.orders[index])
+    }
+
+    /// 不限交易所按 OrderSysID 查找；多个交易所有同号报单时无法确定，返回 None
+    pub fn get_by_sys_id_any(&self, order_sys_id: &str) -> Option<&ManagedOrder> {
+        let mut matches = self.by_sys_id.iter().filter(|((_, id), _)| id == order_sys_id);
+        match (matches.next(), matches.next()) {
+            (Some((_, &index)), None) => Some(&self.orders[index]),
+            _ => None,
+        }
+    }
+
+    pub fn trades(&self) -> &[TradeInfo] {
+        &self.trades
+    }
+
+    /// 符合条件且仍可撤的报单
+    pub fn working_orders(&self, filter: &OrderFilter) -> Vec<OrderInfo> {
+        self.orders
+            .iter()
+            .filter(|managed| managed.is_active() && filter.matches(&managed.order))
+            .map(|managed| managed.order.clone())
+            .collect()
+    }
+}
+
+/// 批量撤单的筛选条件，未设置的条件不参与筛选
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderFilter {
+    pub instrument_id: Option<String>,
+    /// 买卖方向，"0" 买 / "1" 卖
+    pub direction: Option<String>,
+    /// 委托价下限（含）
+    pub price_min: Option<f64>,
+    /// 委托价上限（含）
+    pub price_max: Option<f64>,
+    pub investor_id: Option<String>,
+}
+
+impl OrderFilter {
+    pub fn matches(&self, order: &OrderInfo) -> bool {
+        let text = |value: &Option<String>, actual: &str| {
+            value.as_deref().filter(|v| !v.is_empty()).is_none_or(|v| v == actual)
+        };
+        text(&self.instrument_id, &order.instrument_id)
+            && text(&self.direction, &order.direction)
+            && text(&self.investor_id, &order.investor_id)
+            && self.price_min.is_none_or(|min| order.limit_price >= min)
+            && self.price_max.is_none_or(|max| order.limit_price <= max)
+    }
+}
+
+/// 按 OrderRef（可带 FrontID/SessionID）或 ExchangeID + OrderSysID 查找报单，
+/// ExchangeID 可省略，此时 OrderSysID 须在各交易所中唯一
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderLookup {
+    pub order_ref: Option<String>,
+    /// 省略时使用本会话登录得到的 FrontID
+    pub front_id: Option<i32>,
+    /// 省略时使用本会话登录得到的 SessionID
+    pub session_id: Option<i32>,
+    pub exchange_id: Option<String>,
+    pub order_sys_id: Option<String>,
+}
+
+impl OrderLookup {
+    pub fn find<'a>(&self, book: &'a OrderBook, own: (i32, i32)) -> Option<&'a ManagedOrder> {
+        let sys_id = self.order_sys_id.as_deref().map(str::trim).filter(|id| !id.is_empty());
+        if let Some(order_sys_id) = sys_id {
+            let exchange_id = self.exchange_id.as_deref().map(str::trim).filter(|id| !id.is_empty());
+            let found = match exchange_id {
+                Some(exchange_id) => book.get_by_sys_id(exchange_id, order_sys_id),
+                None => book.get_by_sys_id_any(order_sys_id),
+            };
+            // OrderSysID 没找到时再按 OrderRef 查找
+            if found.is_some() {
+                return found;
+            }
+        }
+        let order_ref = self.order_ref.as_deref().map(str::trim).filter(|r| !r.is_empty())?;
+        book.get_by_key(&OrderKey {
+            front_id: self.front_id.unwrap_or(own.0),
+            session_id: self.session_id.unwrap_or(own.1),
+            order_ref: order_ref.to_string(),
+        })
+    }
+}
+
+/// 根据报单回报判断报单是否已有结论：有 OrderSysID 或已被接受即为交易所确认
+pub fn ack_outcome(order: &OrderInfo) -> Option<AckResult> {
+    if order.order_submit_status == char_flag_to_string(THOST_FTDC_OSS_InsertRejected as i8) {
+        Some(Err(order.status_msg.clone()))
+    } else if !order.order_sys_id.is_empty()
+        || order.order_submit_status == char_flag_to_string(THOST_FTDC_OSS_Accepted as i8)
+    {
+        Some(Ok(order.clone()))
+    } else {
+        None
+    }
+}
+
+/// 根据报单回报判断撤单是否已有结论：已撤单为成功，其他终态说明报单已先结束
+pub fn cancel_outcome(order: &OrderInfo) -> Option<AckResult> {
+    if !is_final_status(&order.order_status) {
+        None
+    } else if order.order_status == char_flag_to_string(THOST_FTDC_OST_Canceled as i8) {
+        Some(Ok(order.clone()))
+    } else if order.order_status == char_flag_to_string(THOST_FTDC_OST_AllTraded as i8) {
+        Some(Err("报单已全部成交".to_string()))
+    } else {
+        Some(Err(format!("报单已结束: {}", order.status_msg)))
+    }
+}
+
+lazy_static::lazy_static! {
+    // 每个交易会话一本报单簿
+    static ref ORDER_BOOKS: Arc<Mutex<HashMap<String, OrderBook>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // 等待交易所确认的报单
+    static ref ACK_WAITERS: Arc<Mutex<Waiters>> = Arc::new(Mutex::new(HashMap::new()));
+    // 等待撤单结果的报单
+    static ref CANCEL_WAITERS: Arc<Mutex<Waiters>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+fn register(waiters: &Mutex<Waiters>, session_id: &str, key: OrderKey) -> oneshot::Receiver<AckResult> {
+    let (sender, receiver) = oneshot::channel();
+    waiters
+        .lock()
+        .unwrap()
+        .insert((session_id.to_string(), key), sender);
+    receiver
+}
+
+fn notify(waiters: &Mutex<Waiters>, session_id: &str, key: OrderKey, result: AckResult) {
+    let waiter = waiters.lock().unwrap().remove(&(session_id.to_string(), key));
+    if let Some(waiter) = waiter {
+        let _ = waiter.send(result);
+    }
+}
+
+/// 登记等待报单确认，必须在发出报单之前调用
+pub fn register_ack_waiter(session_id: &str, key: OrderKey) -> oneshot::Receiver<AckResult> {
+    register(&ACK_WAITERS, session_id, key)
+}
+
+/// 报单未发出或等待超时时撤销登记
+pub fn remove_ack_waiter(session_id: &str, key: &OrderKey) {
+    ACK_WAITERS
+        .lock()
+        .unwrap()
+        .remove(&(session_id.to_string(), key.clone()));
+}
+
+/// 登记等待撤单结果，必须在发出撤单之前调用
+pub fn register_cancel_waiter(session_id: &str, key: OrderKey) -> oneshot::Receiver<AckResult> {
+    register(&CANCEL_WAITERS, session_id, key)
+}
+
+/// 撤单未发出或等待超时时撤销登记
+pub fn remove_cancel_waiter(session_id: &str, key: &OrderKey) {
+    CANCEL_WAITERS
+        .lock()
+        .unwrap()
+        .remove(&(session_id.to_string(), key.clone()));
+}
+
+pub fn with_book<R>(session_id: &str, f: impl FnOnce(&mut OrderBook) -> R) -> R {
+    let mut books = ORDER_BOOKS.lock().unwrap();
+    f(books.entry(session_id.to_string()).or_default())
+}
+
+fn now_millis() -> i64 {
+    chrono::Local::now().timestamp_millis()
+}
+
+// 本会话登录得到的 FrontID / SessionID，拒单回报里没有这两个字段
+pub fn own_front_session(session_id: &str) -> (i32, i32) {
+    TRADER_SESSION_INFO
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .map(|info| (info.front_id, info.session_id))
+        .unwrap_or_default()
+}
+
+fn rejection_message(rsp: Option<&CThostFtdcRspInfoField>) -> String {
+    rsp.map(|r| {
+        format!(
+            "[{}] {}",
+            r.ErrorID,
+            gb18030_cstr_to_str_i8(&r.ErrorMsg).trim()
+        )
+    })
+    .unwrap_or_default()
+}
+
+/// 交易事件泵收到的每条消息都交给这里更新报单簿
+pub fn on_spi_output(session_id: &str, msg: &CThostFtdcTraderSpiOutput) {
+    use CThostFtdcTraderSpiOutput::*;
+    match msg {
+        OnRtnOrder(p) => {
+            if let Some(order) = p.p_order.as_ref() {
+                let order = OrderInfo::from(order);
+                if let Some(result) = ack_outcome(&order) {
+                    notify(&ACK_WAITERS, session_id, OrderKey::of(&order), result);
+                }
+                if let Some(result) = cancel_outcome(&order) {
+                    notify(&CANCEL_WAITERS, session_id, OrderKey::of(&order), result);
+                }
+                with_book(session_id, |book| book.on_rtn_order(order, now_millis()));
+            }
+        }
+        OnRtnTrade(p) => {
+            if let Some(trade) = p.p_trade.as_ref() {
+                let trade = TradeInfo::from(trade);
+                with_book(session_id, |book| book.on_rtn_trade(trade));
+            }
+        }
+        OnRspOrderInsert(p) => {
+            if let Some(input) = p.p_input_order.as_ref() {
+                reject(session_id, input, p.p_rsp_info.as_ref());
+            }
+        }
+        OnErrRtnOrderInsert(p) => {
+            if let Some(input) = p.p_input_order.as_ref() {
+                reject(session_id, input, p.p_rsp_info.as_ref());
+            }
+        }
+        // 撤单成功没有 OnRspOrderAction，只有失败时才有这两种回报
+        OnRspOrderAction(p) => {
+            let failed = p.p_rsp_info.as_ref().is_some_and(|r| r.ErrorID != 0);
+            if let (Some(action), true) = (p.p_input_order_action.as_ref(), failed) {
+                let key = OrderKey::from_ctp(action.FrontID, action.SessionID, &action.OrderRef);
+                let error = rejection_message(p.p_rsp_info.as_ref());
+                notify(&CANCEL_WAITERS, session_id, key, Err(error));
+            }
+        }
+        OnErrRtnOrderAction(p) => {
+            if let Some(action) = p.p_order_action.as_ref() {
+                let key = OrderKey::from_ctp(action.FrontID, action.SessionID, &action.OrderRef);
+                let error = rejection_message(p.p_rsp_info.as_ref());
+                notify(&CANCEL_WAITERS, session_id, key, Err(error));
+            }
+        }
+        OnRtnErrorConditionalOrder(p) => {
+            if let Some(failed) = p.p_error_conditional_order.as_ref() {
+                let key = OrderKey::from_ctp(failed.FrontID, failed.SessionID, &failed.OrderRef);
+                let error = format!(
+                    "[{}] {}",
+                    failed.ErrorID,
+                    gb18030_cstr_to_str_i8(&failed.ErrorMsg).trim()
+                );
+                with_book(session_id, |book| book.on_trigger_failed(&key, error, now_millis()));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn reject(session_id: &str, input: &CThostFtdcInputOrderField, rsp: Option<&CThostFtdcRspInfoField>) {
+    let (front_id, ctp_session_id) = own_front_session(session_id);
+    let order = order_from_input(input, front_id, ctp_session_id);
+    let error = rejection_message(rsp);
+    notify(&ACK_WAITERS, session_id, OrderKey::of(&order), Err(error.clone()));
+    with_book(session_id, |book| book.on_insert_rejected(order, error, now_millis()));
+}
+
+/// insert_order 发出报单后登记，确保在第一条回报之前就能查到
+pub fn record_submitted(session_id: &str, input: &CThostFtdcInputOrderField) {
+    let (front_id, ctp_session_id) = own_front_session(session_id);
+    let order = order_from_input(input, front_id, ctp_session_id);
+    with_book(session_id, |book| book.on_submitted(order, now_millis()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(order_ref: &str, order_sys_id: &str, status: &str, traded: i32) -> OrderInfo {
+        OrderInfo {
+            front_id: 1,
+            session_id: 100,
+            order_ref: order_ref.to_string(),
+            exchange_id: "SHFE".to_string(),
+            order_sys_id: order_sys_id.to_string(),
+            order_status: status.to_string(),
+            order_submit_status: "3".to_string(),
+            volume_total_original: 3,
+            volume_traded: traded,
+            ..Default::default()
+        }
+    }
+
+    fn trade(trade_id: &str, order_sys_id: &str, price: f64, volume: i32) -> TradeInfo {
+        TradeInfo {
+            exchange_id: "SHFE".to_string(),
+            trade_id: trade_id.to_string(),
+            order_sys_id: order_sys_id.to_string(),
+            direction: "0".to_string(),
+            price,
+            volume,
+            ..Default::default()
+        }
+    }
+
+    fn key(order_ref: &str) -> OrderKey {
+        OrderKey {
+            front_id: 1,
+            session_id: 100,
+            order_ref: order_ref.to_string(),
+        }
+    }
+
+    #[test]
+    fn tracks_status_history_and_average_fill_price() {
+        let mut book = OrderBook::default();
+        book.on_submitted(order("1", "", "a", 0), 1);
+        book.on_rtn_order(order("1", "", "a", 0), 2);
+        book.on_rtn_order(order("1", "123", "3", 0), 3);
+        book.on_rtn_trade(trade("t1", "123", 3500.0, 1));
+        book.on_rtn_order(order("1", "123", "1", 1), 4);
+        book.on_rtn_trade(trade("t2", "123", 3503.0, 2));
+        book.on_rtn_order(order("1", "123", "0", 3), 5);
+
+        let managed = book.get_by_key(&key("1")).unwrap();
+        assert_eq!(managed.filled_volume, 3);
+        assert_eq!(managed.avg_fill_price, 3502.0);
+        assert_eq!(managed.trade_ids, vec!["t1", "t2"]);
+        let statuses: Vec<&str> = managed
+            .status_history
+            .iter()
+            .map(|e| e.order_status.as_str())
+            .collect();
+        assert_eq!(statuses, vec!["a", "3", "1", "0"]);
+        assert!(!managed.is_active());
+        assert!(book.get_by_sys_id("SHFE", "123").is_some());
+    }
+
+    #[test]
+    fn trade_before_order_is_attached_later() {
+        let mut book = OrderBook::default();
+        book.on_rtn_trade(trade("t1", "9", 10.0, 1));
+        book.on_rtn_order(order("2", "9", "1", 1), 1);
+
+        let managed = book.get_by_key(&key("2")).unwrap();
+        assert_eq!(managed.avg_fill_price, 10.0);
+        assert_eq!(book.trades().len(), 1);
+    }
+
+    #[test]
+    fn replayed_trades_are_ignored() {
+        let mut book = OrderBook::default();
+        book.on_rtn_order(order("3", "7", "3", 0), 1);
+        book.on_rtn_trade(trade("t1", "7", 10.0, 1));
+        book.on_rtn_trade(trade("t1", "7", 10.0, 1));
+
+        assert_eq!(book.trades().len(), 1);
+        assert_eq!(book.get_by_key(&key("3")).unwrap().filled_volume, 1);
+    }
+
+    #[test]
+    fn rejected_insert_becomes_a_final_order() {
+        let mut book = OrderBook::default();
+        book.on_submitted(order("4", "", "a", 0), 1);
+        book.on_insert_rejected(order("4", "", "a", 0), "[31] 资金不足".to_string(), 2);
+
+        let managed = book.get_by_key(&key("4")).unwrap();
+        assert!(!managed.is_active());
+        assert_eq!(managed.order.status_msg, "[31] 资金不足");
+        assert_eq!(managed.status_history.len(), 2);
+    }
+
+    #[test]
+    fn ack_requires_exchange_acceptance_or_rejection() {
+        let mut submitted = order("6", "", "a", 0);
+        submitted.order_submit_status = "0".to_string();
+        assert!(ack_outcome(&submitted).is_none());
+
+        assert!(ack_outcome(&order("6", "88", "3", 0)).unwrap().is_ok());
+
+        let mut rejected = order("6", "", "5", 0);
+        rejected.order_submit_status = "4".to_string();
+        rejected.status_msg = "价格超出涨跌停".to_string();
+        assert_eq!(ack_outcome(&rejected).unwrap().unwrap_err(), "价格超出涨跌停");
+    }
+
+    #[test]
+    fn cancel_succeeds_only_when_the_order_is_canceled() {
+        assert!(cancel_outcome(&order("8", "12", "3", 0)).is_none());
+        assert!(cancel_outcome(&order("8", "12", "1", 1)).is_none());
+        assert_eq!(cancel_outcome(&order("8", "12", "5", 1)).unwrap().unwrap().volume_traded, 1);
+        assert_eq!(
+            cancel_outcome(&order("8", "12", "0", 3)).unwrap().unwrap_err(),
+            "报单已全部成交"
+        );
+    }
+
+    #[test]
+    fn lookup_prefers_sys_id_and_defaults_to_own_session() {
+        let mut book = OrderBook::default();
+        book.on_rtn_order(order("9", "55", "3", 0), 1);
+
+        let by_ref = OrderLookup {
+            order_ref: Some(" 9 ".to_string()),
+            ..Default::default()
+        };
+        assert!(by_ref.find(&book, (1, 100)).is_some());
+        assert!(by_ref.find(&book, (2, 100)).is_none());
+
+        let by_sys_id = OrderLookup {
+            order_ref: Some("missing".to_string()),
+            exchange_id: Some("SHFE".to_string()),
+            order_sys_id: Some("55".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(by_sys_id.find(&book, (0, 0)).unwrap().order.order_ref, "9");
+    }
+
+    #[test]
+    fn lookup_by_sys_id_alone_searches_all_exchanges() {
+        let mut book = OrderBook::default();
+        book.on_rtn_order(order("9", "55", "3", 0), 1);
+
+        let sys_id_only = OrderLookup {
+            order_sys_id: Some("55".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(sys_id_only.find(&book, (0, 0)).unwrap().order.order_ref, "9");
+
+        // 两个交易所有同号报单时不猜测
+        let mut other = order("10", "55", "3", 0);
+        other.exchange_id = "DCE".to_string();
+        book.on_rtn_order(other, 2);
+        assert!(sys_id_only.find(&book, (0, 0)).is_none());
+
+        // OrderSysID 未命中时按 OrderRef 查找
+        let with_ref = OrderLookup {
+            order_ref: Some("9".to_string()),
+            order_sys_id: Some("55".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(with_ref.find(&book, (1, 100)).unwrap().order.order_ref, "9");
+    }
+
+    #[test]
+    fn working_orders_are_filtered_by_instrument_side_and_price() {
+        let mut book = OrderBook::default();
+        let mut buy = order("10", "1", "3", 0);
+        buy.instrument_id = "rb2505".to_string();
+        buy.direction = "0".to_string();
+        buy.limit_price = 3500.0;
+        let mut sell = buy.clone();
+        sell.order_ref = "11".to_string();
+        sell.order_sys_id = "2".to_string();
+        sell.direction = "1".to_string();
+        sell.limit_price = 3520.0;
+        let mut filled = buy.clone();
+        filled.order_ref = "12".to_string();
+        filled.order_sys_id = "3".to_string();
+        filled.order_status = "0".to_string();
+        for o in [buy, sell, filled] {
+            book.on_rtn_order(o, 1);
+        }
+
+        assert_eq!(book.working_orders(&OrderFilter::default()).len(), 2);
+        let buys = book.working_orders(&OrderFilter {
+            instrument_id: Some("rb2505".to_string()),
+            direction: Some("0".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(buys.len(), 1);
+        assert_eq!(buys[0].order_ref, "10");
+        let band = book.working_orders(&OrderFilter {
+            price_min: Some(3510.0),
+            price_max: Some(3530.0),
+            ..Default::default()
+        });
+        assert_eq!(band[0].order_ref, "11");
+        assert!(book
+            .working_orders(&OrderFilter {
+                instrument_id: Some("rb2510".to_string()),
+                ..Default::default()
+            })
+            .is_empty());
+    }
+
+    #[test]
+    fn submit_does_not_overwrite_an_earlier_return() {
+        let mut book = OrderBook::default();
+        book.on_rtn_order(order("7", "", "3", 0), 1);
+        book.on_submitted(order("7", "", "a", 0), 2);
+        assert_eq!(book.get_by_key(&key("7")).unwrap().order.order_status, "3");
+    }
+
+    #[test]
+    fn new_trading_day_clears_the_book() {
+        let mut book = OrderBook::default();
+        book.start_trading_day("20250102");
+        book.on_rtn_order(order("5", "1", "3", 0), 1);
+        book.start_trading_day("20250102");
+        assert_eq!(book.orders().len(), 1);
+        book.start_trading_day("20250103");
+        assert!(book.orders().is_empty());
+    }
+
+    #[test]
+    fn conditional_orders_wait_for_trigger_and_record_failures() {
+        let mut book = OrderBook::default();
+        book.on_rtn_order(order("8", "TJBD_1", "b", 0), 1);
+        let pending = book.get_by_key(&key("8")).unwrap();
+        assert!(pending.pending_trigger);
+        assert!(pending.is_active());
+
+        book.on_trigger_failed(&key("8"), "[31] 资金不足".to_string(), 2);
+        let failed = book.get_by_key(&key("8")).unwrap();
+        assert!(!failed.pending_trigger);
+        assert!(!failed.is_active());
+        assert_eq!(failed.trigger_error.as_deref(), Some("[31] 资金不足"));
+    }
+}
@@ -0,0 +1,213 @@
+// 预埋单：开盘前（例如夜盘 21:00 之前）把报单和撤单存到柜台，开盘后由柜台自动发出。
+// 这里负责预埋报单/预埋撤单结构体与前端结构之间的转换
+use serde::{Deserialize, Serialize};
+
+use tauri_app_vue_lib::{
+    char_flag_to_string, gb18030_cstr_to_str_i8, CThostFtdcParkedOrderActionField, CThostFtdcParkedOrderField,
+    THOST_FTDC_AF_Delete, THOST_FTDC_FCC_NotForceClose, THOST_FTDC_PAOS_Deleted, THOST_FTDC_PAOS_NotSend,
+    THOST_FTDC_PAOS_Send,
+};
+
+use crate::ctp_commands::{copy_str_to_ctp_array, CtpAccountConfig, OrderRequest};
+use crate::ctp_flags::{ContingentCondition, HedgeFlag, OffsetFlag, TimeCondition, VolumeCondition};
+use crate::trader_events::OrderInfo;
+
+/// 预埋单状态的中文说明：未发送、已发送、已删除
+pub fn status_text(status: &str) -> &'static str {
+    if status == char_flag_to_string(THOST_FTDC_PAOS_NotSend as i8) {
+        "未发送"
+    } else if status == char_flag_to_string(THOST_FTDC_PAOS_Send as i8) {
+        "已发送"
+    } else if status == char_flag_to_string(THOST_FTDC_PAOS_Deleted as i8) {
+        "已删除"
+    } else {
+        "未知"
+    }
+}
+
+/// 预埋报单，字段与 types/ctp.ts 中的 ParkedOrderInfo 对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParkedOrderInfo {
+    pub parked_order_id: String,
+    pub instrument_id: String,
+    pub exchange_id: String,
+    pub direction: String,
+    pub combine_offset_flag: String,
+    pub combine_hedge_flag: String,
+    pub order_price_type: String,
+    pub limit_price: f64,
+    pub volume_total_original: i32,
+    pub time_condition: String,
+    pub volume_condition: String,
+    pub contingent_condition: String,
+    pub stop_price: f64,
+    pub status: String,
+    pub status_text: String,
+    pub error_id: i32,
+    pub error_msg: String,
+}
+
+impl From<&CThostFtdcParkedOrderField> for ParkedOrderInfo {
+    fn from(p: &CThostFtdcParkedOrderField) -> Self {
+        let status = char_flag_to_string(p.Status);
+        ParkedOrderInfo {
+            parked_order_id: gb18030_cstr_to_str_i8(&p.ParkedOrderID).trim().to_string(),
+            instrument_id: gb18030_cstr_to_str_i8(&p.InstrumentID).to_string(),
+            exchange_id: gb18030_cstr_to_str_i8(&p.ExchangeID).to_string(),
+            direction: char_flag_to_string(p.Direction),
+            combine_offset_flag: gb18030_cstr_to_str_i8(&p.CombOffsetFlag).to_string(),
+            combine_hedge_flag: gb18030_cstr_to_str_i8(&p.CombHedgeFlag).to_string(),
+            order_price_type: char_flag_to_string(p.OrderPriceType),
+            limit_price: p.LimitPrice,
+            volume_total_original: p.VolumeTotalOriginal,
+            time_condition: char_flag_to_string(p.TimeCondition),
+            volume_condition: char_flag_to_string(p.VolumeCondition),
+            contingent_condition: char_flag_to_string(p.ContingentCondition),
+            stop_price: p.StopPrice,
+            status_text: status_text(&status).to_string(),
+            status,
+            error_id: p.ErrorID,
+            error_msg: gb18030_cstr_to_str_i8(&p.ErrorMsg).to_string(),
+        }
+    }
+}
+
+/// 预埋撤单，字段与 types/ctp.ts 中的 ParkedCancelInfo 对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParkedCancelInfo {
+    pub parked_order_action_id: String,
+    pub instrument_id: String,
+    pub exchange_id: String,
+    pub order_sys_id: String,
+    pub order_ref: String,
+    pub front_id: i32,
+    pub session_id: i32,
+    pub status: String,
+    pub status_text: String,
+    pub error_id: i32,
+    pub error_msg: String,
+}
+
+impl From<&CThostFtdcParkedOrderActionField> for ParkedCancelInfo {
+    fn from(a: &CThostFtdcParkedOrderActionField) -> Self {
+        let status = char_flag_to_string(a.Status);
+        ParkedCancelInfo {
+            parked_order_action_id: gb18030_cstr_to_str_i8(&a.ParkedOrderActionID).trim().to_string(),
+            instrument_id: gb18030_cstr_to_str_i8(&a.InstrumentID).to_string(),
+            exchange_id: gb18030_cstr_to_str_i8(&a.ExchangeID).to_string(),
+            order_sys_id: gb18030_cstr_to_str_i8(&a.OrderSysID).trim().to_string(),
+            order_ref: gb18030_cstr_to_str_i8(&a.OrderRef).trim().to_string(),
+            front_id: a.FrontID,
+            session_id: a.SessionID,
+            status_text: status_text(&status).to_string(),
+            status,
+            error_id: a.ErrorID,
+            error_msg: gb18030_cstr_to_str_i8(&a.ErrorMsg).to_string(),
+        }
+    }
+}
+
+/// 预埋报单和预埋撤单的查询结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParkedOrders {
+    pub orders: Vec<ParkedOrderInfo>,
+    pub cancels: Vec<ParkedCancelInfo>,
+}
+
+/// 按报单请求填写预埋报单；报单引用由柜台在开盘发出时分配
+pub fn parked_order_field(account: &CtpAccountConfig, order: &OrderRequest) -> Result<CThostFtdcParkedOrderField, String> {
+    if order.volume <= 0 {
+        return Err(format!("预埋单数量必须大于 0: {}", order.volume));
+    }
+    if order.contingent_condition.is_some_and(|c| c.needs_stop_price()) {
+        return Err("预埋单不支持条件单触发条件".to_string());
+    }
+
+    let mut field = CThostFtdcParkedOrderField::default();
+    copy_str_to_ctp_array(&account.broker_id, &mut field.BrokerID);
+    copy_str_to_ctp_array(&account.account, &mut field.InvestorID);
+    copy_str_to_ctp_array(&account.account, &mut field.UserID);
+    copy_str_to_ctp_array(&order.instrument_id, &mut field.InstrumentID);
+    field.Direction = order.direction.to_ctp();
+    field.OrderPriceType = order.order_type.to_ctp();
+    field.LimitPrice = order.price;
+    field.VolumeTotalOriginal = order.volume;
+    field.CombOffsetFlag[0] = order.offset_flag.unwrap_or(OffsetFlag::Open).to_ctp();
+    field.CombHedgeFlag[0] = order.hedge_flag.unwrap_or(HedgeFlag::Speculation).to_ctp();
+    field.TimeCondition = order.time_condition.unwrap_or(TimeCondition::Gfd).to_ctp();
+    field.VolumeCondition = order
+        .volume_condition
+        .unwrap_or(VolumeCondition::AnyVolume)
+        .to_ctp();
+    field.ContingentCondition = ContingentCondition::Immediately.to_ctp();
+    field.MinVolume = 1;
+    field.ForceCloseReason = THOST_FTDC_FCC_NotForceClose as i8;
+    field.IsAutoSuspend = 0;
+    field.UserForceClose = 0;
+    Ok(field)
+}
+
+/// 对报单簿中的一笔报单填写预埋撤单
+pub fn parked_cancel_field(account: &CtpAccountConfig, target: &OrderInfo) -> CThostFtdcParkedOrderActionField {
+    let mut field = CThostFtdcParkedOrderActionField::default();
+    copy_str_to_ctp_array(&account.broker_id, &mut field.BrokerID);
+    copy_str_to_ctp_array(&account.account, &mut field.InvestorID);
+    copy_str_to_ctp_array(&account.account, &mut field.UserID);
+    copy_str_to_ctp_array(&target.instrument_id, &mut field.InstrumentID);
+    copy_str_to_ctp_array(&target.order_ref, &mut field.OrderRef);
+    field.FrontID = target.front_id;
+    field.SessionID = target.session_id;
+    copy_str_to_ctp_array(&target.exchange_id, &mut field.ExchangeID);
+    copy_str_to_ctp_array(&target.order_sys_id, &mut field.OrderSysID);
+    field.ActionFlag = THOST_FTDC_AF_Delete as i8;
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctp_flags::Direction;
+
+    fn account() -> CtpAccountConfig {
+        CtpAccountConfig {
+            broker_id: "9999".to_string(),
+            account: "000001".to_string(),
+            password: String::new(),
+            trade_front: String::new(),
+            md_front: String::new(),
+            auth_code: String::new(),
+            user_product_info: String::new(),
+            app_id: String::new(),
+        }
+    }
+
+    fn order(volume: i32) -> OrderRequest {
+        OrderRequest {
+            offset_flag: Some(OffsetFlag::CloseToday),
+            ..OrderRequest::limit("rb2505", Direction::Sell, 3500.0, volume)
+        }
+    }
+
+    #[test]
+    fn parked_order_round_trips_to_info() {
+        let mut field = parked_order_field(&account(), &order(3)).unwrap();
+        copy_str_to_ctp_array("P0001", &mut field.ParkedOrderID);
+        field.Status = THOST_FTDC_PAOS_NotSend as i8;
+
+        let info = ParkedOrderInfo::from(&field);
+        assert_eq!(info.parked_order_id, "P0001");
+        assert_eq!(info.instrument_id, "rb2505");
+        assert_eq!(info.direction, "1");
+        assert_eq!(info.combine_offset_flag, "3");
+        assert_eq!(info.volume_total_original, 3);
+        assert_eq!(info.status_text, "未发送");
+    }
+
+    #[test]
+    fn rejects_invalid_parked_orders() {
+        assert!(parked_order_field(&account(), &order(0)).is_err());
+        let mut conditional = order(1);
+        conditional.contingent_condition = Some(ContingentCondition::Touch);
+        assert!(parked_order_field(&account(), &conditional).is_err());
+    }
+}
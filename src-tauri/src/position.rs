@@ -0,0 +1,290 @@
+// 持仓：CThostFtdcInvestorPositionField 到前端 PositionInfo 的转换，
+// 以及把上期所/能源中心分开返回的今仓、昨仓记录合并成按合约、方向的持仓汇总
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use tauri_app_vue_lib::{
+    char_flag_to_string, gb18030_cstr_to_str_i8, CThostFtdcInvestorPositionField,
+    THOST_FTDC_PD_Long, THOST_FTDC_PD_Short, THOST_FTDC_PSD_History,
+};
+
+/// CTP 返回的原始持仓记录，字段与 types/ctp.ts 中的 PositionInfo 对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PositionInfo {
+    pub instrument_id: String,
+    pub broker_id: String,
+    pub investor_id: String,
+    pub posi_direction: String,
+    pub hedge_flag: String,
+    pub position_date: String,
+    pub yd_position: i32,
+    pub position: i32,
+    pub long_frozen: i32,
+    pub short_frozen: i32,
+    pub long_frozen_amount: f64,
+    pub short_frozen_amount: f64,
+    pub open_volume: i32,
+    pub close_volume: i32,
+    pub open_amount: f64,
+    pub close_amount: f64,
+    pub position_cost: f64,
+    pub pre_margin: f64,
+    pub use_margin: f64,
+    pub frozen_margin: f64,
+    pub frozen_cash: f64,
+    pub frozen_commission: f64,
+    pub cash_in: f64,
+    pub commission: f64,
+    pub close_profit: f64,
+    pub position_profit: f64,
+    pub pre_settlement_price: f64,
+    pub settlement_price: f64,
+    pub trading_day: String,
+    pub settlement_id: i32,
+    pub open_cost: f64,
+    pub exchange_margin: f64,
+    pub comb_position: i32,
+    pub comb_long_frozen: i32,
+    pub comb_short_frozen: i32,
+    pub close_profit_by_date: f64,
+    pub close_profit_by_trade: f64,
+    pub today_position: i32,
+    pub margin_rate_by_money: f64,
+    pub margin_rate_by_volume: f64,
+    pub strike_frozen: i32,
+    pub strike_frozen_amount: f64,
+    pub abandon_frozen: i32,
+    pub exchange_id: String,
+    pub yd_strike_frozen: i32,
+    pub invest_unit_id: String,
+    pub position_cost_offset: f64,
+    pub tas_position: i32,
+    pub tas_position_cost: f64,
+}
+
+impl From<&CThostFtdcInvestorPositionField> for PositionInfo {
+    fn from(p: &CThostFtdcInvestorPositionField) -> Self {
+        PositionInfo {
+            instrument_id: gb18030_cstr_to_str_i8(&p.InstrumentID).to_string(),
+            broker_id: gb18030_cstr_to_str_i8(&p.BrokerID).to_string(),
+            investor_id: gb18030_cstr_to_str_i8(&p.InvestorID).to_string(),
+            posi_direction: char_flag_to_string(p.PosiDirection),
+            hedge_flag: char_flag_to_string(p.HedgeFlag),
+            position_date: char_flag_to_string(p.PositionDate),
+            yd_position: p.YdPosition,
+            position: p.Position,
+            long_frozen: p.LongFrozen,
+            short_frozen: p.ShortFrozen,
+            long_frozen_amount: p.LongFrozenAmount,
+            short_frozen_amount: p.ShortFrozenAmount,
+            open_volume: p.OpenVolume,
+            close_volume: p.CloseVolume,
+            open_amount: p.OpenAmount,
+            close_amount: p.CloseAmount,
+            position_cost: p.PositionCost,
+            pre_margin: p.PreMargin,
+            use_margin: p.UseMargin,
+            frozen_margin: p.FrozenMargin,
+            frozen_cash: p.FrozenCash,
+            frozen_commission: p.FrozenCommission,
+            cash_in: p.CashIn,
+            commission: p.Commission,
+            close_profit: p.CloseProfit,
+            position_profit: p.PositionProfit,
+            pre_settlement_price: p.PreSettlementPrice,
+            settlement_price: p.SettlementPrice,
+            trading_day: gb18030_cstr_to_str_i8(&p.TradingDay).to_string(),
+            settlement_id: p.SettlementID,
+            open_cost: p.OpenCost,
+            exchange_margin: p.ExchangeMargin,
+            comb_position: p.CombPosition,
+            comb_long_frozen: p.CombLongFrozen,
+            comb_short_frozen: p.CombShortFrozen,
+            close_profit_by_date: p.CloseProfitByDate,
+            close_profit_by_trade: p.CloseProfitByTrade,
+            today_position: p.TodayPosition,
+            margin_rate_by_money: p.MarginRateByMoney,
+            margin_rate_by_volume: p.MarginRateByVolume,
+            strike_frozen: p.StrikeFrozen,
+            strike_frozen_amount: p.StrikeFrozenAmount,
+            abandon_frozen: p.AbandonFrozen,
+            exchange_id: gb18030_cstr_to_str_i8(&p.ExchangeID).to_string(),
+            yd_strike_frozen: p.YdStrikeFrozen,
+            invest_unit_id: gb18030_cstr_to_str_i8(&p.InvestUnitID).to_string(),
+            position_cost_offset: p.PositionCostOffset,
+            tas_position: p.TasPosition,
+            tas_position_cost: p.TasPositionCost,
+        }
+    }
+}
+
+/// 按合约、方向、投机套保标志合并后的持仓
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MergedPosition {
+    pub instrument_id: String,
+    pub exchange_id: String,
+    pub posi_direction: String,
+    pub hedge_flag: String,
+    /// 总持仓
+    pub position: i32,
+    /// 今仓
+    pub today_position: i32,
+    /// 昨仓（剩余）
+    pub yd_position: i32,
+    /// 已被平仓委托冻结的数量
+    pub frozen: i32,
+    /// 可平数量
+    pub closable: i32,
+    /// 可平今仓，只有今昨分开记录的交易所（上期所、能源中心）才能区分
+    pub today_closable: i32,
+    /// 可平昨仓，同上
+    pub yd_closable: i32,
+    pub position_cost: f64,
+    pub open_cost: f64,
+    pub use_margin: f64,
+    pub position_profit: f64,
+    pub close_profit: f64,
+}
+
+/// query_position_summary 的返回：原始记录和合并视图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionSummary {
+    pub positions: Vec<PositionInfo>,
+    pub merged: Vec<MergedPosition>,
+}
+
+// 平仓委托冻结在反方向上：多头持仓的冻结记在 ShortFrozen，空头记在 LongFrozen
+fn close_frozen(row: &PositionInfo) -> i32 {
+    if row.posi_direction == char_flag_to_string(THOST_FTDC_PD_Long as i8) {
+        row.short_frozen
+    } else if row.posi_direction == char_flag_to_string(THOST_FTDC_PD_Short as i8) {
+        row.long_frozen
+    } else {
+        0
+    }
+}
+
+/// 合并持仓记录
+///
+/// 上期所/能源中心的今仓、昨仓分两条返回（PositionDate 为 1 和 2），
+/// 其他交易所只有一条，今仓由 TodayPosition 给出、其余为昨仓。
+/// 总持仓为 0 的记录（当日已全部平仓）不出现在合并视图中。
+pub fn merge_positions(rows: &[PositionInfo]) -> Vec<MergedPosition> {
+    let history = char_flag_to_string(THOST_FTDC_PSD_History as i8);
+    let mut merged: BTreeMap<(String, String, String, String), MergedPosition> = BTreeMap::new();
+
+    for row in rows {
+        let key = (
+            row.instrument_id.clone(),
+            row.exchange_id.clone(),
+            row.posi_direction.clone(),
+            row.hedge_flag.clone(),
+        );
+        let entry = merged.entry(key).or_insert_with(|| MergedPosition {
+            instrument_id: row.instrument_id.clone(),
+            exchange_id: row.exchange_id.clone(),
+            posi_direction: row.posi_direction.clone(),
+            hedge_flag: row.hedge_flag.clone(),
+            ..Default::default()
+        });
+
+        let frozen = close_frozen(row);
+        let closable = (row.position - frozen).max(0);
+        entry.position += row.position;
+        entry.frozen += frozen;
+        entry.closable += closable;
+        if row.position_date == history {
+            entry.yd_position += row.position;
+            entry.yd_closable += closable;
+        } else {
+            let today = row.today_position.min(row.position);
+            entry.today_position += today;
+            entry.yd_position += row.position - today;
+            if row.position == today {
+                entry.today_closable += closable;
+            }
+        }
+        entry.position_cost += row.position_cost;
+        entry.open_cost += row.open_cost;
+        entry.use_margin += row.use_margin;
+        entry.position_profit += row.position_profit;
+        entry.close_profit += row.close_profit;
+    }
+
+    merged
+        .into_values()
+        .filter(|position| position.position != 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(
+        exchange_id: &str,
+        posi_direction: &str,
+        position_date: &str,
+        position: i32,
+        today_position: i32,
+    ) -> PositionInfo {
+        PositionInfo {
+            instrument_id: "rb2505".to_string(),
+            exchange_id: exchange_id.to_string(),
+            posi_direction: posi_direction.to_string(),
+            hedge_flag: "1".to_string(),
+            position_date: position_date.to_string(),
+            position,
+            today_position,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shfe_today_and_yesterday_rows_are_combined() {
+        let mut today = row("SHFE", "2", "1", 3, 3);
+        today.short_frozen = 1;
+        let yesterday = row("SHFE", "2", "2", 5, 0);
+
+        let merged = merge_positions(&[today, yesterday]);
+        assert_eq!(merged.len(), 1);
+        let long = &merged[0];
+        assert_eq!(long.position, 8);
+        assert_eq!(long.today_position, 3);
+        assert_eq!(long.yd_position, 5);
+        assert_eq!(long.frozen, 1);
+        assert_eq!(long.closable, 7);
+        assert_eq!(long.today_closable, 2);
+        assert_eq!(long.yd_closable, 5);
+    }
+
+    #[test]
+    fn single_row_exchanges_split_by_today_position() {
+        let mut short = row("DCE", "3", "1", 10, 4);
+        short.long_frozen = 2;
+
+        let merged = merge_positions(&[short]);
+        let short = &merged[0];
+        assert_eq!(short.today_position, 4);
+        assert_eq!(short.yd_position, 6);
+        assert_eq!(short.closable, 8);
+        // 今昨仓混在一条记录里，无法区分冻结的是哪一部分
+        assert_eq!(short.today_closable, 0);
+        assert_eq!(short.yd_closable, 0);
+    }
+
+    #[test]
+    fn directions_stay_separate_and_flat_rows_are_dropped() {
+        let merged = merge_positions(&[
+            row("SHFE", "2", "1", 1, 1),
+            row("SHFE", "3", "1", 2, 2),
+            row("SHFE", "3", "2", 0, 0),
+            row("SHFE", "2", "2", 0, 0),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].posi_direction, "2");
+        assert_eq!(merged[1].posi_direction, "3");
+        assert_eq!(merged[1].position, 2);
+    }
+}
@@ -0,0 +1,541 @@
+// 追价单：把一笔限价单挂在本方最优价或对手价上，每笔行情到达时若挂价偏离就撤单后按新价重报，
+// 直到全部成交、达到最大滑点或改价次数上限
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::ctp_commands::{await_cancel, get_next_request_id, send_cancel, send_order, OrderRequest};
+use crate::ctp_flags::{Direction, HedgeFlag, OffsetFlag};
+use crate::market_data::{self, MarketDataTick};
+use crate::order_store::{self, OrderKey};
+
+/// 追价单状态或成交变化时推送给前端
+pub const PRICE_CHASE_EVENT: &str = "price-chase-update";
+
+/// 未指定时的最大改价次数
+pub const DEFAULT_MAX_REPRICES: u32 = 20;
+
+/// 挂价位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChasePeg {
+    /// 本方最优价：买单挂买一，卖单挂卖一
+    #[default]
+    Best,
+    /// 对手价：买单挂卖一，卖单挂买一
+    Opposite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChaseStatus {
+    /// 跟随行情改价中
+    Working,
+    /// 已达到改价次数上限或无法继续撤单，最后一笔子单保留挂单、不再改价
+    Resting,
+    Filled,
+    Canceled,
+    /// 子单被拒或在追单之外被撤销
+    Failed,
+}
+
+/// 前端提交的追价单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChaseRequest {
+    pub instrument_id: String,
+    pub direction: Direction,
+    pub volume: i32,
+    pub offset_flag: Option<OffsetFlag>,
+    pub hedge_flag: Option<HedgeFlag>,
+    pub peg: Option<ChasePeg>,
+    /// 相对首笔子单价格允许追出的最大价差，不设则不限
+    pub max_slippage: Option<f64>,
+    pub max_reprices: Option<u32>,
+}
+
+/// 追价单下的一笔子单及其最新状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChaseChild {
+    pub key: OrderKey,
+    pub price: f64,
+    pub volume: i32,
+    pub order_status: String,
+    pub filled_volume: i32,
+}
+
+/// 一笔追价单（父单）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceChase {
+    pub chase_id: String,
+    pub instrument_id: String,
+    pub direction: Direction,
+    pub volume: i32,
+    pub offset_flag: Option<OffsetFlag>,
+    pub hedge_flag: Option<HedgeFlag>,
+    pub peg: ChasePeg,
+    pub max_slippage: Option<f64>,
+    pub max_reprices: u32,
+    /// 首笔子单的价格，滑点由此计算
+    pub initial_price: f64,
+    pub reprices: u32,
+    pub filled_volume: i32,
+    pub status: ChaseStatus,
+    pub created_at: i64,
+    pub children: Vec<ChaseChild>,
+    pub error: Option<String>,
+    // 正在撤单重报，期间不再改价
+    #[serde(skip)]
+    replacing: bool,
+}
+
+/// 按挂价位置从行情中取价，该档没有报价时返回 None
+pub fn peg_price(peg: ChasePeg, direction: Direction, tick: &MarketDataTick) -> Option<f64> {
+    let price = match (peg, direction) {
+        (ChasePeg::Best, Direction::Buy) | (ChasePeg::Opposite, Direction::Sell) => tick.bid_price1,
+        (ChasePeg::Best, Direction::Sell) | (ChasePeg::Opposite, Direction::Buy) => tick.ask_price1,
+    };
+    (price > 0.0).then_some(price)
+}
+
+impl PriceChase {
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, ChaseStatus::Working | ChaseStatus::Resting)
+    }
+
+    /// 按行情计算的挂价，买单不高于、卖单不低于首笔价格加减最大滑点
+    pub fn target_price(&self, tick: &MarketDataTick) -> Option<f64> {
+        let price = peg_price(self.peg, self.direction, tick)?;
+        Some(match (self.max_slippage, self.direction) {
+            (Some(slippage), Direction::Buy) => price.min(self.initial_price + slippage),
+            (Some(slippage), Direction::Sell) => price.max(self.initial_price - slippage),
+            (None, _) => price,
+        })
+    }
+
+    /// 这笔行情是否需要改价；需要时记一次改价并标记为撤单重报中，返回新价格。
+    /// 改价次数用完时转为 Resting
+    pub fn next_price(&mut self, tick: &MarketDataTick) -> Option<f64> {
+        if self.status != ChaseStatus::Working || self.replacing {
+            return None;
+        }
+        let child = self.children.last()?;
+        if order_store::is_final_status(&child.order_status) {
+            return None;
+        }
+        let target = self.target_price(tick)?;
+        if (target - child.price).abs() < 1e-9 {
+            return None;
+        }
+        if self.reprices >= self.max_reprices {
+            println!("⚠️ [WARN] Price chase {} reached {} reprices", self.chase_id, self.max_reprices);
+            self.status = ChaseStatus::Resting;
+            return None;
+        }
+        self.reprices += 1;
+        self.replacing = true;
+        Some(target)
+    }
+
+    /// 按子单状态汇总成交数量，判断是否全部成交或子单已在追单之外结束
+    pub fn settle(&mut self) {
+        self.filled_volume = self.children.iter().map(|child| child.filled_volume).sum();
+        if !self.is_active() {
+            return;
+        }
+        if self.filled_volume >= self.volume {
+            self.status = ChaseStatus::Filled;
+            return;
+        }
+        if self.replacing {
+            return;
+        }
+        if let Some(child) = self.children.last() {
+            if order_store::is_final_status(&child.order_status) {
+                self.status = ChaseStatus::Failed;
+                self.error = Some(format!(
+                    "子单 {} 已结束（状态 {}），追单停止",
+                    child.key.order_ref, child.order_status
+                ));
+            }
+        }
+    }
+
+    /// 从报单簿刷新子单状态
+    fn refresh(&mut self, session_id: &str) {
+        order_store::with_book(session_id, |book| {
+            for child in &mut self.children {
+                if let Some(managed) = book.get_by_key(&child.key) {
+                    child.order_status = managed.order.order_status.clone();
+                    child.filled_volume = managed.filled_volume;
+                }
+            }
+        });
+        self.settle();
+    }
+
+    fn child_order(&self, price: f64, volume: i32) -> OrderRequest {
+        OrderRequest::limit(&self.instrument_id, self.direction, price, volume)
+            .with_flags(self.offset_flag, self.hedge_flag)
+    }
+}
+
+lazy_static::lazy_static! {
+    // 每个交易会话当日的追价单
+    static ref CHASES: Arc<Mutex<HashMap<String, Vec<PriceChase>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+fn emit(app: &AppHandle, chase: &PriceChase) {
+    if let Err(e) = app.emit(PRICE_CHASE_EVENT, chase) {
+        println!("❌ [ERROR] Failed to emit {}: {}", PRICE_CHASE_EVENT, e);
+    }
+}
+
+// 发出一笔子单
+fn send_child(session_id: &str, order: &OrderRequest) -> Result<ChaseChild, String> {
+    let pending = send_order(session_id, order, false)?;
+    Ok(ChaseChild {
+        key: pending.key,
+        price: order.price,
+        volume: order.volume,
+        order_status: String::new(),
+        filled_volume: 0,
+    })
+}
+
+/// 按最新行情发出首笔子单并开始追价
+pub fn start(session_id: &str, request: ChaseRequest) -> Result<PriceChase, String> {
+    if request.volume <= 0 {
+        return Err(format!("追价单数量必须大于 0: {}", request.volume));
+    }
+    if request.max_slippage.is_some_and(|slippage| slippage < 0.0) {
+        return Err("最大滑点不能为负数".to_string());
+    }
+    let peg = request.peg.unwrap_or_default();
+    let tick = market_data::last_tick(&request.instrument_id)
+        .ok_or_else(|| format!("没有 {} 的行情，请先订阅", request.instrument_id))?;
+    let price = peg_price(peg, request.direction, &tick)
+        .ok_or_else(|| format!("{} 当前没有可用的挂价", request.instrument_id))?;
+
+    let created_at = chrono::Local::now().timestamp_millis();
+    let mut chase = PriceChase {
+        chase_id: format!("chase_{}_{}", created_at, get_next_request_id()),
+        instrument_id: request.instrument_id,
+        direction: request.direction,
+        volume: request.volume,
+        offset_flag: request.offset_flag,
+        hedge_flag: request.hedge_flag,
+        peg,
+        max_slippage: request.max_slippage,
+        max_reprices: request.max_reprices.unwrap_or(DEFAULT_MAX_REPRICES),
+        initial_price: price,
+        reprices: 0,
+        filled_volume: 0,
+        status: ChaseStatus::Working,
+        created_at,
+        children: Vec::new(),
+        error: None,
+        replacing: false,
+    };
+    let child = send_child(session_id, &chase.child_order(price, chase.volume))?;
+    chase.children.push(child);
+    CHASES
+        .lock()
+        .unwrap()
+        .entry(session_id.to_string())
+        .or_default()
+        .push(chase.clone());
+    Ok(chase)
+}
+
+/// 本会话的全部追价单，子单状态取自报单簿
+pub fn list(session_id: &str) -> Vec<PriceChase> {
+    let mut chases = CHASES.lock().unwrap();
+    let Some(session) = chases.get_mut(session_id) else {
+        return Vec::new();
+    };
+    for chase in session.iter_mut() {
+        chase.refresh(session_id);
+    }
+    session.clone()
+}
+
+/// 停止追价并撤销仍在挂单的子单，已成交部分保留；撤单未成功时恢复原来的状态
+pub async fn cancel(session_id: &str, chase_id: &str) -> Result<PriceChase, String> {
+    let (previous, working) = {
+        let mut chases = CHASES.lock().unwrap();
+        let chase = chases
+            .get_mut(session_id)
+            .and_then(|session| session.iter_mut().find(|c| c.chase_id == chase_id))
+            .ok_or_else(|| format!("未找到追价单 {}", chase_id))?;
+        if !chase.is_active() {
+            return Err(format!("追价单 {} 已结束", chase_id));
+        }
+        // 先改状态，改价任务不再重报
+        let previous = chase.status;
+        chase.status = ChaseStatus::Canceled;
+        // 撤单重报中的子单由改价任务撤掉，撤单完成后不会再重报
+        let working = if chase.replacing {
+            None
+        } else {
+            chase.children.last().map(|child| child.key.clone())
+        };
+        (previous, working)
+    };
+
+    let target = working.and_then(|key| {
+        order_store::with_book(session_id, |book| book.get_by_key(&key).map(|m| m.order.clone()))
+    });
+    if let Some(target) = target.filter(|o| !order_store::is_final_status(&o.order_status)) {
+        let deadline = tokio::time::Instant::now() + order_store::ORDER_ACK_TIMEOUT;
+        let canceled = match send_cancel(session_id, &target) {
+            Ok(pending) => await_cancel(session_id, pending, deadline).await.map(|_| ()),
+            Err(error) => Err(error),
+        };
+        if let Err(error) = canceled {
+            // 子单可能仍在挂单，追价单不能显示为已撤销
+            let mut chases = CHASES.lock().unwrap();
+            if let Some(chase) = chases
+                .get_mut(session_id)
+                .and_then(|session| session.iter_mut().find(|c| c.chase_id == chase_id))
+            {
+                if chase.status == ChaseStatus::Canceled {
+                    chase.status = previous;
+                }
+                chase.refresh(session_id);
+            }
+            return Err(error);
+        }
+    }
+
+    let mut chases = CHASES.lock().unwrap();
+    let chase = chases
+        .get_mut(session_id)
+        .and_then(|session| session.iter_mut().find(|c| c.chase_id == chase_id))
+        .ok_or_else(|| format!("未找到追价单 {}", chase_id))?;
+    chase.refresh(session_id);
+    Ok(chase.clone())
+}
+
+// 改价失败：撤单未能发出时原子单仍在挂单；撤单已确认后重报失败时已没有挂单
+enum ReplaceError {
+    CancelNotSent(String),
+    ResendFailed(String),
+}
+
+// 撤掉当前子单，撤单确认后按新价格报出剩余数量；追单已停止或已全部成交时不再重报
+async fn replace_child(session_id: &str, chase_id: &str, price: f64) -> Result<Option<ChaseChild>, ReplaceError> {
+    let key = CHASES
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .and_then(|session| session.iter().find(|c| c.chase_id == chase_id))
+        .and_then(|chase| chase.children.last().map(|child| child.key.clone()));
+    let Some(target) = key.and_then(|key| {
+        order_store::with_book(session_id, |book| book.get_by_key(&key).map(|m| m.order.clone()))
+    }) else {
+        return Ok(None);
+    };
+
+    let pending = send_cancel(session_id, &target).map_err(ReplaceError::CancelNotSent)?;
+    let deadline = tokio::time::Instant::now() + order_store::ORDER_ACK_TIMEOUT;
+    if let Err(error) = await_cancel(session_id, pending, deadline).await {
+        // 多数情况是子单已经成交，由下一次刷新判断
+        println!("⚠️ [WARN] Price chase {} cancel not confirmed: {}", chase_id, error);
+        return Ok(None);
+    }
+
+    let order = {
+        let mut chases = CHASES.lock().unwrap();
+        let Some(chase) = chases
+            .get_mut(session_id)
+            .and_then(|session| session.iter_mut().find(|c| c.chase_id == chase_id))
+        else {
+            return Ok(None);
+        };
+        chase.refresh(session_id);
+        let remaining = chase.volume - chase.filled_volume;
+        if chase.status != ChaseStatus::Working || remaining <= 0 {
+            return Ok(None);
+        }
+        chase.child_order(price, remaining)
+    };
+    println!(
+        "📤 [DEBUG] Price chase {} repricing to {} for {} lots",
+        chase_id, price, order.volume
+    );
+    send_child(session_id, &order)
+        .map(Some)
+        .map_err(ReplaceError::ResendFailed)
+}
+
+async fn reprice(app: AppHandle, session_id: String, chase_id: String, price: f64) {
+    let replaced = replace_child(&session_id, &chase_id, price).await;
+
+    let mut chases = CHASES.lock().unwrap();
+    let Some(chase) = chases
+        .get_mut(&session_id)
+        .and_then(|session| session.iter_mut().find(|c| c.chase_id == chase_id))
+    else {
+        return;
+    };
+    chase.replacing = false;
+    if let Ok(Some(child)) = &replaced {
+        chase.children.push(child.clone());
+    }
+    chase.refresh(&session_id);
+    if let Err(error) = replaced {
+        let (status, error) = match error {
+            // 原来的子单仍在挂单，停止改价并保留该挂单
+            ReplaceError::CancelNotSent(error) => (ChaseStatus::Resting, error),
+            // 原来的子单已撤掉，剩余数量没有挂单
+            ReplaceError::ResendFailed(error) => (ChaseStatus::Failed, error),
+        };
+        println!("❌ [ERROR] Price chase {} reprice failed: {}", chase_id, error);
+        if chase.is_active() {
+            chase.status = status;
+        }
+        chase.error = Some(error);
+    }
+    emit(&app, chase);
+}
+
+/// 每笔行情到达时调用：刷新同合约追价单的成交，挂价偏离时在后台撤单重报
+pub fn on_tick(app: &AppHandle, tick: &MarketDataTick) {
+    let mut jobs = Vec::new();
+    let mut changed = Vec::new();
+    {
+        let mut chases = CHASES.lock().unwrap();
+        for (session_id, session) in chases.iter_mut() {
+            for chase in session
+                .iter_mut()
+                .filter(|c| c.is_active() && c.instrument_id == tick.instrument_id)
+            {
+                let before = (chase.status, chase.filled_volume);
+                chase.refresh(session_id);
+                if let Some(price) = chase.next_price(tick) {
+                    jobs.push((session_id.clone(), chase.chase_id.clone(), price));
+                }
+                if (chase.status, chase.filled_volume) != before {
+                    changed.push(chase.clone());
+                }
+            }
+        }
+    }
+    for chase in &changed {
+        emit(app, chase);
+    }
+    for (session_id, chase_id, price) in jobs {
+        tauri::async_runtime::spawn(reprice(app.clone(), session_id, chase_id, price));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(bid: f64, ask: f64) -> MarketDataTick {
+        MarketDataTick {
+            instrument_id: "rb2505".to_string(),
+            bid_price1: bid,
+            ask_price1: ask,
+            ..Default::default()
+        }
+    }
+
+    fn chase(direction: Direction, price: f64) -> PriceChase {
+        PriceChase {
+            chase_id: "c1".to_string(),
+            instrument_id: "rb2505".to_string(),
+            direction,
+            volume: 5,
+            offset_flag: None,
+            hedge_flag: None,
+            peg: ChasePeg::Best,
+            max_slippage: Some(3.0),
+            max_reprices: 2,
+            initial_price: price,
+            reprices: 0,
+            filled_volume: 0,
+            status: ChaseStatus::Working,
+            created_at: 0,
+            children: vec![ChaseChild {
+                key: OrderKey {
+                    front_id: 1,
+                    session_id: 1,
+                    order_ref: "1".to_string(),
+                },
+                price,
+                volume: 5,
+                order_status: "3".to_string(),
+                filled_volume: 0,
+            }],
+            error: None,
+            replacing: false,
+        }
+    }
+
+    #[test]
+    fn pegs_to_own_or_opposite_side() {
+        let t = tick(3500.0, 3501.0);
+        assert_eq!(peg_price(ChasePeg::Best, Direction::Buy, &t), Some(3500.0));
+        assert_eq!(peg_price(ChasePeg::Best, Direction::Sell, &t), Some(3501.0));
+        assert_eq!(peg_price(ChasePeg::Opposite, Direction::Buy, &t), Some(3501.0));
+        assert_eq!(peg_price(ChasePeg::Opposite, Direction::Sell, &t), Some(3500.0));
+        assert_eq!(peg_price(ChasePeg::Best, Direction::Buy, &tick(0.0, 3501.0)), None);
+    }
+
+    #[test]
+    fn target_price_is_capped_by_max_slippage() {
+        let buy = chase(Direction::Buy, 3500.0);
+        assert_eq!(buy.target_price(&tick(3502.0, 3503.0)), Some(3502.0));
+        assert_eq!(buy.target_price(&tick(3510.0, 3511.0)), Some(3503.0));
+        let sell = chase(Direction::Sell, 3500.0);
+        assert_eq!(sell.target_price(&tick(3489.0, 3490.0)), Some(3497.0));
+    }
+
+    #[test]
+    fn reprices_until_the_limit_then_rests() {
+        let mut buy = chase(Direction::Buy, 3500.0);
+        assert_eq!(buy.next_price(&tick(3500.0, 3501.0)), None);
+        assert_eq!(buy.next_price(&tick(3501.0, 3502.0)), Some(3501.0));
+        // 撤单重报未完成时不再改价
+        assert_eq!(buy.next_price(&tick(3502.0, 3503.0)), None);
+
+        buy.replacing = false;
+        buy.children[0].price = 3501.0;
+        assert_eq!(buy.next_price(&tick(3502.0, 3503.0)), Some(3502.0));
+        buy.replacing = false;
+        buy.children[0].price = 3502.0;
+        assert_eq!(buy.next_price(&tick(3503.0, 3504.0)), None);
+        assert_eq!(buy.status, ChaseStatus::Resting);
+        assert_eq!(buy.reprices, 2);
+    }
+
+    #[test]
+    fn settles_fills_across_children() {
+        let mut buy = chase(Direction::Buy, 3500.0);
+        buy.children[0].order_status = "5".to_string();
+        buy.children[0].filled_volume = 2;
+        buy.replacing = true;
+        buy.settle();
+        assert_eq!(buy.filled_volume, 2);
+        assert_eq!(buy.status, ChaseStatus::Working);
+
+        let mut second = buy.children[0].clone();
+        second.order_status = "0".to_string();
+        second.filled_volume = 3;
+        buy.children.push(second);
+        buy.replacing = false;
+        buy.settle();
+        assert_eq!(buy.filled_volume, 5);
+        assert_eq!(buy.status, ChaseStatus::Filled);
+
+        let mut canceled = chase(Direction::Sell, 3500.0);
+        canceled.children[0].order_status = "5".to_string();
+        canceled.settle();
+        assert_eq!(canceled.status, ChaseStatus::Failed);
+    }
+}
@@ -0,0 +1,273 @@
+// 查询流控：CTP 要求同一时刻只有一个查询在途、每秒最多一个查询，
+// 这里按交易会话排队，依优先级逐个发出 ReqQry*，遇到 -2/-3 退避重试
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use tauri_app_vue_lib::trader_api::CThostFtdcTraderSpiOutput;
+use tauri_app_vue_lib::CThostFtdcTraderApi;
+
+use crate::rsp_correlator::{self, SendError};
+
+/// 两次查询之间的最小间隔
+pub const QUERY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 等待一个查询全部回报的默认时间
+pub const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 遇到 -2/-3 时最多重试的次数
+const MAX_RETRIES: u32 = 5;
+
+// 第一次重试前的额外等待，之后每次翻倍
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// 查询优先级，同一优先级按提交顺序执行
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+struct Ticket {
+    priority: QueryPriority,
+    seq: u64,
+    waker: oneshot::Sender<()>,
+}
+
+impl PartialEq for Ticket {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ticket {}
+
+impl PartialOrd for Ticket {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// 优先级高的先出堆，同优先级 seq 小的先出堆
+impl Ord for Ticket {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// 单个交易会话的查询队列
+#[derive(Default)]
+pub struct SessionQueue {
+    busy: bool,
+    next_seq: u64,
+    last_sent: Option<Instant>,
+    waiting: BinaryHeap<Ticket>,
+}
+
+impl SessionQueue {
+    /// 申请发送权；空闲时立即获得（返回 None），否则返回排队等待的接收端
+    pub fn acquire(&mut self, priority: QueryPriority) -> Option<oneshot::Receiver<()>> {
+        if !self.busy {
+            self.busy = true;
+            return None;
+        }
+        let (waker, receiver) = oneshot::channel();
+        self.waiting.push(Ticket {
+            priority,
+            seq: self.next_seq,
+            waker,
+        });
+        self.next_seq += 1;
+        Some(receiver)
+    }
+
+    /// 交出发送权给优先级最高的等待者，已放弃等待的跳过
+    pub fn release(&mut self) {
+        while let Some(ticket) = self.waiting.pop() {
+            if ticket.waker.send(()).is_ok() {
+                return;
+            }
+        }
+        self.busy = false;
+    }
+
+    /// 距离下一次允许发送还需要等待的时间
+    pub fn pacing_delay(&self, now: Instant) -> Duration {
+        self.last_sent
+            .map(|last| (last + QUERY_INTERVAL).saturating_duration_since(now))
+            .unwrap_or_default()
+    }
+
+    pub fn mark_sent(&mut self, now: Instant) {
+        self.last_sent = Some(now);
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref QUERY_QUEUES: Arc<Mutex<HashMap<String, SessionQueue>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+fn with_queue<R>(session_id: &str, f: impl FnOnce(&mut SessionQueue) -> R) -> R {
+    let mut queues = QUERY_QUEUES.lock().unwrap();
+    f(queues.entry(session_id.to_string()).or_default())
+}
+
+// 持有发送权期间存在，drop 时（包括调用方被取消）交给下一个查询
+struct QueryPermit {
+    session_id: String,
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        with_queue(&self.session_id, |queue| queue.release());
+    }
+}
+
+// 排队中的查询；等待期间被取消而发送权恰好已经交过来时，继续交给下一个
+struct QueuedTicket {
+    session_id: String,
+    receiver: oneshot::Receiver<()>,
+}
+
+impl Drop for QueuedTicket {
+    fn drop(&mut self) {
+        if self.receiver.try_recv().is_ok() {
+            with_queue(&self.session_id, |queue| queue.release());
+        }
+    }
+}
+
+async fn acquire(session_id: &str, priority: QueryPriority) -> QueryPermit {
+    if let Some(receiver) = with_queue(session_id, |queue| queue.acquire(priority)) {
+        let mut ticket = QueuedTicket {
+            session_id: session_id.to_string(),
+            receiver,
+        };
+        // 发送端只会在交出发送权时使用，不会被提前丢弃
+        let _ = (&mut ticket.receiver).await;
+    }
+    QueryPermit {
+        session_id: session_id.to_string(),
+    }
+}
+
+/// -2：未处理请求超过许可数，-3：每秒发送请求数超过许可数
+fn is_flow_control_error(code: i32) -> bool {
+    code == -2 || code == -3
+}
+
+/// 排队发出一个查询并在 timeout 内等待全部回报
+///
+/// `send` 可能因流控被多次调用，每次都会拿到新的 request_id。
+pub async fn query<T>(
+    session_id: &str,
+    priority: QueryPriority,
+    timeout: Duration,
+    mut send: impl FnMut(&mut CThostFtdcTraderApi, i32) -> i32,
+    extract: impl FnMut(CThostFtdcTraderSpiOutput) -> Option<T>,
+) -> Result<Vec<T>, String> {
+    let _permit = acquire(session_id, priority).await;
+
+    let mut retries = 0;
+    let sent = loop {
+        let delay = with_queue(session_id, |queue| queue.pacing_delay(Instant::now()));
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        let result = rsp_correlator::send(session_id, &mut send);
+        with_queue(session_id, |queue| queue.mark_sent(Instant::now()));
+        match result {
+            Ok(sent) => break sent,
+            Err(SendError::Rejected(code))
+                if is_flow_control_error(code) && retries < MAX_RETRIES =>
+            {
+                let backoff = RETRY_BACKOFF * 2u32.pow(retries);
+                retries += 1;
+                println!(
+                    "⚠️ [WARN] Query throttled by CTP (code {}), retry {}/{} in {:?}",
+                    code, retries, MAX_RETRIES, backoff
+                );
+                tokio::time::sleep(backoff).await;
+            }
+            Err(error) => return Err(error.to_string()),
+        }
+    };
+
+    sent.wait(timeout, extract).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_caller_gets_the_slot_immediately() {
+        let mut queue = SessionQueue::default();
+        assert!(queue.acquire(QueryPriority::Normal).is_none());
+        assert!(queue.acquire(QueryPriority::Normal).is_some());
+    }
+
+    #[test]
+    fn higher_priority_runs_first_then_fifo() {
+        let mut queue = SessionQueue::default();
+        assert!(queue.acquire(QueryPriority::Normal).is_none());
+        let mut low = queue.acquire(QueryPriority::Low).unwrap();
+        let mut normal = queue.acquire(QueryPriority::Normal).unwrap();
+        let mut high_1 = queue.acquire(QueryPriority::High).unwrap();
+        let mut high_2 = queue.acquire(QueryPriority::High).unwrap();
+
+        queue.release();
+        assert!(high_1.try_recv().is_ok());
+        assert!(high_2.try_recv().is_err());
+        queue.release();
+        assert!(high_2.try_recv().is_ok());
+        queue.release();
+        assert!(normal.try_recv().is_ok());
+        queue.release();
+        assert!(low.try_recv().is_ok());
+        queue.release();
+        assert!(queue.acquire(QueryPriority::Low).is_none());
+    }
+
+    #[test]
+    fn abandoned_waiters_are_skipped() {
+        let mut queue = SessionQueue::default();
+        assert!(queue.acquire(QueryPriority::Normal).is_none());
+        drop(queue.acquire(QueryPriority::High).unwrap());
+        let mut normal = queue.acquire(QueryPriority::Normal).unwrap();
+
+        queue.release();
+        assert!(normal.try_recv().is_ok());
+    }
+
+    #[test]
+    fn pacing_enforces_query_interval() {
+        let mut queue = SessionQueue::default();
+        let t0 = Instant::now();
+        assert_eq!(queue.pacing_delay(t0), Duration::ZERO);
+        queue.mark_sent(t0);
+        assert_eq!(
+            queue.pacing_delay(t0 + Duration::from_millis(400)),
+            Duration::from_millis(600)
+        );
+        assert_eq!(queue.pacing_delay(t0 + QUERY_INTERVAL), Duration::ZERO);
+    }
+
+    #[test]
+    fn only_minus_two_and_three_are_retried() {
+        assert!(is_flow_control_error(-2));
+        assert!(is_flow_control_error(-3));
+        assert!(!is_flow_control_error(-1));
+    }
+}
@@ -0,0 +1,580 @@
+// 报单前风控：每笔报单在调用 req_order_insert 之前都经过这里，
+// 任何一条规则不通过就带着规则编号拒单，报单不会发到 CTP
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use tauri_app_vue_lib::{char_flag_to_string, THOST_FTDC_PD_Long, THOST_FTDC_PD_Short};
+
+use crate::ctp_commands::OrderRequest;
+use crate::ctp_flags::{Direction, OrderPriceType};
+use crate::instruments::{self, InstrumentInfo};
+use crate::market_data::{self, MarketDataTick};
+use crate::order_store;
+use crate::position::PositionInfo;
+use crate::trader_events::TradeInfo;
+
+// 报单频率的统计窗口
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// 风控规则编号，出现在拒单信息中
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskRule {
+    MaxOrderVolume,
+    PriceLimit,
+    FatFinger,
+    MaxNetPosition,
+    MaxOpenOrders,
+    MaxOrderRate,
+}
+
+impl fmt::Display for RiskRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = match self {
+            RiskRule::MaxOrderVolume => "max_order_volume",
+            RiskRule::PriceLimit => "price_limit",
+            RiskRule::FatFinger => "fat_finger",
+            RiskRule::MaxNetPosition => "max_net_position",
+            RiskRule::MaxOpenOrders => "max_open_orders",
+            RiskRule::MaxOrderRate => "max_order_rate",
+        };
+        f.write_str(id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskRejection {
+    pub rule: RiskRule,
+    pub message: String,
+}
+
+impl fmt::Display for RiskRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "风控拒单 [{}]: {}", self.rule, self.message)
+    }
+}
+
+fn reject(rule: RiskRule, message: String) -> Result<(), RiskRejection> {
+    Err(RiskRejection { rule, message })
+}
+
+/// 风控参数，未设置的限制不检查
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    pub enabled: bool,
+    /// 单笔数量上限，在交易所的单笔上限之外另加的限制
+    pub max_order_volume: Option<i32>,
+    /// 限价不得超出最新行情的涨跌停价
+    pub check_price_limits: bool,
+    /// 限价偏离最新价的最大比例，例如 0.05 表示 5%
+    pub fat_finger_ratio: Option<f64>,
+    /// 每个品种的最大净持仓（手）
+    pub max_net_position: Option<i32>,
+    /// 按品种覆盖 max_net_position
+    pub product_net_position: HashMap<String, i32>,
+    /// 最多同时挂着的未结束报单数
+    pub max_open_orders: Option<usize>,
+    /// 每秒最多发出的报单数
+    pub max_orders_per_second: Option<usize>,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        RiskConfig {
+            enabled: true,
+            max_order_volume: None,
+            check_price_limits: true,
+            fat_finger_ratio: Some(0.05),
+            max_net_position: None,
+            product_net_position: HashMap::new(),
+            max_open_orders: None,
+            max_orders_per_second: Some(10),
+        }
+    }
+}
+
+/// 检查一笔报单所需的行情和账户状态，缺少的数据对应的规则跳过
+#[derive(Debug, Default)]
+pub struct RiskContext {
+    pub instrument: Option<InstrumentInfo>,
+    pub last_tick: Option<MarketDataTick>,
+    /// 报单所属品种当前的净持仓（多头为正），未查询过持仓时为 None
+    pub net_position: Option<i32>,
+    /// 同品种与报单同方向、尚未成交的挂单数量（手）
+    pub working_volume: i32,
+    pub open_orders: usize,
+    /// 统计窗口内已发出的报单数
+    pub recent_orders: usize,
+}
+
+/// 依次检查各条规则，返回第一条不通过的规则
+pub fn evaluate(config: &RiskConfig, order: &OrderRequest, ctx: &RiskContext) -> Result<(), RiskRejection> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let is_limit = order.order_type == OrderPriceType::LimitPrice;
+
+    if order.volume <= 0 {
+        return reject(RiskRule::MaxOrderVolume, format!("报单数量必须大于 0: {}", order.volume));
+    }
+    let exchange_max = ctx.instrument.as_ref().map(|i| {
+        if is_limit {
+            i.max_limit_order_volume
+        } else {
+            i.max_market_order_volume
+        }
+    });
+    for max in [exchange_max.filter(|max| *max > 0), config.max_order_volume]
+        .into_iter()
+        .flatten()
+    {
+        if order.volume > max {
+            return reject(
+                RiskRule::MaxOrderVolume,
+                format!("{} 单笔数量 {} 超过上限 {}", order.instrument_id, order.volume, max),
+            );
+        }
+    }
+
+    // 价格类规则只适用于限价单
+    if let Some(tick) = ctx.last_tick.as_ref().filter(|_| is_limit) {
+        if config.check_price_limits {
+            if tick.upper_limit_price > 0.0 && order.price > tick.upper_limit_price {
+                return reject(
+                    RiskRule::PriceLimit,
+                    format!("价格 {} 高于涨停价 {}", order.price, tick.upper_limit_price),
+                );
+            }
+            if tick.lower_limit_price > 0.0 && order.price < tick.lower_limit_price {
+                return reject(
+                    RiskRule::PriceLimit,
+                    format!("价格 {} 低于跌停价 {}", order.price, tick.lower_limit_price),
+                );
+            }
+        }
+        if let Some(ratio) = config.fat_finger_ratio.filter(|_| tick.last_price > 0.0) {
+            let distance = (order.price - tick.last_price).abs() / tick.last_price;
+            if distance > ratio {
+                return reject(
+                    RiskRule::FatFinger,
+                    format!(
+                        "价格 {} 偏离最新价 {} 达 {:.2}%，超过 {:.2}%",
+                        order.price,
+                        tick.last_price,
+                        distance * 100.0,
+                        ratio * 100.0
+                    ),
+                );
+            }
+        }
+    }
+
+    // 净持仓按品种统计，没有合约信息时无法确定品种
+    let has_net_limit = config.max_net_position.is_some() || !config.product_net_position.is_empty();
+    if has_net_limit && ctx.instrument.is_none() {
+        return reject(
+            RiskRule::MaxNetPosition,
+            format!("未找到合约 {} 的信息，无法检查净持仓上限，请先加载合约列表", order.instrument_id),
+        );
+    }
+    let product_limit = ctx.instrument.as_ref().and_then(|i| {
+        config
+            .product_net_position
+            .get(&i.product_id)
+            .copied()
+            .or(config.max_net_position)
+    });
+    if let Some(limit) = product_limit {
+        let Some(net) = ctx.net_position else {
+            return reject(
+                RiskRule::MaxNetPosition,
+                format!("尚未取得 {} 的持仓，无法检查净持仓上限", order.instrument_id),
+            );
+        };
+        let sign = match order.direction {
+            Direction::Buy => 1,
+            Direction::Sell => -1,
+        };
+        // 同方向的挂单按全部成交计入
+        let expected = net + sign * ctx.working_volume;
+        let projected = expected + sign * order.volume;
+        // 只拦截会扩大敞口的报单，平仓方向的报单总是允许
+        if projected.abs() > limit && projected.abs() > expected.abs() {
+            return reject(
+                RiskRule::MaxNetPosition,
+                format!("成交后净持仓 {} 超过上限 {}", projected, limit),
+            );
+        }
+    }
+
+    if let Some(max) = config.max_open_orders {
+        if ctx.open_orders >= max {
+            return reject(
+                RiskRule::MaxOpenOrders,
+                format!("未结束报单已有 {} 笔，上限 {}", ctx.open_orders, max),
+            );
+        }
+    }
+
+    if let Some(max) = config.max_orders_per_second {
+        if ctx.recent_orders >= max {
+            return reject(
+                RiskRule::MaxOrderRate,
+                format!("每秒报单数已达上限 {}", max),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 持仓记录与之后的成交合计出的净持仓（多头为正），只统计 in_scope 为真的合约
+pub fn net_position(
+    rows: &[PositionInfo],
+    trades: &[TradeInfo],
+    in_scope: impl Fn(&str) -> bool,
+) -> i32 {
+    let long = char_flag_to_string(THOST_FTDC_PD_Long as i8);
+    let short = char_flag_to_string(THOST_FTDC_PD_Short as i8);
+    let held: i32 = rows
+        .iter()
+        .filter(|row| in_scope(&row.instrument_id))
+        .map(|row| {
+            if row.posi_direction == long {
+                row.position
+            } else if row.posi_direction == short {
+                -row.position
+            } else {
+                0
+            }
+        })
+        .sum();
+    // 买入（开多或平空）使净持仓增加，卖出使其减少
+    let traded: i32 = trades
+        .iter()
+        .filter(|trade| in_scope(&trade.instrument_id))
+        .map(|trade| {
+            if trade.direction == Direction::Buy.to_string() {
+                trade.volume
+            } else {
+                -trade.volume
+            }
+        })
+        .sum();
+    held + traded
+}
+
+// 最近一次持仓查询结果，以及当时报单簿里已有的成交笔数
+struct PositionSnapshot {
+    rows: Vec<PositionInfo>,
+    trade_count: usize,
+}
+
+lazy_static::lazy_static! {
+    static ref RISK_CONFIGS: Arc<Mutex<HashMap<String, RiskConfig>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    static ref POSITION_SNAPSHOTS: Arc<Mutex<HashMap<String, PositionSnapshot>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    static ref ORDER_TIMES: Arc<Mutex<HashMap<String, VecDeque<Instant>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+pub fn config(session_id: &str) -> RiskConfig {
+    RISK_CONFIGS
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub fn set_config(session_id: &str, config: RiskConfig) {
+    RISK_CONFIGS
+        .lock()
+        .unwrap()
+        .insert(session_id.to_string(), config);
+}
+
+/// 持仓查询成功后调用，作为净持仓检查的基准
+pub fn record_positions(session_id: &str, rows: &[PositionInfo]) {
+    let trade_count = order_store::with_book(session_id, |book| book.trades().len());
+    POSITION_SNAPSHOTS.lock().unwrap().insert(
+        session_id.to_string(),
+        PositionSnapshot {
+            rows: rows.to_vec(),
+            trade_count,
+        },
+    );
+}
+
+fn product_net_position(session_id: &str, product_id: &str) -> Option<i32> {
+    let snapshots = POSITION_SNAPSHOTS.lock().unwrap();
+    let snapshot = snapshots.get(session_id)?;
+    let trades = order_store::with_book(session_id, |book| {
+        book.trades().get(snapshot.trade_count..).unwrap_or_default().to_vec()
+    });
+    let in_product = |instrument_id: &str| {
+        instruments::find(instrument_id).is_some_and(|i| i.product_id == product_id)
+    };
+    Some(net_position(&snapshot.rows, &trades, in_product))
+}
+
+// 同品种与报单同方向的未结束报单中尚未成交的数量
+fn product_working_volume(session_id: &str, product_id: &str, direction: Direction) -> i32 {
+    let direction = direction.to_string();
+    order_store::with_book(session_id, |book| {
+        book.orders()
+            .iter()
+            .filter(|o| o.is_active() && o.order.direction == direction)
+            .filter(|o| instruments::find(&o.order.instrument_id).is_some_and(|i| i.product_id == product_id))
+            .map(|o| (o.order.volume_total_original - o.order.volume_traded).max(0))
+            .sum()
+    })
+}
+
+// 清掉统计窗口之外的发送记录
+fn prune(times: &mut VecDeque<Instant>, now: Instant) {
+    while times
+        .front()
+        .is_some_and(|sent| now.duration_since(*sent) >= RATE_WINDOW)
+    {
+        times.pop_front();
+    }
+}
+
+/// 风控通过后占用的报单频率名额，报单最终没有发出时归还
+pub struct RateSlot {
+    // 风控关闭或不计频率时为 None
+    reserved: Option<(String, Instant)>,
+}
+
+impl RateSlot {
+    pub fn release(self) {
+        let Some((session_id, at)) = self.reserved else {
+            return;
+        };
+        if let Some(times) = ORDER_TIMES.lock().unwrap().get_mut(&session_id) {
+            if let Some(index) = times.iter().position(|sent| *sent == at) {
+                times.remove(index);
+            }
+        }
+    }
+}
+
+/// 收集合约、行情、持仓和报单簿状态后检查报单，通过后占用一个报单频率名额
+pub fn check(session_id: &str, order: &OrderRequest) -> Result<RateSlot, RiskRejection> {
+    check_with_rate(session_id, order, true)
+}
+
+/// 与 check 相同但不检查报单频率，用于预埋单这类不会立即发到交易所的报单
+pub fn check_without_rate(session_id: &str, order: &OrderRequest) -> Result<(), RiskRejection> {
+    check_with_rate(session_id, order, false).map(|_| ())
+}
+
+// 检查通过且 count_rate 为真时立即占用一个报单频率名额，并发的报单不会同时通过频率检查
+fn check_with_rate(session_id: &str, order: &OrderRequest, count_rate: bool) -> Result<RateSlot, RiskRejection> {
+    let config = config(session_id);
+    if !config.enabled {
+        return Ok(RateSlot { reserved: None });
+    }
+    let instrument = instruments::find(&order.instrument_id);
+    let net_position = instrument
+        .as_ref()
+        .and_then(|i| product_net_position(session_id, &i.product_id));
+    let working_volume = instrument
+        .as_ref()
+        .map(|i| product_working_volume(session_id, &i.product_id, order.direction))
+        .unwrap_or(0);
+    let mut ctx = RiskContext {
+        instrument,
+        last_tick: market_data::last_tick(&order.instrument_id),
+        net_position,
+        working_volume,
+        open_orders: order_store::with_book(session_id, |book| {
+            book.orders().iter().filter(|o| o.is_active()).count()
+        }),
+        recent_orders: 0,
+    };
+    if !count_rate {
+        return evaluate(&config, order, &ctx).map(|_| RateSlot { reserved: None });
+    }
+
+    let now = Instant::now();
+    let mut times = ORDER_TIMES.lock().unwrap();
+    let times = times.entry(session_id.to_string()).or_default();
+    prune(times, now);
+    ctx.recent_orders = times.len();
+    evaluate(&config, order, &ctx)?;
+    times.push_back(now);
+    Ok(RateSlot {
+        reserved: Some((session_id.to_string(), now)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(direction: Direction, price: f64, volume: i32) -> OrderRequest {
+        OrderRequest::limit("rb2505", direction, price, volume)
+    }
+
+    fn context() -> RiskContext {
+        RiskContext {
+            instrument: Some(InstrumentInfo {
+                instrument_id: "rb2505".to_string(),
+                product_id: "rb".to_string(),
+                max_limit_order_volume: 500,
+                max_market_order_volume: 30,
+                ..Default::default()
+            }),
+            last_tick: Some(MarketDataTick {
+                instrument_id: "rb2505".to_string(),
+                last_price: 3500.0,
+                upper_limit_price: 3745.0,
+                lower_limit_price: 3255.0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn rule(result: Result<(), RiskRejection>) -> RiskRule {
+        result.unwrap_err().rule
+    }
+
+    #[test]
+    fn volume_is_capped_by_exchange_and_config() {
+        let config = RiskConfig::default();
+        assert!(evaluate(&config, &order(Direction::Buy, 3500.0, 500), &context()).is_ok());
+        assert_eq!(
+            rule(evaluate(&config, &order(Direction::Buy, 3500.0, 501), &context())),
+            RiskRule::MaxOrderVolume
+        );
+        let mut market = order(Direction::Buy, 0.0, 31);
+        market.order_type = OrderPriceType::AnyPrice;
+        assert_eq!(rule(evaluate(&config, &market, &context())), RiskRule::MaxOrderVolume);
+
+        let config = RiskConfig {
+            max_order_volume: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            rule(evaluate(&config, &order(Direction::Buy, 3500.0, 11), &context())),
+            RiskRule::MaxOrderVolume
+        );
+    }
+
+    #[test]
+    fn price_must_stay_within_limits_and_near_last() {
+        let config = RiskConfig {
+            fat_finger_ratio: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            rule(evaluate(&config, &order(Direction::Buy, 3750.0, 1), &context())),
+            RiskRule::PriceLimit
+        );
+        assert!(evaluate(&config, &order(Direction::Buy, 3700.0, 1), &context()).is_ok());
+
+        let config = RiskConfig::default();
+        assert_eq!(
+            rule(evaluate(&config, &order(Direction::Buy, 3700.0, 1), &context())),
+            RiskRule::FatFinger
+        );
+        assert!(evaluate(&config, &order(Direction::Sell, 3400.0, 1), &context()).is_ok());
+    }
+
+    #[test]
+    fn net_position_limit_only_blocks_growing_exposure() {
+        let config = RiskConfig {
+            max_net_position: Some(10),
+            product_net_position: HashMap::from([("rb".to_string(), 5)]),
+            ..Default::default()
+        };
+        let mut ctx = context();
+        ctx.net_position = Some(4);
+        assert!(evaluate(&config, &order(Direction::Buy, 3500.0, 1), &ctx).is_ok());
+        assert_eq!(
+            rule(evaluate(&config, &order(Direction::Buy, 3500.0, 2), &ctx)),
+            RiskRule::MaxNetPosition
+        );
+        ctx.net_position = Some(8);
+        assert!(evaluate(&config, &order(Direction::Sell, 3500.0, 2), &ctx).is_ok());
+    }
+
+    #[test]
+    fn net_position_counts_working_orders_and_needs_a_snapshot() {
+        let config = RiskConfig {
+            max_net_position: Some(5),
+            ..Default::default()
+        };
+        let mut ctx = context();
+        assert_eq!(
+            rule(evaluate(&config, &order(Direction::Buy, 3500.0, 1), &ctx)),
+            RiskRule::MaxNetPosition
+        );
+
+        let no_catalogue = RiskContext {
+            net_position: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            rule(evaluate(&config, &order(Direction::Buy, 3500.0, 1), &no_catalogue)),
+            RiskRule::MaxNetPosition
+        );
+        assert!(evaluate(&RiskConfig::default(), &order(Direction::Buy, 3500.0, 1), &no_catalogue).is_ok());
+
+        ctx.net_position = Some(2);
+        ctx.working_volume = 2;
+        assert!(evaluate(&config, &order(Direction::Buy, 3500.0, 1), &ctx).is_ok());
+        assert_eq!(
+            rule(evaluate(&config, &order(Direction::Buy, 3500.0, 2), &ctx)),
+            RiskRule::MaxNetPosition
+        );
+    }
+
+    #[test]
+    fn open_orders_and_rate_are_limited() {
+        let config = RiskConfig {
+            max_open_orders: Some(3),
+            max_orders_per_second: Some(2),
+            ..Default::default()
+        };
+        let mut ctx = context();
+        ctx.open_orders = 3;
+        assert_eq!(
+            rule(evaluate(&config, &order(Direction::Buy, 3500.0, 1), &ctx)),
+            RiskRule::MaxOpenOrders
+        );
+        ctx.open_orders = 0;
+        ctx.recent_orders = 2;
+        let rejection = evaluate(&config, &order(Direction::Buy, 3500.0, 1), &ctx).unwrap_err();
+        assert_eq!(rejection.rule, RiskRule::MaxOrderRate);
+        assert!(rejection.to_string().starts_with("风控拒单 [max_order_rate]"));
+    }
+
+    #[test]
+    fn net_position_combines_rows_and_later_trades() {
+        let row = |instrument_id: &str, posi_direction: &str, position: i32| PositionInfo {
+            instrument_id: instrument_id.to_string(),
+            posi_direction: posi_direction.to_string(),
+            position,
+            ..Default::default()
+        };
+        let trade = |direction: &str, volume: i32| TradeInfo {
+            instrument_id: "rb2510".to_string(),
+            direction: direction.to_string(),
+            volume,
+            ..Default::default()
+        };
+        let rows = [row("rb2505", "2", 5), row("rb2510", "3", 2), row("ag2506", "2", 9)];
+        let trades = [trade("0", 3), trade("1", 1)];
+        let net = net_position(&rows, &trades, |id| id.starts_with("rb"));
+        assert_eq!(net, 5 - 2 + 3 - 1);
+    }
+}
@@ -0,0 +1,362 @@
+// 交易请求/应答关联：按 n_request_id 收集 OnRsp* 回报直到 b_is_last，
+// 让查询类命令可以直接 await 一个请求的全部结果
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use tauri_app_vue_lib::rsp_info_error;
+use tauri_app_vue_lib::trader_api::CThostFtdcTraderSpiOutput;
+use tauri_app_vue_lib::CThostFtdcTraderApi;
+
+use crate::ctp_commands::get_next_request_id;
+use crate::trader_spi;
+
+pub type RspResult = Result<Vec<CThostFtdcTraderSpiOutput>, String>;
+
+/// 一条 OnRsp* 回报的关联信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct RspMeta {
+    pub request_id: i32,
+    pub is_last: bool,
+    pub error: Option<String>,
+}
+
+macro_rules! rsp_meta {
+    ($msg:expr, $($variant:ident),+ $(,)?) => {
+        match $msg {
+            $(CThostFtdcTraderSpiOutput::$variant(p) => Some(RspMeta {
+                request_id: p.n_request_id,
+                is_last: p.b_is_last,
+                error: rsp_info_error(p.p_rsp_info.as_ref()),
+            }),)+
+            _ => None,
+        }
+    };
+}
+
+/// 取出 OnRsp* 回报的 request_id、是否最后一包以及错误信息，OnRtn* 等推送返回 None
+pub fn rsp_meta(msg: &CThostFtdcTraderSpiOutput) -> Option<RspMeta> {
+    rsp_meta!(
+        msg,
+        OnRspAuthenticate,
+        OnRspUserLogin,
+        OnRspUserLogout,
+        OnRspUserPasswordUpdate,
+        OnRspTradingAccountPasswordUpdate,
+        OnRspUserAuthMethod,
+        OnRspGenUserCaptcha,
+        OnRspGenUserText,
+        OnRspOrderInsert,
+        OnRspParkedOrderInsert,
+        OnRspParkedOrderAction,
+        OnRspOrderAction,
+        OnRspQryMaxOrderVolume,
+        OnRspSettlementInfoConfirm,
+        OnRspRemoveParkedOrder,
+        OnRspRemoveParkedOrderAction,
+        OnRspExecOrderInsert,
+        OnRspExecOrderAction,
+        OnRspForQuoteInsert,
+        OnRspQuoteInsert,
+        OnRspQuoteAction,
+        OnRspBatchOrderAction,
+        OnRspOptionSelfCloseInsert,
+        OnRspOptionSelfCloseAction,
+        OnRspCombActionInsert,
+        OnRspQryOrder,
+        OnRspQryTrade,
+        OnRspQryInvestorPosition,
+        OnRspQryTradingAccount,
+        OnRspQryInvestor,
+        OnRspQryTradingCode,
+        OnRspQryInstrumentMarginRate,
+        OnRspQryInstrumentCommissionRate,
+        OnRspQryExchange,
+        OnRspQryProduct,
+        OnRspQryInstrument,
+        OnRspQryDepthMarketData,
+        OnRspQryTraderOffer,
+        OnRspQrySettlementInfo,
+        OnRspQryTransferBank,
+        OnRspQryInvestorPositionDetail,
+        OnRspQryNotice,
+        OnRspQrySettlementInfoConfirm,
+        OnRspQryInvestorPositionCombineDetail,
+        OnRspQryCFMMCTradingAccountKey,
+        OnRspQryEWarrantOffset,
+        OnRspQryInvestorProductGroupMargin,
+        OnRspQryExchangeMarginRate,
+        OnRspQryExchangeMarginRateAdjust,
+        OnRspQryExchangeRate,
+        OnRspQrySecAgentACIDMap,
+        OnRspQryProductExchRate,
+        OnRspQryProductGroup,
+        OnRspQryMMInstrumentCommissionRate,
+        OnRspQryMMOptionInstrCommRate,
+        OnRspQryInstrumentOrderCommRate,
+        OnRspQrySecAgentTradingAccount,
+        OnRspQrySecAgentCheckMode,
+        OnRspQrySecAgentTradeInfo,
+        OnRspQryOptionInstrTradeCost,
+        OnRspQryOptionInstrCommRate,
+        OnRspQryExecOrder,
+        OnRspQryForQuote,
+        OnRspQryQuote,
+        OnRspQryOptionSelfClose,
+        OnRspQryInvestUnit,
+        OnRspQryCombInstrumentGuard,
+        OnRspQryCombAction,
+        OnRspQryTransferSerial,
+        OnRspQryAccountregister,
+        OnRspError,
+        OnRspQryContractBank,
+        OnRspQryParkedOrder,
+        OnRspQryParkedOrderAction,
+        OnRspQryTradingNotice,
+        OnRspQryBrokerTradingParams,
+        OnRspQryBrokerTradingAlgos,
+        OnRspQueryCFMMCTradingAccountToken,
+        OnRspFromBankToFutureByFuture,
+        OnRspFromFutureToBankByFuture,
+        OnRspQueryBankAccountMoneyByFuture,
+        OnRspQryClassifiedInstrument,
+        OnRspQryCombPromotionParam,
+        OnRspQryRiskSettleInvstPosition,
+        OnRspQryRiskSettleProductStatus,
+    )
+}
+
+struct PendingRequest {
+    session_id: String,
+    rows: Vec<CThostFtdcTraderSpiOutput>,
+    sender: oneshot::Sender<RspResult>,
+}
+
+/// 正在等待回报的请求表：request_id → 已收到的回报
+#[derive(Default)]
+pub struct PendingTable {
+    requests: HashMap<i32, PendingRequest>,
+}
+
+impl PendingTable {
+    /// 登记一个请求，必须在发出请求之前调用，避免回报先于登记到达
+    pub fn register(&mut self, session_id: &str, request_id: i32) -> oneshot::Receiver<RspResult> {
+        let (sender, receiver) = oneshot::channel();
+        self.requests.insert(
+            request_id,
+            PendingRequest {
+                session_id: session_id.to_string(),
+                rows: Vec::new(),
+                sender,
+            },
+        );
+        receiver
+    }
+
+    pub fn unregister(&mut self, request_id: i32) {
+        self.requests.remove(&request_id);
+    }
+
+    /// 把回报交给等待中的请求；不属于任何已登记请求的消息原样返回
+    pub fn route(
+        &mut self,
+        session_id: &str,
+        msg: CThostFtdcTraderSpiOutput,
+    ) -> Option<CThostFtdcTraderSpiOutput> {
+        let Some(meta) = rsp_meta(&msg) else {
+            return Some(msg);
+        };
+        let Some(pending) = self.requests.get_mut(&meta.request_id) else {
+            return Some(msg);
+        };
+        if pending.session_id != session_id {
+            return Some(msg);
+        }
+
+        if let Some(error) = meta.error {
+            let pending = self.requests.remove(&meta.request_id).unwrap();
+            let _ = pending.sender.send(Err(error));
+            return None;
+        }
+        pending.rows.push(msg);
+        if meta.is_last {
+            let pending = self.requests.remove(&meta.request_id).unwrap();
+            let _ = pending.sender.send(Ok(pending.rows));
+        }
+        None
+    }
+
+    /// 前置断开时结束该会话的全部等待，回报不会再到达
+    pub fn fail_session(&mut self, session_id: &str, error: &str) {
+        let request_ids: Vec<i32> = self
+            .requests
+            .iter()
+            .filter(|(_, pending)| pending.session_id == session_id)
+            .map(|(request_id, _)| *request_id)
+            .collect();
+        for request_id in request_ids {
+            if let Some(pending) = self.requests.remove(&request_id) {
+                let _ = pending.sender.send(Err(error.to_string()));
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    // 所有交易会话共用一张表，request_id 由 get_next_request_id 全局分配
+    static ref PENDING_REQUESTS: Arc<Mutex<PendingTable>> =
+        Arc::new(Mutex::new(PendingTable::default()));
+}
+
+/// 事件泵收到的每条消息先经过这里，被等待中的请求认领后返回 None
+pub fn route(session_id: &str, msg: CThostFtdcTraderSpiOutput) -> Option<CThostFtdcTraderSpiOutput> {
+    PENDING_REQUESTS.lock().unwrap().route(session_id, msg)
+}
+
+pub fn fail_session(session_id: &str, error: &str) {
+    PENDING_REQUESTS.lock().unwrap().fail_session(session_id, error);
+}
+
+/// 发送请求失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendError {
+    /// CTP 拒绝发送：-1 网络连接失败，-2 未处理请求超过许可数，-3 每秒发送请求数超过许可数
+    Rejected(i32),
+    /// 会话不存在等本地错误
+    Unavailable(String),
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Rejected(code) => write!(f, "发送请求失败，错误代码: {}", code),
+            SendError::Unavailable(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// 已经发出、正在等待回报的请求
+pub struct SentRequest {
+    request_id: i32,
+    receiver: oneshot::Receiver<RspResult>,
+}
+
+/// 分配 request_id 并发出请求，`send` 拿到交易 API 和 request_id，返回 CTP 的发送结果
+///
+/// 请求在发出之前就已登记，发送失败时撤销登记。
+pub fn send(
+    session_id: &str,
+    send: impl FnOnce(&mut CThostFtdcTraderApi, i32) -> i32,
+) -> Result<SentRequest, SendError> {
+    let request_id = get_next_request_id();
+    let receiver = PENDING_REQUESTS.lock().unwrap().register(session_id, request_id);
+
+    let result = match trader_spi::with_trader_api(session_id, |api| send(api, request_id)) {
+        Ok(0) => {
+            println!("📤 [DEBUG] Request sent, request_id: {}", request_id);
+            return Ok(SentRequest {
+                request_id,
+                receiver,
+            });
+        }
+        Ok(code) => Err(SendError::Rejected(code)),
+        Err(error) => Err(SendError::Unavailable(error)),
+    };
+    PENDING_REQUESTS.lock().unwrap().unregister(request_id);
+    result
+}
+
+impl SentRequest {
+    /// 等待全部回报；`extract` 从每条回报里取出需要的数据，
+    /// 空记录（例如查询无结果时的最后一包）返回 None 即可
+    pub async fn wait<T>(
+        self,
+        timeout: Duration,
+        extract: impl FnMut(CThostFtdcTraderSpiOutput) -> Option<T>,
+    ) -> Result<Vec<T>, String> {
+        let request_id = self.request_id;
+        match tokio::time::timeout(timeout, self.receiver).await {
+            Ok(Ok(result)) => result.map(|rows| rows.into_iter().filter_map(extract).collect()),
+            Ok(Err(_)) => Err(format!("请求 {} 已中断", request_id)),
+            Err(_) => {
+                PENDING_REQUESTS.lock().unwrap().unregister(request_id);
+                Err(format!(
+                    "请求超时（{}秒内未收到 request_id {} 的全部回报）",
+                    timeout.as_secs(),
+                    request_id
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri_app_vue_lib::trader_api::{
+        CThostFtdcTraderSpiOnRspQryOrderPacket, CThostFtdcTraderSpiOnRtnOrderPacket,
+    };
+    use tauri_app_vue_lib::{CThostFtdcOrderField, CThostFtdcRspInfoField};
+
+    fn qry_order(request_id: i32, is_last: bool, error_id: i32) -> CThostFtdcTraderSpiOutput {
+        let rsp_info = (error_id != 0).then(|| CThostFtdcRspInfoField {
+            ErrorID: error_id,
+            ..Default::default()
+        });
+        CThostFtdcTraderSpiOutput::OnRspQryOrder(CThostFtdcTraderSpiOnRspQryOrderPacket {
+            p_order: Some(CThostFtdcOrderField::default()),
+            p_rsp_info: rsp_info,
+            n_request_id: request_id,
+            b_is_last: is_last,
+        })
+    }
+
+    #[test]
+    fn collects_rows_until_last_packet() {
+        let mut table = PendingTable::default();
+        let mut receiver = table.register("s1", 7);
+
+        assert!(table.route("s1", qry_order(7, false, 0)).is_none());
+        assert!(receiver.try_recv().is_err());
+        assert!(table.route("s1", qry_order(7, true, 0)).is_none());
+        assert_eq!(receiver.try_recv().unwrap().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rsp_info_error_resolves_early() {
+        let mut table = PendingTable::default();
+        let mut receiver = table.register("s1", 8);
+
+        assert!(table.route("s1", qry_order(8, false, 90)).is_none());
+        let error = receiver.try_recv().unwrap().unwrap_err();
+        assert!(error.starts_with("[90]"));
+        // 之后的回报不再被认领
+        assert!(table.route("s1", qry_order(8, true, 0)).is_some());
+    }
+
+    #[test]
+    fn unrelated_messages_pass_through() {
+        let mut table = PendingTable::default();
+        let _receiver = table.register("s1", 9);
+
+        assert!(table.route("s1", qry_order(10, true, 0)).is_some());
+        assert!(table.route("s2", qry_order(9, true, 0)).is_some());
+        let rtn = CThostFtdcTraderSpiOutput::OnRtnOrder(CThostFtdcTraderSpiOnRtnOrderPacket {
+            p_order: None,
+        });
+        assert!(table.route("s1", rtn).is_some());
+    }
+
+    #[test]
+    fn disconnect_fails_pending_requests_of_the_session() {
+        let mut table = PendingTable::default();
+        let mut r1 = table.register("s1", 1);
+        let mut r2 = table.register("s2", 2);
+
+        table.fail_session("s1", "disconnected");
+        assert_eq!(r1.try_recv().unwrap().unwrap_err(), "disconnected");
+        assert!(r2.try_recv().is_err());
+    }
+}
@@ -0,0 +1,101 @@
+// 自成交防范：新报单会与本账户同一合约的反向挂单成交时，拒绝新报单或先撤掉挂单
+use serde::{Deserialize, Serialize};
+
+use crate::ctp_commands::OrderRequest;
+use crate::ctp_flags::{Direction, OrderPriceType};
+use crate::order_store::{self, OrderFilter};
+use crate::trader_events::OrderInfo;
+
+/// 发现自成交风险时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTradeAction {
+    /// 拒绝新报单
+    #[default]
+    Reject,
+    /// 先撤掉会成交的挂单，撤单成功后再报新单
+    CancelResting,
+}
+
+/// 新报单是否会与这笔反向挂单成交；非限价单按一定成交处理
+pub fn crosses(order: &OrderRequest, resting: &OrderInfo) -> bool {
+    if order.order_type != OrderPriceType::LimitPrice {
+        return true;
+    }
+    match order.direction {
+        Direction::Buy => order.price >= resting.limit_price,
+        Direction::Sell => order.price <= resting.limit_price,
+    }
+}
+
+/// 本账户同一合约中会与新报单成交的未结束反向报单；
+/// 未触发的柜台条件单不在交易所排队，新报单本身是条件单时也不检查
+pub fn crossing_orders(session_id: &str, investor_id: &str, order: &OrderRequest) -> Vec<OrderInfo> {
+    if order.contingent_condition.is_some_and(|c| c.needs_stop_price()) {
+        return Vec::new();
+    }
+    let opposite = match order.direction {
+        Direction::Buy => Direction::Sell,
+        Direction::Sell => Direction::Buy,
+    };
+    let filter = OrderFilter {
+        instrument_id: Some(order.instrument_id.clone()),
+        direction: Some(opposite.to_string()),
+        investor_id: Some(investor_id.to_string()),
+        ..Default::default()
+    };
+    order_store::with_book(session_id, |book| book.working_orders(&filter))
+        .into_iter()
+        .filter(|resting| !order_store::is_pending_trigger(&resting.order_status))
+        .filter(|resting| crosses(order, resting))
+        .collect()
+}
+
+/// 有会成交的反向挂单时拒绝报单；send_order 对所有报单路径都做这项检查
+pub fn check(session_id: &str, investor_id: &str, order: &OrderRequest) -> Result<(), String> {
+    let crossing = crossing_orders(session_id, investor_id, order);
+    if crossing.is_empty() {
+        return Ok(());
+    }
+    let refs: Vec<&str> = crossing.iter().map(|o| o.order_ref.as_str()).collect();
+    Err(format!(
+        "自成交风险: {} 与本账户挂单 {} 价格相交",
+        order.instrument_id,
+        refs.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(direction: Direction, order_type: OrderPriceType, price: f64) -> OrderRequest {
+        OrderRequest {
+            order_type,
+            ..OrderRequest::limit("rb2505", direction, price, 1)
+        }
+    }
+
+    fn resting(limit_price: f64) -> OrderInfo {
+        OrderInfo {
+            instrument_id: "rb2505".to_string(),
+            limit_price,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn limit_orders_cross_at_or_through_the_resting_price() {
+        let limit = OrderPriceType::LimitPrice;
+        assert!(crosses(&order(Direction::Buy, limit, 3500.0), &resting(3500.0)));
+        assert!(crosses(&order(Direction::Buy, limit, 3501.0), &resting(3500.0)));
+        assert!(!crosses(&order(Direction::Buy, limit, 3499.0), &resting(3500.0)));
+        assert!(crosses(&order(Direction::Sell, limit, 3499.0), &resting(3500.0)));
+        assert!(!crosses(&order(Direction::Sell, limit, 3501.0), &resting(3500.0)));
+    }
+
+    #[test]
+    fn market_orders_always_cross() {
+        assert!(crosses(&order(Direction::Sell, OrderPriceType::AnyPrice, 0.0), &resting(3500.0)));
+    }
+}
@@ -0,0 +1,193 @@
+// 行情订阅登记：多个窗口可以关注同一合约，按窗口做引用计数，
+// 第一个窗口关注时才向 CTP 订阅，最后一个窗口释放时才真正退订
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+/// list_subscriptions 返回的单个合约订阅情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionInfo {
+    pub instrument_id: String,
+    pub ref_count: u32,
+    pub windows: Vec<WindowSubscription>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowSubscription {
+    pub window_label: String,
+    pub ref_count: u32,
+}
+
+/// 单个行情会话的订阅表：合约 → 窗口 → 引用次数
+#[derive(Debug, Default)]
+pub struct SubscriptionRegistry {
+    instruments: BTreeMap<String, BTreeMap<String, u32>>,
+}
+
+impl SubscriptionRegistry {
+    /// 登记窗口对合约的关注，返回此前没有任何窗口关注、需要向 CTP 订阅的合约
+    pub fn add(&mut self, window_label: &str, instrument_ids: &[String]) -> Vec<String> {
+        let mut newly_watched = Vec::new();
+        for id in instrument_ids {
+            let watchers = self.instruments.entry(id.clone()).or_default();
+            if watchers.is_empty() && !newly_watched.contains(id) {
+                newly_watched.push(id.clone());
+            }
+            *watchers.entry(window_label.to_string()).or_insert(0) += 1;
+        }
+        newly_watched
+    }
+
+    /// 释放窗口对合约的关注，返回已经没有窗口关注、需要向 CTP 退订的合约
+    pub fn remove(&mut self, window_label: &str, instrument_ids: &[String]) -> Vec<String> {
+        let mut released = Vec::new();
+        for id in instrument_ids {
+            let Some(watchers) = self.instruments.get_mut(id) else {
+                continue;
+            };
+            if let Some(count) = watchers.get_mut(window_label) {
+                *count -= 1;
+                if *count == 0 {
+                    watchers.remove(window_label);
+                }
+            }
+            if watchers.is_empty() {
+                self.instruments.remove(id);
+                released.push(id.clone());
+            }
+        }
+        released
+    }
+
+    /// 窗口关闭时释放它持有的全部订阅
+    pub fn remove_window(&mut self, window_label: &str) -> Vec<String> {
+        let mut released = Vec::new();
+        self.instruments.retain(|id, watchers| {
+            watchers.remove(window_label);
+            if watchers.is_empty() {
+                released.push(id.clone());
+                false
+            } else {
+                true
+            }
+        });
+        released
+    }
+
+    /// 当前需要保持订阅的全部合约
+    pub fn instruments(&self) -> Vec<String> {
+        self.instruments.keys().cloned().collect()
+    }
+
+    pub fn snapshot(&self) -> Vec<SubscriptionInfo> {
+        self.instruments
+            .iter()
+            .map(|(id, watchers)| SubscriptionInfo {
+                instrument_id: id.clone(),
+                ref_count: watchers.values().sum(),
+                windows: watchers
+                    .iter()
+                    .map(|(label, count)| WindowSubscription {
+                        window_label: label.clone(),
+                        ref_count: *count,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+lazy_static::lazy_static! {
+    // 每个行情会话一张订阅表
+    static ref MD_SUBSCRIPTIONS: Arc<Mutex<HashMap<String, SubscriptionRegistry>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+pub fn add(session_id: &str, window_label: &str, instrument_ids: &[String]) -> Vec<String> {
+    let mut registries = MD_SUBSCRIPTIONS.lock().unwrap();
+    registries
+        .entry(session_id.to_string())
+        .or_default()
+        .add(window_label, instrument_ids)
+}
+
+pub fn remove(session_id: &str, window_label: &str, instrument_ids: &[String]) -> Vec<String> {
+    let mut registries = MD_SUBSCRIPTIONS.lock().unwrap();
+    registries
+        .get_mut(session_id)
+        .map(|registry| registry.remove(window_label, instrument_ids))
+        .unwrap_or_default()
+}
+
+/// 释放窗口在所有行情会话中的订阅，返回每个会话需要退订的合约
+pub fn remove_window(window_label: &str) -> Vec<(String, Vec<String>)> {
+    let mut registries = MD_SUBSCRIPTIONS.lock().unwrap();
+    registries
+        .iter_mut()
+        .map(|(session_id, registry)| (session_id.clone(), registry.remove_window(window_label)))
+        .filter(|(_, released)| !released.is_empty())
+        .collect()
+}
+
+/// 行情会话释放后丢弃它的订阅表
+pub fn remove_session(session_id: &str) {
+    MD_SUBSCRIPTIONS.lock().unwrap().remove(session_id);
+}
+
+pub fn instruments(session_id: &str) -> Vec<String> {
+    let registries = MD_SUBSCRIPTIONS.lock().unwrap();
+    registries
+        .get(session_id)
+        .map(|registry| registry.instruments())
+        .unwrap_or_default()
+}
+
+pub fn snapshot(session_id: &str) -> Vec<SubscriptionInfo> {
+    let registries = MD_SUBSCRIPTIONS.lock().unwrap();
+    registries
+        .get(session_id)
+        .map(|registry| registry.snapshot())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn subscribes_on_first_watcher_and_releases_on_last() {
+        let mut registry = SubscriptionRegistry::default();
+        assert_eq!(registry.add("trading-panel-1", &ids(&["rb2505"])), ids(&["rb2505"]));
+        assert!(registry.add("trading-panel-2", &ids(&["rb2505"])).is_empty());
+        assert!(registry.add("trading-panel-2", &ids(&["rb2505"])).is_empty());
+
+        assert!(registry.remove("trading-panel-1", &ids(&["rb2505"])).is_empty());
+        assert!(registry.remove("trading-panel-2", &ids(&["rb2505"])).is_empty());
+        assert_eq!(registry.remove("trading-panel-2", &ids(&["rb2505"])), ids(&["rb2505"]));
+        assert!(registry.instruments().is_empty());
+    }
+
+    #[test]
+    fn closing_a_window_releases_only_its_instruments() {
+        let mut registry = SubscriptionRegistry::default();
+        registry.add("main", &ids(&["rb2505", "hc2505"]));
+        registry.add("trading-panel-1", &ids(&["rb2505"]));
+
+        assert_eq!(registry.remove_window("main"), ids(&["hc2505"]));
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].instrument_id, "rb2505");
+        assert_eq!(snapshot[0].windows[0].window_label, "trading-panel-1");
+    }
+
+    #[test]
+    fn releasing_unknown_instrument_is_a_no_op() {
+        let mut registry = SubscriptionRegistry::default();
+        assert!(registry.remove("main", &ids(&["rb2505"])).is_empty());
+    }
+}
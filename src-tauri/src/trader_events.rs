@@ -0,0 +1,530 @@
+// 交易回报事件：把 CTP 私有流/公共流的回报转换为可序列化的结构并推送给相关窗口
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use tauri_app_vue_lib::trader_api::CThostFtdcTraderSpiOutput;
+use tauri_app_vue_lib::{
+    char_flag_to_string, gb18030_cstr_to_str_i8, CThostFtdcBulletinField,
+    CThostFtdcErrorConditionalOrderField, CThostFtdcInputOrderField,
+    CThostFtdcInstrumentStatusField, CThostFtdcOrderActionField,
+    CThostFtdcOrderField, CThostFtdcRspInfoField, CThostFtdcTradeField,
+    CThostFtdcTradingNoticeInfoField,
+};
+
+use crate::algo_orders::AlgoOrder;
+use crate::cancel_quota::CancelCount;
+
+pub const ORDER_UPDATE_EVENT: &str = "order-update";
+pub const TRADE_UPDATE_EVENT: &str = "trade-update";
+pub const ORDER_INSERT_ERROR_EVENT: &str = "order-insert-error";
+pub const ORDER_ACTION_ERROR_EVENT: &str = "order-action-error";
+pub const CONDITIONAL_ORDER_ERROR_EVENT: &str = "conditional-order-error";
+pub const INSTRUMENT_STATUS_EVENT: &str = "instrument-status";
+pub const BULLETIN_EVENT: &str = "bulletin";
+pub const TRADING_NOTICE_EVENT: &str = "trading-notice";
+pub const CANCEL_COUNT_EVENT: &str = "cancel-count-update";
+pub const ALGO_ORDER_EVENT: &str = "algo-order-update";
+
+// 需要接收报单和成交回报的窗口
+const ORDER_WINDOWS: &[&str] = &["main", "trading-panel-"];
+// 交易所公告和通知只在主窗口展示
+const NOTICE_WINDOWS: &[&str] = &["main"];
+
+/// 报单回报，字段与 types/ctp.ts 中的 OrderInfo 对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderInfo {
+    pub broker_id: String,
+    pub investor_id: String,
+    pub order_ref: String,
+    pub user_id: String,
+    pub instrument_id: String,
+    pub order_price_type: String,
+    pub direction: String,
+    pub combine_offset_flag: String,
+    pub combine_hedge_flag: String,
+    pub limit_price: f64,
+    pub volume_total_original: i32,
+    pub time_condition: String,
+    pub gtd_date: String,
+    pub volume_condition: String,
+    pub min_volume: i32,
+    pub contingent_condition: String,
+    pub stop_price: f64,
+    pub force_close_reason: String,
+    pub is_auto_suspend: i32,
+    pub business_unit: String,
+    pub request_id: i32,
+    pub order_local_id: String,
+    pub exchange_id: String,
+    pub participant_id: String,
+    pub client_id: String,
+    pub exchange_inst_id: String,
+    pub trader_id: String,
+    pub install_id: i32,
+    pub order_submit_status: String,
+    pub notify_sequence: i32,
+    pub trading_day: String,
+    pub settlement_id: i32,
+    pub order_sys_id: String,
+    pub order_source: String,
+    pub order_status: String,
+    pub order_type: String,
+    pub volume_traded: i32,
+    pub volume_total: i32,
+    pub insert_date: String,
+    pub insert_time: String,
+    pub active_time: String,
+    pub suspend_time: String,
+    pub update_time: String,
+    pub cancel_time: String,
+    pub active_trader_id: String,
+    pub clearing_part_id: String,
+    pub sequence_no: i32,
+    pub front_id: i32,
+    pub session_id: i32,
+    pub user_product_info: String,
+    pub status_msg: String,
+    pub user_force_close: i32,
+    pub active_user_id: String,
+    pub broker_order_seq: i32,
+    pub relative_order_sys_id: String,
+    pub zcetotal_traded_volume: i32,
+    pub is_swap_order: i32,
+    pub branch_id: String,
+    pub invest_unit_id: String,
+    pub account_id: String,
+    pub currency_id: String,
+    pub ip_address: String,
+    pub mac_address: String,
+}
+
+impl From<&CThostFtdcOrderField> for OrderInfo {
+    fn from(o: &CThostFtdcOrderField) -> Self {
+        OrderInfo {
+            broker_id: gb18030_cstr_to_str_i8(&o.BrokerID).to_string(),
+            investor_id: gb18030_cstr_to_str_i8(&o.InvestorID).to_string(),
+            order_ref: gb18030_cstr_to_str_i8(&o.OrderRef).trim().to_string(),
+            user_id: gb18030_cstr_to_str_i8(&o.UserID).to_string(),
+            instrument_id: gb18030_cstr_to_str_i8(&o.InstrumentID).to_string(),
+            order_price_type: char_flag_to_string(o.OrderPriceType),
+            direction: char_flag_to_string(o.Direction),
+            combine_offset_flag: gb18030_cstr_to_str_i8(&o.CombOffsetFlag).to_string(),
+            combine_hedge_flag: gb18030_cstr_to_str_i8(&o.CombHedgeFlag).to_string(),
+            limit_price: o.LimitPrice,
+            volume_total_original: o.VolumeTotalOriginal,
+            time_condition: char_flag_to_string(o.TimeCondition),
+            gtd_date: gb18030_cstr_to_str_i8(&o.GTDDate).to_string(),
+            volume_condition: char_flag_to_string(o.VolumeCondition),
+            min_volume: o.MinVolume,
+            contingent_condition: char_flag_to_string(o.ContingentCondition),
+            stop_price: o.StopPrice,
+            force_close_reason: char_flag_to_string(o.ForceCloseReason),
+            is_auto_suspend: o.IsAutoSuspend,
+            business_unit: gb18030_cstr_to_str_i8(&o.BusinessUnit).to_string(),
+            request_id: o.RequestID,
+            order_local_id: gb18030_cstr_to_str_i8(&o.OrderLocalID).trim().to_string(),
+            exchange_id: gb18030_cstr_to_str_i8(&o.ExchangeID).to_string(),
+            participant_id: gb18030_cstr_to_str_i8(&o.ParticipantID).to_string(),
+            client_id: gb18030_cstr_to_str_i8(&o.ClientID).to_string(),
+            exchange_inst_id: gb18030_cstr_to_str_i8(&o.ExchangeInstID).to_string(),
+            trader_id: gb18030_cstr_to_str_i8(&o.TraderID).to_string(),
+            install_id: o.InstallID,
+            order_submit_status: char_flag_to_string(o.OrderSubmitStatus),
+            notify_sequence: o.NotifySequence,
+            trading_day: gb18030_cstr_to_str_i8(&o.TradingDay).to_string(),
+            settlement_id: o.SettlementID,
+            order_sys_id: gb18030_cstr_to_str_i8(&o.OrderSysID).trim().to_string(),
+            order_source: char_flag_to_string(o.OrderSource),
+            order_status: char_flag_to_string(o.OrderStatus),
+            order_type: char_flag_to_string(o.OrderType),
+            volume_traded: o.VolumeTraded,
+            volume_total: o.VolumeTotal,
+            insert_date: gb18030_cstr_to_str_i8(&o.InsertDate).to_string(),
+            insert_time: gb18030_cstr_to_str_i8(&o.InsertTime).to_string(),
+            active_time: gb18030_cstr_to_str_i8(&o.ActiveTime).to_string(),
+            suspend_time: gb18030_cstr_to_str_i8(&o.SuspendTime).to_string(),
+            update_time: gb18030_cstr_to_str_i8(&o.UpdateTime).to_string(),
+            cancel_time: gb18030_cstr_to_str_i8(&o.CancelTime).to_string(),
+            active_trader_id: gb18030_cstr_to_str_i8(&o.ActiveTraderID).to_string(),
+            clearing_part_id: gb18030_cstr_to_str_i8(&o.ClearingPartID).to_string(),
+            sequence_no: o.SequenceNo,
+            front_id: o.FrontID,
+            session_id: o.SessionID,
+            user_product_info: gb18030_cstr_to_str_i8(&o.UserProductInfo).to_string(),
+            status_msg: gb18030_cstr_to_str_i8(&o.StatusMsg).to_string(),
+            user_force_close: o.UserForceClose,
+            active_user_id: gb18030_cstr_to_str_i8(&o.ActiveUserID).to_string(),
+            broker_order_seq: o.BrokerOrderSeq,
+            relative_order_sys_id: gb18030_cstr_to_str_i8(&o.RelativeOrderSysID).trim().to_string(),
+            zcetotal_traded_volume: o.ZCETotalTradedVolume,
+            is_swap_order: o.IsSwapOrder,
+            branch_id: gb18030_cstr_to_str_i8(&o.BranchID).to_string(),
+            invest_unit_id: gb18030_cstr_to_str_i8(&o.InvestUnitID).to_string(),
+            account_id: gb18030_cstr_to_str_i8(&o.AccountID).to_string(),
+            currency_id: gb18030_cstr_to_str_i8(&o.CurrencyID).to_string(),
+            ip_address: gb18030_cstr_to_str_i8(&o.IPAddress).to_string(),
+            mac_address: gb18030_cstr_to_str_i8(&o.MacAddress).to_string(),
+        }
+    }
+}
+
+/// 成交回报，字段与 types/ctp.ts 中的 TradeInfo 对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeInfo {
+    pub broker_id: String,
+    pub investor_id: String,
+    pub instrument_id: String,
+    pub order_ref: String,
+    pub user_id: String,
+    pub exchange_id: String,
+    pub trade_id: String,
+    pub direction: String,
+    pub order_sys_id: String,
+    pub participant_id: String,
+    pub client_id: String,
+    pub trading_role: String,
+    pub exchange_inst_id: String,
+    pub offset_flag: String,
+    pub hedge_flag: String,
+    pub price: f64,
+    pub volume: i32,
+    pub trade_date: String,
+    pub trade_time: String,
+    pub trade_type: String,
+    pub price_source: String,
+    pub trader_id: String,
+    pub order_local_id: String,
+    pub clearing_part_id: String,
+    pub business_unit: String,
+    pub sequence_no: i32,
+    pub trading_day: String,
+    pub settlement_id: i32,
+    pub broker_order_seq: i32,
+    pub trade_source: String,
+    pub invest_unit_id: String,
+}
+
+impl From<&CThostFtdcTradeField> for TradeInfo {
+    fn from(t: &CThostFtdcTradeField) -> Self {
+        TradeInfo {
+            broker_id: gb18030_cstr_to_str_i8(&t.BrokerID).to_string(),
+            investor_id: gb18030_cstr_to_str_i8(&t.InvestorID).to_string(),
+            instrument_id: gb18030_cstr_to_str_i8(&t.InstrumentID).to_string(),
+            order_ref: gb18030_cstr_to_str_i8(&t.OrderRef).trim().to_string(),
+            user_id: gb18030_cstr_to_str_i8(&t.UserID).to_string(),
+            exchange_id: gb18030_cstr_to_str_i8(&t.ExchangeID).to_string(),
+            trade_id: gb18030_cstr_to_str_i8(&t.TradeID).trim().to_string(),
+            direction: char_flag_to_string(t.Direction),
+            order_sys_id: gb18030_cstr_to_str_i8(&t.OrderSysID).trim().to_string(),
+            participant_id: gb18030_cstr_to_str_i8(&t.ParticipantID).to_string(),
+            client_id: gb18030_cstr_to_str_i8(&t.ClientID).to_string(),
+            trading_role: char_flag_to_string(t.TradingRole),
+            exchange_inst_id: gb18030_cstr_to_str_i8(&t.ExchangeInstID).to_string(),
+            offset_flag: char_flag_to_string(t.OffsetFlag),
+            hedge_flag: char_flag_to_string(t.HedgeFlag),
+            price: t.Price,
+            volume: t.Volume,
+            trade_date: gb18030_cstr_to_str_i8(&t.TradeDate).to_string(),
+            trade_time: gb18030_cstr_to_str_i8(&t.TradeTime).to_string(),
+            trade_type: char_flag_to_string(t.TradeType),
+            price_source: char_flag_to_string(t.PriceSource),
+            trader_id: gb18030_cstr_to_str_i8(&t.TraderID).to_string(),
+            order_local_id: gb18030_cstr_to_str_i8(&t.OrderLocalID).trim().to_string(),
+            clearing_part_id: gb18030_cstr_to_str_i8(&t.ClearingPartID).to_string(),
+            business_unit: gb18030_cstr_to_str_i8(&t.BusinessUnit).to_string(),
+            sequence_no: t.SequenceNo,
+            trading_day: gb18030_cstr_to_str_i8(&t.TradingDay).to_string(),
+            settlement_id: t.SettlementID,
+            broker_order_seq: t.BrokerOrderSeq,
+            trade_source: char_flag_to_string(t.TradeSource),
+            invest_unit_id: gb18030_cstr_to_str_i8(&t.InvestUnitID).to_string(),
+        }
+    }
+}
+
+/// 报单录入被拒（OnErrRtnOrderInsert）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderInsertError {
+    pub order_ref: String,
+    pub instrument_id: String,
+    pub exchange_id: String,
+    pub direction: String,
+    pub combine_offset_flag: String,
+    pub limit_price: f64,
+    pub volume_total_original: i32,
+    pub request_id: i32,
+    pub error_id: i32,
+    pub error_msg: String,
+}
+
+impl OrderInsertError {
+    pub fn new(o: &CThostFtdcInputOrderField, rsp: Option<&CThostFtdcRspInfoField>) -> Self {
+        OrderInsertError {
+            order_ref: gb18030_cstr_to_str_i8(&o.OrderRef).trim().to_string(),
+            instrument_id: gb18030_cstr_to_str_i8(&o.InstrumentID).to_string(),
+            exchange_id: gb18030_cstr_to_str_i8(&o.ExchangeID).to_string(),
+            direction: char_flag_to_string(o.Direction),
+            combine_offset_flag: gb18030_cstr_to_str_i8(&o.CombOffsetFlag).to_string(),
+            limit_price: o.LimitPrice,
+            volume_total_original: o.VolumeTotalOriginal,
+            request_id: o.RequestID,
+            error_id: rsp.map(|r| r.ErrorID).unwrap_or(0),
+            error_msg: rsp
+                .map(|r| gb18030_cstr_to_str_i8(&r.ErrorMsg).to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 撤单/改单被拒（OnErrRtnOrderAction）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderActionError {
+    pub order_ref: String,
+    pub order_action_ref: i32,
+    pub instrument_id: String,
+    pub exchange_id: String,
+    pub order_sys_id: String,
+    pub front_id: i32,
+    pub session_id: i32,
+    pub action_flag: String,
+    pub order_action_status: String,
+    pub error_id: i32,
+    pub error_msg: String,
+}
+
+impl OrderActionError {
+    pub fn new(a: &CThostFtdcOrderActionField, rsp: Option<&CThostFtdcRspInfoField>) -> Self {
+        OrderActionError {
+            order_ref: gb18030_cstr_to_str_i8(&a.OrderRef).trim().to_string(),
+            order_action_ref: a.OrderActionRef,
+            instrument_id: gb18030_cstr_to_str_i8(&a.InstrumentID).to_string(),
+            exchange_id: gb18030_cstr_to_str_i8(&a.ExchangeID).to_string(),
+            order_sys_id: gb18030_cstr_to_str_i8(&a.OrderSysID).trim().to_string(),
+            front_id: a.FrontID,
+            session_id: a.SessionID,
+            action_flag: char_flag_to_string(a.ActionFlag),
+            order_action_status: char_flag_to_string(a.OrderActionStatus),
+            error_id: rsp.map(|r| r.ErrorID).unwrap_or(0),
+            error_msg: rsp
+                .map(|r| gb18030_cstr_to_str_i8(&r.ErrorMsg).to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 柜台条件单触发后报单失败（OnRtnErrorConditionalOrder）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConditionalOrderError {
+    pub order_ref: String,
+    pub front_id: i32,
+    pub session_id: i32,
+    pub instrument_id: String,
+    pub exchange_id: String,
+    pub order_sys_id: String,
+    pub direction: String,
+    pub contingent_condition: String,
+    pub stop_price: f64,
+    pub limit_price: f64,
+    pub volume_total_original: i32,
+    pub order_status: String,
+    pub error_id: i32,
+    pub error_msg: String,
+}
+
+impl From<&CThostFtdcErrorConditionalOrderField> for ConditionalOrderError {
+    fn from(o: &CThostFtdcErrorConditionalOrderField) -> Self {
+        ConditionalOrderError {
+            order_ref: gb18030_cstr_to_str_i8(&o.OrderRef).trim().to_string(),
+            front_id: o.FrontID,
+            session_id: o.SessionID,
+            instrument_id: gb18030_cstr_to_str_i8(&o.InstrumentID).to_string(),
+            exchange_id: gb18030_cstr_to_str_i8(&o.ExchangeID).to_string(),
+            order_sys_id: gb18030_cstr_to_str_i8(&o.OrderSysID).trim().to_string(),
+            direction: char_flag_to_string(o.Direction),
+            contingent_condition: char_flag_to_string(o.ContingentCondition),
+            stop_price: o.StopPrice,
+            limit_price: o.LimitPrice,
+            volume_total_original: o.VolumeTotalOriginal,
+            order_status: char_flag_to_string(o.OrderStatus),
+            error_id: o.ErrorID,
+            error_msg: gb18030_cstr_to_str_i8(&o.ErrorMsg).to_string(),
+        }
+    }
+}
+
+/// 合约交易状态通知
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstrumentStatusInfo {
+    pub exchange_id: String,
+    pub instrument_id: String,
+    pub exchange_inst_id: String,
+    pub settlement_group_id: String,
+    pub instrument_status: String,
+    pub trading_segment_sn: i32,
+    pub enter_time: String,
+    pub enter_reason: String,
+}
+
+impl From<&CThostFtdcInstrumentStatusField> for InstrumentStatusInfo {
+    fn from(s: &CThostFtdcInstrumentStatusField) -> Self {
+        InstrumentStatusInfo {
+            exchange_id: gb18030_cstr_to_str_i8(&s.ExchangeID).to_string(),
+            instrument_id: gb18030_cstr_to_str_i8(&s.InstrumentID).to_string(),
+            exchange_inst_id: gb18030_cstr_to_str_i8(&s.ExchangeInstID).to_string(),
+            settlement_group_id: gb18030_cstr_to_str_i8(&s.SettlementGroupID).to_string(),
+            instrument_status: char_flag_to_string(s.InstrumentStatus),
+            trading_segment_sn: s.TradingSegmentSN,
+            enter_time: gb18030_cstr_to_str_i8(&s.EnterTime).to_string(),
+            enter_reason: char_flag_to_string(s.EnterReason),
+        }
+    }
+}
+
+/// 交易所公告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulletinInfo {
+    pub exchange_id: String,
+    pub trading_day: String,
+    pub bulletin_id: i32,
+    pub sequence_no: i32,
+    pub news_type: String,
+    pub news_urgency: String,
+    pub send_time: String,
+    pub abstract_text: String,
+    pub come_from: String,
+    pub content: String,
+    pub url_link: String,
+    pub market_id: String,
+}
+
+impl From<&CThostFtdcBulletinField> for BulletinInfo {
+    fn from(b: &CThostFtdcBulletinField) -> Self {
+        BulletinInfo {
+            exchange_id: gb18030_cstr_to_str_i8(&b.ExchangeID).to_string(),
+            trading_day: gb18030_cstr_to_str_i8(&b.TradingDay).to_string(),
+            bulletin_id: b.BulletinID,
+            sequence_no: b.SequenceNo,
+            news_type: gb18030_cstr_to_str_i8(&b.NewsType).to_string(),
+            news_urgency: char_flag_to_string(b.NewsUrgency),
+            send_time: gb18030_cstr_to_str_i8(&b.SendTime).to_string(),
+            abstract_text: gb18030_cstr_to_str_i8(&b.Abstract).to_string(),
+            come_from: gb18030_cstr_to_str_i8(&b.ComeFrom).to_string(),
+            content: gb18030_cstr_to_str_i8(&b.Content).to_string(),
+            url_link: gb18030_cstr_to_str_i8(&b.URLLink).to_string(),
+            market_id: gb18030_cstr_to_str_i8(&b.MarketID).to_string(),
+        }
+    }
+}
+
+/// 期货公司发给投资者的交易通知
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradingNoticeInfo {
+    pub broker_id: String,
+    pub investor_id: String,
+    pub send_time: String,
+    pub content: String,
+    pub sequence_series: i16,
+    pub sequence_no: i32,
+    pub invest_unit_id: String,
+}
+
+impl From<&CThostFtdcTradingNoticeInfoField> for TradingNoticeInfo {
+    fn from(n: &CThostFtdcTradingNoticeInfoField) -> Self {
+        TradingNoticeInfo {
+            broker_id: gb18030_cstr_to_str_i8(&n.BrokerID).to_string(),
+            investor_id: gb18030_cstr_to_str_i8(&n.InvestorID).to_string(),
+            send_time: gb18030_cstr_to_str_i8(&n.SendTime).to_string(),
+            content: gb18030_cstr_to_str_i8(&n.FieldContent).to_string(),
+            sequence_series: n.SequenceSeries,
+            sequence_no: n.SequenceNo,
+            invest_unit_id: gb18030_cstr_to_str_i8(&n.InvestUnitID).to_string(),
+        }
+    }
+}
+
+// 按窗口 label 前缀推送事件，例如 "trading-panel-" 匹配所有交易面板
+fn emit_to_windows<S: Serialize + Clone>(app: &AppHandle, windows: &[&str], event: &str, payload: S) {
+    for label in app.webview_windows().keys() {
+        if windows.iter().any(|prefix| label.starts_with(prefix)) {
+            if let Err(e) = app.emit_to(label.as_str(), event, payload.clone()) {
+                println!("❌ [ERROR] Failed to emit {} to {}: {}", event, label, e);
+            }
+        }
+    }
+}
+
+// 推送给所有窗口
+fn emit_to_all<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app.emit(event, payload) {
+        println!("❌ [ERROR] Failed to emit {}: {}", event, e);
+    }
+}
+
+/// 撤单次数变化推送给所有窗口，各交易面板据此显示和限制撤单
+pub fn emit_cancel_count(app: &AppHandle, count: CancelCount) {
+    emit_to_all(app, CANCEL_COUNT_EVENT, count);
+}
+
+/// 算法单成交或状态变化推送给接收报单回报的窗口
+pub fn emit_algo_order(app: &AppHandle, algo: AlgoOrder) {
+    emit_to_windows(app, ORDER_WINDOWS, ALGO_ORDER_EVENT, algo);
+}
+
+/// 把交易 SPI 的回报转换为前端事件，其余消息忽略
+pub fn dispatch(app: &AppHandle, msg: &CThostFtdcTraderSpiOutput) {
+    use CThostFtdcTraderSpiOutput::*;
+    match msg {
+        OnRtnOrder(p) => {
+            if let Some(order) = p.p_order.as_ref() {
+                emit_to_windows(app, ORDER_WINDOWS, ORDER_UPDATE_EVENT, OrderInfo::from(order));
+            }
+        }
+        OnRtnTrade(p) => {
+            if let Some(trade) = p.p_trade.as_ref() {
+                emit_to_windows(app, ORDER_WINDOWS, TRADE_UPDATE_EVENT, TradeInfo::from(trade));
+            }
+        }
+        OnErrRtnOrderInsert(p) => {
+            if let Some(input) = p.p_input_order.as_ref() {
+                let error = OrderInsertError::new(input, p.p_rsp_info.as_ref());
+                println!("❌ [ERROR] OnErrRtnOrderInsert: {:?}", error);
+                emit_to_windows(app, ORDER_WINDOWS, ORDER_INSERT_ERROR_EVENT, error);
+            }
+        }
+        OnErrRtnOrderAction(p) => {
+            if let Some(action) = p.p_order_action.as_ref() {
+                let error = OrderActionError::new(action, p.p_rsp_info.as_ref());
+                println!("❌ [ERROR] OnErrRtnOrderAction: {:?}", error);
+                emit_to_windows(app, ORDER_WINDOWS, ORDER_ACTION_ERROR_EVENT, error);
+            }
+        }
+        OnRtnErrorConditionalOrder(p) => {
+            if let Some(order) = p.p_error_conditional_order.as_ref() {
+                let error = ConditionalOrderError::from(order);
+                println!("❌ [ERROR] OnRtnErrorConditionalOrder: {:?}", error);
+                emit_to_windows(app, ORDER_WINDOWS, CONDITIONAL_ORDER_ERROR_EVENT, error);
+            }
+        }
+        OnRtnInstrumentStatus(p) => {
+            if let Some(status) = p.p_instrument_status.as_ref() {
+                emit_to_all(app, INSTRUMENT_STATUS_EVENT, InstrumentStatusInfo::from(status));
+            }
+        }
+        OnRtnBulletin(p) => {
+            if let Some(bulletin) = p.p_bulletin.as_ref() {
+                emit_to_windows(app, NOTICE_WINDOWS, BULLETIN_EVENT, BulletinInfo::from(bulletin));
+            }
+        }
+        OnRtnTradingNotice(p) => {
+            if let Some(notice) = p.p_trading_notice_info.as_ref() {
+                emit_to_windows(
+                    app,
+                    NOTICE_WINDOWS,
+                    TRADING_NOTICE_EVENT,
+                    TradingNoticeInfo::from(notice),
+                );
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,370 @@
+// 交易 SPI 事件泵：每个交易会话一个后台任务，负责消费 CThostFtdcTraderSpiStream
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+use tauri_app_vue_lib::trader_api::{self, CThostFtdcTraderSpiOutput, CThostFtdcTraderSpiStream};
+use tauri_app_vue_lib::{
+    gb18030_cstr_to_str_i8, rsp_info_error, CThostFtdcReqAuthenticateField,
+    CThostFtdcReqUserLoginField, CThostFtdcRspUserLoginField,
+    CThostFtdcSettlementInfoConfirmField, CThostFtdcTraderApi,
+    THOST_TE_RESUME_TYPE_THOST_TERT_QUICK, THOST_TE_RESUME_TYPE_THOST_TERT_RESTART,
+};
+
+use crate::algo_orders;
+use crate::cancel_quota;
+use crate::ctp_commands::{
+    copy_str_to_ctp_array, get_next_request_id, CtpAccountConfig, SESSION_LOGIN_INFO, TRADER_APIS,
+    TRADER_LOGIN_STATUS, TRADER_SESSION_INFO,
+};
+use crate::local_triggers;
+use crate::order_ref;
+use crate::order_store;
+use crate::rsp_correlator;
+use crate::trader_events;
+
+/// 等待 OnRspUserLogin 的最长时间
+pub const LOGIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub type LoginResult = Result<TraderLoginInfo, String>;
+
+/// 交易登录成功后返回给前端的会话信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraderLoginInfo {
+    pub front_id: i32,
+    pub session_id: i32,
+    pub max_order_ref: String,
+    pub trading_day: String,
+    pub login_time: String,
+    pub system_name: String,
+}
+
+impl From<&CThostFtdcRspUserLoginField> for TraderLoginInfo {
+    fn from(u: &CThostFtdcRspUserLoginField) -> Self {
+        TraderLoginInfo {
+            front_id: u.FrontID,
+            session_id: u.SessionID,
+            max_order_ref: gb18030_cstr_to_str_i8(&u.MaxOrderRef).trim().to_string(),
+            trading_day: gb18030_cstr_to_str_i8(&u.TradingDay).to_string(),
+            login_time: gb18030_cstr_to_str_i8(&u.LoginTime).to_string(),
+            system_name: gb18030_cstr_to_str_i8(&u.SystemName).to_string(),
+        }
+    }
+}
+
+// 事件泵的运行状态
+#[derive(Default)]
+struct TraderSpiState {
+    front_connected: bool,
+    login_waiter: Option<oneshot::Sender<LoginResult>>,
+}
+
+lazy_static::lazy_static! {
+    // 已经注册 SPI 并启动事件泵的交易会话
+    static ref TRADER_SPI_STATES: Arc<Mutex<HashMap<String, TraderSpiState>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+/// 在指定会话的交易 API 上执行操作
+pub(crate) fn with_trader_api<R>(
+    session_id: &str,
+    f: impl FnOnce(&mut CThostFtdcTraderApi) -> R,
+) -> Result<R, String> {
+    let mut apis = TRADER_APIS
+        .lock()
+        .map_err(|e| format!("Failed to lock TRADER_APIS: {}", e))?;
+    match apis.get_mut(session_id) {
+        Some(api) => Ok(f(&mut **api)),
+        None => Err(format!("Session ID {} not found", session_id)),
+    }
+}
+
+fn set_login_status(session_id: &str, logged_in: bool) {
+    let mut login_status = TRADER_LOGIN_STATUS.lock().unwrap();
+    login_status.insert(session_id.to_string(), logged_in);
+}
+
+// 把登录结果交给正在等待的 trader_login 命令
+fn resolve_login(session_id: &str, result: LoginResult) {
+    let waiter = {
+        let mut states = TRADER_SPI_STATES.lock().unwrap();
+        states
+            .get_mut(session_id)
+            .and_then(|state| state.login_waiter.take())
+    };
+    if let Some(waiter) = waiter {
+        let _ = waiter.send(result);
+    }
+}
+
+/// 等待超时后丢弃登录等待端，下次登录可以重新发起
+pub fn cancel_login(session_id: &str) {
+    let mut states = TRADER_SPI_STATES.lock().unwrap();
+    if let Some(state) = states.get_mut(session_id) {
+        state.login_waiter = None;
+    }
+}
+
+/// 启动交易登录流程，返回用于等待 OnRspUserLogin 的接收端
+///
+/// 首次登录时注册 SPI、订阅私有流（RESTART）和公共流（QUICK）、注册前置并调用 init；
+/// 事件泵已在运行时（例如上次密码错误）只更新账户配置并重新发起认证。
+pub fn start_login(
+    session_id: &str,
+    config: CtpAccountConfig,
+    app: AppHandle,
+) -> Result<oneshot::Receiver<LoginResult>, String> {
+    if !TRADER_APIS.lock().unwrap().contains_key(session_id) {
+        return Err(format!("Session ID {} not found", session_id));
+    }
+
+    if *TRADER_LOGIN_STATUS.lock().unwrap().get(session_id).unwrap_or(&false) {
+        if let Some(info) = TRADER_SESSION_INFO.lock().unwrap().get(session_id) {
+            let (sender, receiver) = oneshot::channel();
+            let _ = sender.send(Ok(info.clone()));
+            return Ok(receiver);
+        }
+    }
+
+    let trade_front = config.trade_front.clone();
+    // 前置地址含 NUL 时在加锁前返回错误
+    let front_address = std::ffi::CString::new(trade_front.as_str())
+        .map_err(|e| format!("Invalid trade front {}: {}", trade_front, e))?;
+    let data_dir = app.path().app_data_dir().ok();
+    order_ref::set_store_dir(data_dir.clone());
+    cancel_quota::set_store_dir(data_dir.clone());
+    local_triggers::set_store_dir(data_dir);
+    SESSION_LOGIN_INFO
+        .lock()
+        .unwrap()
+        .insert(session_id.to_string(), config);
+
+    let (sender, receiver) = oneshot::channel();
+    let mut states = TRADER_SPI_STATES.lock().unwrap();
+    if let Some(state) = states.get_mut(session_id) {
+        // 接收端已丢弃（命令超时或被取消）的等待不再占用登录流程
+        if state.login_waiter.as_ref().is_some_and(|waiter| !waiter.is_closed()) {
+            return Err("交易登录流程正在进行中".to_string());
+        }
+        state.login_waiter = Some(sender);
+        if state.front_connected {
+            drop(states);
+            if let Err(error) = send_authenticate_or_login(session_id) {
+                resolve_login(session_id, Err(error));
+            }
+        }
+        return Ok(receiver);
+    }
+
+    let stream = with_trader_api(session_id, |api| {
+        let (stream, pp) = trader_api::create_spi();
+        api.register_spi(pp);
+        // 私有流从头重传，登录后报单簿能拿到当日全部报单和成交
+        api.subscribe_private_topic(THOST_TE_RESUME_TYPE_THOST_TERT_RESTART);
+        api.subscribe_public_topic(THOST_TE_RESUME_TYPE_THOST_TERT_QUICK);
+        api.register_front(front_address);
+        println!("✅ [DEBUG] Registered trade front: {}", trade_front);
+        api.init();
+        println!("✅ [DEBUG] Trader API initialized");
+        stream
+    })?;
+
+    states.insert(
+        session_id.to_string(),
+        TraderSpiState {
+            front_connected: false,
+            login_waiter: Some(sender),
+        },
+    );
+    drop(states);
+
+    spawn_pump(session_id.to_string(), stream, app);
+    Ok(receiver)
+}
+
+// 每个会话一个后台任务，持有 SPI 流直到进程退出
+fn spawn_pump(session_id: String, mut stream: Box<CThostFtdcTraderSpiStream>, app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        while let Some(msg) = stream.next().await {
+            order_store::on_spi_output(&session_id, &msg);
+            if let Some(count) = cancel_quota::on_spi_output(&session_id, &msg) {
+                trader_events::emit_cancel_count(&app, count);
+            }
+            trader_events::dispatch(&app, &msg);
+            for algo in algo_orders::on_spi_output(&session_id, &msg) {
+                trader_events::emit_algo_order(&app, algo);
+            }
+            // 查询等请求的回报由关联器交给等待中的命令，其余消息照常处理
+            if let Some(msg) = rsp_correlator::route(&session_id, msg) {
+                handle_spi_output(&session_id, msg);
+            }
+        }
+        println!("⚠️ [WARN] Trader SPI stream ended for session: {}", session_id);
+    });
+}
+
+fn handle_spi_output(session_id: &str, msg: CThostFtdcTraderSpiOutput) {
+    use CThostFtdcTraderSpiOutput::*;
+    match msg {
+        OnFrontConnected(_) => {
+            println!("✅ [DEBUG] Trader front connected for session: {}", session_id);
+            if let Some(state) = TRADER_SPI_STATES.lock().unwrap().get_mut(session_id) {
+                state.front_connected = true;
+            }
+            // 断线重连后 CTP 会再次回调 OnFrontConnected，这里重新走一遍认证登录
+            if let Err(error) = send_authenticate_or_login(session_id) {
+                resolve_login(session_id, Err(error));
+            }
+        }
+        OnFrontDisconnected(p) => {
+            println!(
+                "⚠️ [WARN] Trader front disconnected for session: {}, reason: {:#x}",
+                session_id, p.n_reason
+            );
+            if let Some(state) = TRADER_SPI_STATES.lock().unwrap().get_mut(session_id) {
+                state.front_connected = false;
+            }
+            set_login_status(session_id, false);
+            rsp_correlator::fail_session(session_id, "交易前置已断开，请求未完成");
+        }
+        OnRspAuthenticate(p) => {
+            if let Some(error) = rsp_info_error(p.p_rsp_info.as_ref()) {
+                println!("❌ [ERROR] ReqAuthenticate failed: {}", error);
+                resolve_login(session_id, Err(format!("客户端认证失败: {}", error)));
+                return;
+            }
+            println!("✅ [DEBUG] Trader authenticated for session: {}", session_id);
+            if let Err(error) = send_user_login(session_id) {
+                resolve_login(session_id, Err(error));
+            }
+        }
+        OnRspUserLogin(p) => {
+            if let Some(error) = rsp_info_error(p.p_rsp_info.as_ref()) {
+                println!("❌ [ERROR] ReqUserLogin failed: {}", error);
+                resolve_login(session_id, Err(format!("交易登录失败: {}", error)));
+                return;
+            }
+            let info = match p.p_rsp_user_login.as_ref() {
+                Some(u) => TraderLoginInfo::from(u),
+                None => {
+                    resolve_login(session_id, Err("OnRspUserLogin 缺少登录应答".to_string()));
+                    return;
+                }
+            };
+            println!("✅ [DEBUG] Trader logged in: {:?}", info);
+
+            TRADER_SESSION_INFO
+                .lock()
+                .unwrap()
+                .insert(session_id.to_string(), info.clone());
+            order_store::with_book(session_id, |book| book.start_trading_day(&info.trading_day));
+            if let Some(config) = SESSION_LOGIN_INFO.lock().unwrap().get(session_id) {
+                let account_key = order_ref::account_key(&config.broker_id, &config.account);
+                order_ref::seed(session_id, &account_key, &info.trading_day, &info.max_order_ref);
+                cancel_quota::start_trading_day(session_id, &account_key, &info.trading_day);
+                local_triggers::restore(session_id, &account_key);
+            }
+            set_login_status(session_id, true);
+
+            if let Err(error) = send_settlement_info_confirm(session_id) {
+                println!("⚠️ [WARN] ReqSettlementInfoConfirm not sent: {}", error);
+            }
+            resolve_login(session_id, Ok(info));
+        }
+        OnRspSettlementInfoConfirm(p) => match rsp_info_error(p.p_rsp_info.as_ref()) {
+            Some(error) => println!("⚠️ [WARN] Settlement info confirm failed: {}", error),
+            None => println!("✅ [DEBUG] Settlement info confirmed for session: {}", session_id),
+        },
+        OnRspError(p) => {
+            if let Some(error) = rsp_info_error(p.p_rsp_info.as_ref()) {
+                println!(
+                    "❌ [ERROR] Trader OnRspError, request_id: {}, {}",
+                    p.n_request_id, error
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+// 配置了认证码时先认证，否则直接登录
+fn send_authenticate_or_login(session_id: &str) -> Result<(), String> {
+    let config = SESSION_LOGIN_INFO
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .ok_or_else(|| format!("未找到会话 {} 的登录信息", session_id))?;
+
+    if config.auth_code.is_empty() {
+        return send_user_login(session_id);
+    }
+
+    let mut req = CThostFtdcReqAuthenticateField::default();
+    copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+    copy_str_to_ctp_array(&config.account, &mut req.UserID);
+    copy_str_to_ctp_array(&config.user_product_info, &mut req.UserProductInfo);
+    copy_str_to_ctp_array(&config.auth_code, &mut req.AuthCode);
+    copy_str_to_ctp_array(&config.app_id, &mut req.AppID);
+
+    let request_id = get_next_request_id();
+    let result = with_trader_api(session_id, |api| api.req_authenticate(&mut req, request_id))?;
+    if result == 0 {
+        println!("📤 [DEBUG] ReqAuthenticate sent, request_id: {}", request_id);
+        Ok(())
+    } else {
+        Err(format!("发送客户端认证请求失败，错误代码: {}", result))
+    }
+}
+
+fn send_user_login(session_id: &str) -> Result<(), String> {
+    let config = SESSION_LOGIN_INFO
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .ok_or_else(|| format!("未找到会话 {} 的登录信息", session_id))?;
+
+    let mut req = CThostFtdcReqUserLoginField::default();
+    copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+    copy_str_to_ctp_array(&config.account, &mut req.UserID);
+    copy_str_to_ctp_array(&config.password, &mut req.Password);
+    copy_str_to_ctp_array(&config.user_product_info, &mut req.UserProductInfo);
+
+    let request_id = get_next_request_id();
+    let result = with_trader_api(session_id, |api| api.req_user_login(&mut req, request_id))?;
+    if result == 0 {
+        println!("📤 [DEBUG] ReqUserLogin sent, request_id: {}", request_id);
+        Ok(())
+    } else {
+        Err(format!("发送登录请求失败，错误代码: {}", result))
+    }
+}
+
+fn send_settlement_info_confirm(session_id: &str) -> Result<(), String> {
+    let config = SESSION_LOGIN_INFO
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .ok_or_else(|| format!("未找到会话 {} 的登录信息", session_id))?;
+
+    let mut req = CThostFtdcSettlementInfoConfirmField::default();
+    copy_str_to_ctp_array(&config.broker_id, &mut req.BrokerID);
+    copy_str_to_ctp_array(&config.account, &mut req.InvestorID);
+
+    let request_id = get_next_request_id();
+    let result = with_trader_api(session_id, |api| {
+        api.req_settlement_info_confirm(&mut req, request_id)
+    })?;
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(format!("错误代码: {}", result))
+    }
+}
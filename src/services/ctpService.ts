@@ -2,8 +2,29 @@ import { invoke } from '@tauri-apps/api/core';
 import {
   CtpAccountConfig,
   ApiResponse,
+  TraderLoginInfo,
   MarketDataRequest,
   OrderRequest,
+  OrderInsertResult,
+  SelfTradeAction,
+  CancelOrderRequest,
+  OrderCancelResult,
+  OrderFilter,
+  CancelOutcome,
+  CloseRequest,
+  CloseParent,
+  RiskConfig,
+  CancelCount,
+  LocalTrigger,
+  LocalTriggerRequest,
+  ParkedOrderInfo,
+  ParkedCancelInfo,
+  ParkedOrders,
+  ChaseRequest,
+  PriceChase,
+  AlgoRequest,
+  AlgoOrder,
+  CancelLimits,
   ConnectionStatus,
   LogEntry,
   LogLevel,
@@ -364,7 +385,7 @@ export class CtpService {
     }
   }
 
-  async traderLogin(config: CtpAccountConfig): Promise<ApiResponse<string>> {
+  async traderLogin(config: CtpAccountConfig): Promise<ApiResponse<TraderLoginInfo>> {
     if (!this.traderSessionId) {
       const error = '请先创建交易API';
       this.addLog(error, LogLevel.Error);
@@ -378,7 +399,7 @@ export class CtpService {
       const result = await safeInvoke('trader_login', {
         sessionId: this.traderSessionId,
         config
-      }) as ApiResponse<string>;
+      }) as ApiResponse<TraderLoginInfo>;
 
       if (result.success) {
         this.traderStatus = 'login_success' as ConnectionStatus;
@@ -402,7 +423,11 @@ export class CtpService {
     }
   }
 
-  async insertOrder(order: OrderRequest): Promise<ApiResponse<string>> {
+  async insertOrder(
+    order: OrderRequest,
+    waitForAck = false,
+    selfTrade: SelfTradeAction = 'reject'
+  ): Promise<ApiResponse<OrderInsertResult>> {
     if (!this.traderSessionId) {
       const error = '请先创建交易API';
       this.addLog(error, LogLevel.Error);
@@ -412,8 +437,10 @@ export class CtpService {
     try {
       const result = await safeInvoke('insert_order', {
         sessionId: this.traderSessionId,
-        order
-      }) as ApiResponse<string>;
+        order,
+        waitForAck,
+        selfTrade
+      }) as ApiResponse<OrderInsertResult>;
 
       if (result.success) {
         this.addLog(`下单成功: ${order.instrument_id} ${order.direction === '0' ? '买入' : '卖出'} ${order.volume}手 @${order.price}`, LogLevel.Info);
@@ -430,7 +457,7 @@ export class CtpService {
     }
   }
 
-  async cancelOrder(orderRef: string): Promise<ApiResponse<string>> {
+  async cancelOrder(target: string | CancelOrderRequest): Promise<ApiResponse<OrderCancelResult>> {
     if (!this.traderSessionId) {
       const error = '请先创建交易API';
       this.addLog(error, LogLevel.Error);
@@ -438,13 +465,15 @@ export class CtpService {
     }
 
     try {
+      const cancelRequest: CancelOrderRequest =
+        typeof target === 'string' ? { order_ref: target } : target;
       const result = await safeInvoke('cancel_order', {
         sessionId: this.traderSessionId,
-        orderRef
-      }) as ApiResponse<string>;
+        cancelRequest
+      }) as ApiResponse<OrderCancelResult>;
 
       if (result.success) {
-        this.addLog(`撤单成功: ${orderRef}`, LogLevel.Info);
+        this.addLog(`撤单成功: ${result.data?.order_ref}`, LogLevel.Info);
       } else {
         this.addLog(`撤单失败: ${result.error}`, LogLevel.Error, result);
       }
@@ -458,6 +487,589 @@ export class CtpService {
     }
   }
 
+  // 按条件批量撤单，不传条件时撤销全部未成交报单
+  async cancelOrders(filter?: OrderFilter): Promise<ApiResponse<CancelOutcome[]>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = filter
+        ? await safeInvoke('cancel_orders', {
+            sessionId: this.traderSessionId,
+            filter
+          }) as ApiResponse<CancelOutcome[]>
+        : await safeInvoke('cancel_all_orders', {
+            sessionId: this.traderSessionId
+          }) as ApiResponse<CancelOutcome[]>;
+
+      if (result.success && result.data) {
+        const failed = result.data.filter(outcome => !outcome.success);
+        const level = failed.length > 0 ? LogLevel.Warning : LogLevel.Info;
+        this.addLog(`批量撤单: 共 ${result.data.length} 笔，失败 ${failed.length} 笔`, level, failed);
+      } else {
+        this.addLog(`批量撤单失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`批量撤单异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  // 平仓，上期所/能源中心自动拆分平今、平昨
+  async closePosition(request: CloseRequest): Promise<ApiResponse<CloseParent>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('close_position', {
+        sessionId: this.traderSessionId,
+        request
+      }) as ApiResponse<CloseParent>;
+
+      if (result.success && result.data) {
+        const legs = result.data.children.map(child => `${child.offset_flag}:${child.volume}`).join(', ');
+        this.addLog(`平仓已提交: ${request.instrument_id} ${request.volume}手 (${legs})`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`平仓失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`平仓异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async placeLocalTrigger(request: LocalTriggerRequest): Promise<ApiResponse<LocalTrigger>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('place_local_trigger', {
+        sessionId: this.traderSessionId,
+        request
+      }) as ApiResponse<LocalTrigger>;
+
+      if (result.success && result.data) {
+        this.addLog(`条件单已提交: ${request.instrument_id} ${request.kind} 触发价 ${request.trigger_price}`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`条件单提交失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`条件单提交异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async cancelLocalTrigger(triggerId: string): Promise<ApiResponse<LocalTrigger>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('cancel_local_trigger', {
+        sessionId: this.traderSessionId,
+        triggerId
+      }) as ApiResponse<LocalTrigger>;
+
+      if (result.success) {
+        this.addLog(`条件单已撤销: ${triggerId}`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`条件单撤销失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`条件单撤销异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async listLocalTriggers(): Promise<ApiResponse<LocalTrigger[]>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      return await safeInvoke('list_local_triggers', {
+        sessionId: this.traderSessionId
+      }) as ApiResponse<LocalTrigger[]>;
+    } catch (error) {
+      this.addLog(`查询条件单异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  // 追价单：后端按行情撤单重报，直到成交或达到滑点/改价次数上限
+  async startPriceChase(request: ChaseRequest): Promise<ApiResponse<PriceChase>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('start_price_chase', {
+        sessionId: this.traderSessionId,
+        request
+      }) as ApiResponse<PriceChase>;
+
+      if (result.success && result.data) {
+        this.addLog(`追价单已提交: ${request.instrument_id} 首笔价格 ${result.data.initial_price}`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`追价单提交失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`追价单提交异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async cancelPriceChase(chaseId: string): Promise<ApiResponse<PriceChase>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('cancel_price_chase', {
+        sessionId: this.traderSessionId,
+        chaseId
+      }) as ApiResponse<PriceChase>;
+
+      if (result.success) {
+        this.addLog(`追价单已停止: ${chaseId}`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`追价单停止失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`追价单停止异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async listPriceChases(): Promise<ApiResponse<PriceChase[]>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      return await safeInvoke('list_price_chases', {
+        sessionId: this.traderSessionId
+      }) as ApiResponse<PriceChase[]>;
+    } catch (error) {
+      this.addLog(`查询追价单异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  // 算法单：TWAP 或冰山单，子单经正常报单路径报出
+  async startAlgoOrder(request: AlgoRequest): Promise<ApiResponse<AlgoOrder>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('start_algo_order', {
+        sessionId: this.traderSessionId,
+        request
+      }) as ApiResponse<AlgoOrder>;
+
+      if (result.success && result.data) {
+        this.addLog(`算法单已提交: ${request.instrument_id} ${request.kind} ${request.volume}手`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`算法单提交失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`算法单提交异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async pauseAlgoOrder(algoId: string): Promise<ApiResponse<AlgoOrder>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('pause_algo_order', {
+        sessionId: this.traderSessionId,
+        algoId
+      }) as ApiResponse<AlgoOrder>;
+
+      if (result.success) {
+        this.addLog(`算法单已暂停: ${algoId}`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`算法单暂停失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`算法单暂停异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async resumeAlgoOrder(algoId: string): Promise<ApiResponse<AlgoOrder>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('resume_algo_order', {
+        sessionId: this.traderSessionId,
+        algoId
+      }) as ApiResponse<AlgoOrder>;
+
+      if (result.success) {
+        this.addLog(`算法单已恢复: ${algoId}`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`算法单恢复失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`算法单恢复异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async cancelAlgoOrder(algoId: string): Promise<ApiResponse<AlgoOrder>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('cancel_algo_order', {
+        sessionId: this.traderSessionId,
+        algoId
+      }) as ApiResponse<AlgoOrder>;
+
+      if (result.success) {
+        this.addLog(`算法单已撤销: ${algoId}`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`算法单撤销失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`算法单撤销异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async listAlgoOrders(): Promise<ApiResponse<AlgoOrder[]>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      return await safeInvoke('list_algo_orders', {
+        sessionId: this.traderSessionId
+      }) as ApiResponse<AlgoOrder[]>;
+    } catch (error) {
+      this.addLog(`查询算法单异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  // 预埋报单，开盘后由柜台自动报出
+  async parkOrder(order: OrderRequest): Promise<ApiResponse<ParkedOrderInfo>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('park_order', {
+        sessionId: this.traderSessionId,
+        order
+      }) as ApiResponse<ParkedOrderInfo>;
+
+      if (result.success && result.data) {
+        this.addLog(`预埋单已提交: ${order.instrument_id} 编号 ${result.data.parked_order_id}`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`预埋单提交失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`预埋单提交异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  // 预埋撤单，开盘后由柜台自动撤销指定报单
+  async parkCancel(target: string | CancelOrderRequest): Promise<ApiResponse<ParkedCancelInfo>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const cancelRequest: CancelOrderRequest =
+        typeof target === 'string' ? { order_ref: target } : target;
+      const result = await safeInvoke('park_cancel', {
+        sessionId: this.traderSessionId,
+        cancelRequest
+      }) as ApiResponse<ParkedCancelInfo>;
+
+      if (result.success && result.data) {
+        this.addLog(`预埋撤单已提交: 编号 ${result.data.parked_order_action_id}`, LogLevel.Info, result.data);
+      } else {
+        this.addLog(`预埋撤单提交失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`预埋撤单提交异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async queryParkedOrders(): Promise<ApiResponse<ParkedOrders>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      return await safeInvoke('query_parked_orders', {
+        sessionId: this.traderSessionId
+      }) as ApiResponse<ParkedOrders>;
+    } catch (error) {
+      this.addLog(`查询预埋单异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async removeParkedOrder(parkedOrderId: string): Promise<ApiResponse<string>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('remove_parked_order', {
+        sessionId: this.traderSessionId,
+        parkedOrderId
+      }) as ApiResponse<string>;
+
+      if (result.success) {
+        this.addLog(`预埋单已删除: ${parkedOrderId}`, LogLevel.Info);
+      } else {
+        this.addLog(`预埋单删除失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`预埋单删除异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async removeParkedCancel(parkedOrderActionId: string): Promise<ApiResponse<string>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('remove_parked_cancel', {
+        sessionId: this.traderSessionId,
+        parkedOrderActionId
+      }) as ApiResponse<string>;
+
+      if (result.success) {
+        this.addLog(`预埋撤单已删除: ${parkedOrderActionId}`, LogLevel.Info);
+      } else {
+        this.addLog(`预埋撤单删除失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`预埋撤单删除异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async getCancelCounts(): Promise<ApiResponse<CancelCount[]>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      return await safeInvoke('get_cancel_counts', {
+        sessionId: this.traderSessionId
+      }) as ApiResponse<CancelCount[]>;
+    } catch (error) {
+      this.addLog(`获取撤单次数异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async setCancelLimits(limits: CancelLimits): Promise<ApiResponse<CancelCount[]>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('set_cancel_limits', {
+        sessionId: this.traderSessionId,
+        limits
+      }) as ApiResponse<CancelCount[]>;
+
+      if (result.success) {
+        this.addLog(`撤单限制已更新: 上限 ${limits.max_cancels}，预警 ${limits.warn_at}，模式 ${limits.mode}`, LogLevel.Info, limits);
+      } else {
+        this.addLog(`更新撤单限制失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`更新撤单限制异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async getRiskConfig(): Promise<ApiResponse<RiskConfig>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      return await safeInvoke('get_risk_config', {
+        sessionId: this.traderSessionId
+      }) as ApiResponse<RiskConfig>;
+    } catch (error) {
+      this.addLog(`获取风控参数异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
+  async setRiskConfig(config: RiskConfig): Promise<ApiResponse<RiskConfig>> {
+    if (!this.traderSessionId) {
+      const error = '请先创建交易API';
+      this.addLog(error, LogLevel.Error);
+      return { success: false, error };
+    }
+
+    try {
+      const result = await safeInvoke('set_risk_config', {
+        sessionId: this.traderSessionId,
+        config
+      }) as ApiResponse<RiskConfig>;
+
+      if (result.success) {
+        this.addLog('风控参数已更新', LogLevel.Info, config);
+      } else {
+        this.addLog(`更新风控参数失败: ${result.error}`, LogLevel.Error, result);
+      }
+      return result;
+    } catch (error) {
+      this.addLog(`更新风控参数异常: ${error}`, LogLevel.Error, error);
+      return {
+        success: false,
+        error: String(error)
+      };
+    }
+  }
+
   // 查询账户资金
   async queryAccount(): Promise<ApiResponse<AccountInfo>> {
     if (!this.traderSessionId) {
@@ -17,16 +17,336 @@ export interface ApiResponse<T> {
   error?: string;
 }
 
+// trader_login 成功后返回的登录应答
+export interface TraderLoginInfo {
+  front_id: number;
+  session_id: number;
+  max_order_ref: string;
+  trading_day: string;
+  login_time: string;
+  system_name: string;
+}
+
 export interface MarketDataRequest {
   instrument_ids: string[];
 }
 
+// CTP 单字符标志，取值与 THOST_FTDC_* 常量一致，后端拒绝未知取值
+export type Direction = '0' | '1'; // 买 / 卖
+export type OffsetFlag = '0' | '1' | '2' | '3' | '4' | '5' | '6'; // 开 / 平 / 强平 / 平今 / 平昨 / 强减 / 本地强平
+export type HedgeFlag = '1' | '2' | '3' | '5' | '6' | '7'; // 投机 / 套利 / 套保 / 做市商 / 投机套保 / 套保投机
+export type TimeCondition = '1' | '2' | '3' | '4' | '5' | '6'; // IOC / GFS / GFD / GTD / GTC / GFA
+export type VolumeCondition = '1' | '2' | '3'; // 任意数量 / 最小数量 / 全部数量
+export type OrderPriceType =
+  | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9'
+  | 'A' | 'B' | 'C' | 'D' | 'E' | 'F' | 'G'; // '1' 任意价（市价）, '2' 限价
+export type ContingentCondition =
+  | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9'
+  | 'A' | 'B' | 'C' | 'D' | 'E' | 'F' | 'H'; // '1' 立即
+
 export interface OrderRequest {
   instrument_id: string;
-  direction: string; // "0" for buy, "1" for sell
+  direction: Direction;
+  price: number;
+  volume: number;
+  order_type: OrderPriceType; // "2" for limit price, "1" for any price (market)
+  offset_flag?: OffsetFlag; // 默认开仓
+  hedge_flag?: HedgeFlag; // 默认投机
+  time_condition?: TimeCondition; // 默认当日有效
+  volume_condition?: VolumeCondition; // 默认任意数量
+  contingent_condition?: ContingentCondition; // 默认立即；其他取值为柜台条件单
+  stop_price?: number; // 条件单触发价，柜台条件单必填
+}
+
+// 平仓请求，后端按持仓拆成平今/平昨子单（上期所、能源中心）或一笔平仓
+export interface CloseRequest {
+  instrument_id: string;
+  direction: Direction; // "1" 卖出平多，"0" 买入平空
+  price: number;
+  volume: number;
+  order_type: OrderPriceType;
+  hedge_flag?: HedgeFlag;
+  time_condition?: TimeCondition;
+}
+
+export interface OrderKey {
+  front_id: number;
+  session_id: number;
+  order_ref: string;
+}
+
+export interface CloseChild {
+  offset_flag: OffsetFlag;
+  volume: number;
+  key?: OrderKey; // 未能发出的子单只有 error
+  order_status: string;
+  filled_volume: number;
+  error?: string;
+}
+
+export interface CloseParent {
+  parent_id: string;
+  instrument_id: string;
+  exchange_id: string;
+  direction: Direction;
+  price: number;
+  volume: number;
+  filled_volume: number;
+  created_at: number;
+  children: CloseChild[];
+}
+
+// 本地条件单：止损市价、止损限价、触价单，由后端按行情触发
+export type TriggerKind = 'stop_market' | 'stop_limit' | 'market_if_touched';
+export type TriggerStatus = 'pending' | 'triggered' | 'failed' | 'canceled';
+
+export interface LocalTriggerRequest {
+  instrument_id: string;
+  direction: Direction;
+  kind: TriggerKind;
+  trigger_price: number;
+  limit_price?: number; // 仅止损限价单使用
+  volume: number;
+  offset_flag?: OffsetFlag;
+  hedge_flag?: HedgeFlag;
+}
+
+export interface LocalTrigger {
+  trigger_id: string;
+  account_key: string;
+  instrument_id: string;
+  direction: Direction;
+  kind: TriggerKind;
+  trigger_price: number;
+  limit_price: number | null;
+  volume: number;
+  offset_flag: OffsetFlag | null;
+  hedge_flag: HedgeFlag | null;
+  status: TriggerStatus;
+  created_at: number;
+  triggered_at: number | null;
+  triggered_price: number | null;
+  order_ref: string | null;
+  error: string | null;
+}
+
+// 追价单：限价单挂在本方最优价或对手价，行情变化时由后端撤单重报
+export type ChasePeg = 'best' | 'opposite';
+export type ChaseStatus = 'working' | 'resting' | 'filled' | 'canceled' | 'failed';
+
+export interface ChaseRequest {
+  instrument_id: string;
+  direction: Direction;
+  volume: number;
+  offset_flag?: OffsetFlag;
+  hedge_flag?: HedgeFlag;
+  peg?: ChasePeg;
+  max_slippage?: number; // 相对首笔子单价格的最大价差
+  max_reprices?: number; // 默认 20 次
+}
+
+export interface ChaseChild {
+  key: OrderKey;
   price: number;
   volume: number;
-  order_type: string; // "1" for limit order
+  order_status: string;
+  filled_volume: number;
+}
+
+export interface PriceChase {
+  chase_id: string;
+  instrument_id: string;
+  direction: Direction;
+  volume: number;
+  offset_flag: OffsetFlag | null;
+  hedge_flag: HedgeFlag | null;
+  peg: ChasePeg;
+  max_slippage: number | null;
+  max_reprices: number;
+  initial_price: number;
+  reprices: number;
+  filled_volume: number;
+  status: ChaseStatus;
+  created_at: number;
+  children: ChaseChild[];
+  error: string | null;
+}
+
+// 算法单：TWAP 按时间切片报出，冰山单每次只显示一部分数量
+export type AlgoKind = 'twap' | 'iceberg';
+export type AlgoStatus = 'running' | 'paused' | 'completed' | 'canceled' | 'failed';
+
+export interface AlgoRequest {
+  instrument_id: string;
+  direction: Direction;
+  price: number; // 子单限价
+  volume: number;
+  offset_flag?: OffsetFlag;
+  hedge_flag?: HedgeFlag;
+  kind: AlgoKind;
+  clip_size?: number; // 冰山单显示数量
+  duration_secs?: number; // TWAP 执行时长
+  slices?: number; // TWAP 切片数
+}
+
+export interface AlgoChild {
+  key: OrderKey;
+  volume: number;
+  order_status: string;
+  filled_volume: number;
+  error: string | null;
+  canceled_by_algo: boolean;
+}
+
+export interface AlgoOrder {
+  algo_id: string;
+  instrument_id: string;
+  direction: Direction;
+  price: number;
+  volume: number;
+  offset_flag: OffsetFlag | null;
+  hedge_flag: HedgeFlag | null;
+  kind: AlgoKind;
+  clip_size: number | null;
+  duration_secs: number | null;
+  slices: number | null;
+  filled_volume: number;
+  working_volume: number;
+  status: AlgoStatus;
+  created_at: number;
+  children: AlgoChild[];
+  error: string | null;
+}
+
+// 预埋单：开盘前存到柜台，开盘后由柜台自动发出
+// 状态 '1' 未发送，'2' 已发送，'3' 已删除
+export type ParkedStatus = '1' | '2' | '3';
+
+export interface ParkedOrderInfo {
+  parked_order_id: string;
+  instrument_id: string;
+  exchange_id: string;
+  direction: string;
+  combine_offset_flag: string;
+  combine_hedge_flag: string;
+  order_price_type: string;
+  limit_price: number;
+  volume_total_original: number;
+  time_condition: string;
+  volume_condition: string;
+  contingent_condition: string;
+  stop_price: number;
+  status: ParkedStatus;
+  status_text: string;
+  error_id: number;
+  error_msg: string;
+}
+
+export interface ParkedCancelInfo {
+  parked_order_action_id: string;
+  instrument_id: string;
+  exchange_id: string;
+  order_sys_id: string;
+  order_ref: string;
+  front_id: number;
+  session_id: number;
+  status: ParkedStatus;
+  status_text: string;
+  error_id: number;
+  error_msg: string;
+}
+
+export interface ParkedOrders {
+  orders: ParkedOrderInfo[];
+  cancels: ParkedCancelInfo[];
+}
+
+// 撤单次数上限和预警值（每个合约）
+export type CancelMode = 'limited' | 'unlimited';
+
+export interface CancelLimits {
+  max_cancels: number;
+  warn_at: number;
+  mode: CancelMode;
+}
+
+// 某个合约当日的撤单次数，由后端根据撤单回报计数
+export interface CancelCount {
+  instrument_id: string;
+  trading_day: string;
+  count: number;
+  max_cancels: number;
+  warn_at: number;
+  level: 'normal' | 'warning' | 'blocked';
+}
+
+// 报单前风控参数，为 null 的限制不检查
+export interface RiskConfig {
+  enabled: boolean;
+  max_order_volume: number | null;
+  check_price_limits: boolean;
+  fat_finger_ratio: number | null;
+  max_net_position: number | null;
+  product_net_position: Record<string, number>;
+  max_open_orders: number | null;
+  max_orders_per_second: number | null;
+}
+
+// 新报单会与本账户反向挂单成交时的处理：拒绝新报单，或先撤掉挂单
+export type SelfTradeAction = 'reject' | 'cancel_resting';
+
+export interface OrderInsertResult {
+  order_ref: string;
+  front_id: number;
+  session_id: number;
+  request_id: number;
+  order_sys_id: string; // 等待确认时为交易所报单编号
+  order_status: string;
+  acknowledged: boolean;
+}
+
+// 撤单只需 order_ref（默认本会话的 FrontID/SessionID）或 exchange_id + order_sys_id
+export interface CancelOrderRequest {
+  order_ref?: string;
+  front_id?: number;
+  session_id?: number;
+  exchange_id?: string;
+  order_sys_id?: string;
+}
+
+export interface OrderCancelResult {
+  order_ref: string;
+  front_id: number;
+  session_id: number;
+  exchange_id: string;
+  order_sys_id: string;
+  action_ref: number;
+  request_id: number;
+  order_status: string;
+  volume_traded: number;
+}
+
+// 批量撤单的筛选条件，未设置的条件不参与筛选
+export interface OrderFilter {
+  instrument_id?: string;
+  direction?: string; // "0" for buy, "1" for sell
+  price_min?: number;
+  price_max?: number;
+  investor_id?: string;
+}
+
+export interface CancelOutcome {
+  order_ref: string;
+  front_id: number;
+  session_id: number;
+  exchange_id: string;
+  order_sys_id: string;
+  instrument_id: string;
+  direction: string;
+  limit_price: number;
+  success: boolean;
+  order_status: string;
+  error?: string;
 }
 
 export interface MarketDataInfo {
@@ -54,6 +374,24 @@ export interface MarketDataInfo {
   action_day: string;
 }
 
+// 柜台条件单触发后报单失败，对应 conditional-order-error 事件
+export interface ConditionalOrderError {
+  order_ref: string;
+  front_id: number;
+  session_id: number;
+  instrument_id: string;
+  exchange_id: string;
+  order_sys_id: string;
+  direction: string;
+  contingent_condition: string;
+  stop_price: number;
+  limit_price: number;
+  volume_total_original: number;
+  order_status: string;
+  error_id: number;
+  error_msg: string;
+}
+
 export interface OrderInfo {
   order_ref: string;
   user_id: string;
@@ -299,6 +637,30 @@ export type CtpCommand =
   | 'unsubscribe_market_data'
   | 'insert_order'
   | 'cancel_order'
+  | 'cancel_orders'
+  | 'cancel_all_orders'
+  | 'close_position'
+  | 'list_close_orders'
+  | 'place_local_trigger'
+  | 'cancel_local_trigger'
+  | 'list_local_triggers'
+  | 'park_order'
+  | 'park_cancel'
+  | 'query_parked_orders'
+  | 'remove_parked_order'
+  | 'remove_parked_cancel'
+  | 'start_price_chase'
+  | 'cancel_price_chase'
+  | 'list_price_chases'
+  | 'start_algo_order'
+  | 'pause_algo_order'
+  | 'resume_algo_order'
+  | 'cancel_algo_order'
+  | 'list_algo_orders'
+  | 'get_cancel_counts'
+  | 'set_cancel_limits'
+  | 'get_risk_config'
+  | 'set_risk_config'
   | 'query_position'
   | 'query_account'
   | 'query_order'
@@ -95,7 +95,7 @@ export class TradingPanelTest {
         direction: '0', // 买入
         price: 3070,
         volume: 1,
-        order_type: '1' // 限价单
+        order_type: '2' // 限价单
       }
 
       console.log('📤 模拟下单请求:', testOrder)